@@ -0,0 +1,87 @@
+//! Throughput of the checksum, of sending and of receiving packets, run with
+//! `cargo bench --features std`.
+
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
+};
+use flem::checksum::{Checksum, Crc16Ibm};
+use flem::Packet;
+
+const SMALL: usize = 16;
+const MEDIUM: usize = 1024;
+/// The largest size benchmarked. Bigger packets work, but make the benchmark slow to compile.
+const LARGE: usize = 8192;
+
+fn packed<const T: usize>() -> Packet<T> {
+    let payload: Vec<u8> = (0..T).map(|index| index as u8).collect();
+    let mut tx = Packet::<T>::new();
+    tx.pack_data(0x10, &payload).unwrap();
+    tx
+}
+
+fn checksum(c: &mut Criterion) {
+    let payload = [0x5A_u8; 1024];
+    let mut group = c.benchmark_group("checksum");
+    group.throughput(Throughput::Bytes(payload.len() as u64));
+
+    group.bench_function("crc16_ibm_1k", |b| {
+        b.iter(|| {
+            let mut crc = Crc16Ibm::default();
+            crc.update(black_box(&payload));
+            crc.finalize()
+        })
+    });
+
+    let mut tx = packed::<MEDIUM>();
+    group.bench_function("packet_1k", |b| {
+        b.iter(|| black_box(&mut tx).checksum(false))
+    });
+    group.finish();
+}
+
+fn round_trip<const T: usize>(c: &mut Criterion, name: &str) {
+    let tx = packed::<T>();
+    let mut group = c.benchmark_group("round_trip");
+    group.throughput(Throughput::Bytes(tx.length() as u64));
+
+    group.bench_function(BenchmarkId::new("construct", name), |b| {
+        let mut rx = Packet::<T>::new();
+        b.iter(|| {
+            for byte in tx.bytes() {
+                let _ = rx.construct(*byte);
+            }
+            rx.get_request()
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("get_byte", name), |b| {
+        b.iter_batched_ref(
+            || tx,
+            |tx| {
+                // Packs as finalize() does, so the two compare sending the frame
+                tx.pack();
+                let mut sum = 0_u32;
+                while let Ok(byte) = tx.get_byte() {
+                    sum += byte as u32;
+                }
+                sum
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function(BenchmarkId::new("finalize", name), |b| {
+        let mut tx = tx;
+        b.iter(|| tx.finalize().iter().map(|byte| *byte as u32).sum::<u32>())
+    });
+    group.finish();
+}
+
+fn round_trips(c: &mut Criterion) {
+    round_trip::<SMALL>(c, "small");
+    round_trip::<MEDIUM>(c, "medium");
+    round_trip::<LARGE>(c, "large");
+}
+
+criterion_group!(benches, checksum, round_trips);
+criterion_main!(benches);
@@ -1,4 +1,3 @@
-use flem;
 use flem::buffer::le_buffer_to_u32;
 use flem::traits::{DataInterface, DataInterfaceErrors};
 use flem::*;
@@ -8,7 +7,7 @@ use flem::*;
 const FLEM_PACKET_SIZE: usize = 100;
 
 pub mod request_projectx {
-    pub const GET_DIAGNOSTICS: u16 = 10;
+    pub const GET_DIAGNOSTICS: u16 = 0x10;
 }
 
 /// Task times in milliseconds
@@ -18,6 +17,12 @@ pub struct Diagnostics {
     task_3: u32,
 }
 
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Diagnostics {
     const DIAGNOSTICS_SIZE_BYTES: usize = 12;
 
@@ -85,15 +90,13 @@ fn main() {
             Err(status) => {
                 // We expect Status::PacketBuilding
                 if status != Status::PacketBuilding {
-                    assert!(true, "An error shouldn't have occurred in this example");
+                    panic!("An error shouldn't have occurred in this example");
                 }
             }
         }
     }
 
-    client_tx.reset_lazy();
-    client_tx.set_request(client_rx.get_request());
-    client_tx.set_response(flem::response::SUCCESS);
+    client_tx.respond_to(&client_rx);
     // Encodes the struct into the packet
     client_diagnostics.encode(&mut client_tx).unwrap();
     // Compute and store the checksum
@@ -107,7 +110,7 @@ fn main() {
             Err(result) => {
                 // We expect Status::PacketBuilding
                 if result != Status::PacketBuilding {
-                    assert!(true, "An error shouldn't have occurred in this example");
+                    panic!("An error shouldn't have occurred in this example");
                 }
             }
         }
@@ -1,4 +1,4 @@
-use flem;
+use flem::sim::LoopbackLink;
 use flem::*;
 
 use std::iter::FromIterator;
@@ -8,7 +8,7 @@ use std::iter::FromIterator;
 const FLEM_PACKET_SIZE: usize = 100;
 
 pub mod host_requests {
-    pub const GET_DATA: u16 = 10;
+    pub const GET_DATA: u16 = 0x10;
 }
 
 pub mod client_requests {
@@ -36,30 +36,15 @@ fn main() {
     host_tx.set_request(flem::request::ID); // Change this for different responses from the client
     host_tx.pack(); // Pack runs checksum and after that it is ready to send
 
-    // Simulates byte-by-byte tranmission
-    for _i in 0..host_tx.length() {
-        let mut next_byte: u8 = 0;
-        match host_tx.get_byte() {
-            Ok(byte) => {
-                next_byte = byte;
-            }
-            Err(_) => {
-                assert!(false, "get_byte() finished");
-            }
+    // Simulates byte-by-byte tranmission, transmit from host / receive on client.
+    // The hardware bus / protocol (I2C, UART, etc) goes here.
+    let statuses = LoopbackLink::new(&host_tx, &mut client_rx).run();
+    match statuses.last() {
+        Some(Status::PacketReceived) => {
+            println!("Packet received successfully!");
         }
-
-        /* Hardware bus / protocol (I2C, UART, etc) goes here */
-
-        //Transmit from host / receive on client
-        match client_rx.construct(next_byte) {
-            Ok(_) => {
-                println!("Packet received successfully!");
-            }
-            Err(status) => {
-                if status != Status::PacketBuilding {
-                    println!("Packet error occurred!");
-                }
-            }
+        _ => {
+            println!("Packet error occurred!");
         }
     }
     host_tx.reset_lazy(); // Reset the host_tx so it can be used again
@@ -72,7 +57,7 @@ fn main() {
         }
         host_requests::GET_DATA => {
             // Custom command implemented for this project (Project X)
-            let project_x_data = [0 as u8; 40];
+            let project_x_data = [0_u8; 40];
             client_tx
                 .pack_data(client_rx.get_request(), &project_x_data)
                 .unwrap_or_else(|error| {
@@ -82,11 +67,7 @@ fn main() {
         }
         _ => {
             client_tx
-                .pack_error(
-                    client_rx.get_request(),
-                    flem::response::UNKNOWN_REQUEST,
-                    &[],
-                )
+                .pack_error_code(client_rx.get_request(), flem::response::UNKNOWN_REQUEST)
                 .unwrap_or_else(|error| {
                     println!("Error packing the error with code: {:?}", error);
                 });
@@ -95,45 +76,39 @@ fn main() {
     client_rx.reset_lazy(); // Reset the client_rx packet so it can be used again
 
     /* Send response back to host */
-    for byte in client_tx.bytes() {
-        // ** Byte is transmitting over hardware **
-
-        // ** Byte received by host, construct the
-        match host_rx.construct(*byte) {
-            Ok(_) => {
-                // Determine what to do with the received packet
-                match host_rx.get_request() {
-                    request::ID => {
-                        let host_size_data_id = flem::DataId::from(&host_rx.get_data()).unwrap();
-                        println!(
-                            "DataId Message: {}, max packet size: {}, Major: {}, Minor: {}, Patch: {}", 
-                            String::from_iter(host_size_data_id.get_name().iter()),
-                            host_size_data_id.get_max_packet_size(),
-                            host_size_data_id.get_major(),
-                            host_size_data_id.get_minor(),
-                            host_size_data_id.get_patch()
-                        );
-                    }
-                    host_requests::GET_DATA => {
-                        // Custom command implemented for this project (Project X)
-                        // Do something with the requested data
-                    }
-                    _ => {
-                        // Uh oh
-                    }
+    // ** Bytes are transmitting over hardware, received by host **
+    let statuses = LoopbackLink::new(&client_tx, &mut host_rx).run();
+    match statuses.last() {
+        Some(Status::PacketReceived) => {
+            // Determine what to do with the received packet
+            match host_rx.get_request() {
+                request::ID => {
+                    let host_size_data_id = host_rx.parse_id().unwrap();
+                    println!(
+                        "DataId Message: {}, max packet size: {}, Major: {}, Minor: {}, Patch: {}",
+                        String::from_iter(host_size_data_id.get_name().iter()),
+                        host_size_data_id.get_max_packet_size(),
+                        host_size_data_id.get_major(),
+                        host_size_data_id.get_minor(),
+                        host_size_data_id.get_patch()
+                    );
                 }
-
-                host_rx.reset_lazy(); // Reset the host_rx so it can be used again
-            }
-            Err(status) => {
-                /* Catch other errors here */
-
-                if status != Status::PacketBuilding {
-                    println!("Packet error occurred!");
-                    // Usually good to reset the packet after an issue
-                    host_rx.reset_lazy();
+                host_requests::GET_DATA => {
+                    // Custom command implemented for this project (Project X)
+                    // Do something with the requested data
+                }
+                _ => {
+                    // Uh oh
                 }
             }
+
+            host_rx.reset_lazy(); // Reset the host_rx so it can be used again
+        }
+        _ => {
+            /* Catch other errors here */
+            println!("Packet error occurred!");
+            // Usually good to reset the packet after an issue
+            host_rx.reset_lazy();
         }
     }
     client_tx.reset_lazy(); // Reset the client_tx so it can be used again
@@ -30,12 +30,10 @@ impl<const PACKET_SIZE: usize> Channel<PACKET_SIZE> for FlemSoftwareHost<PACKET_
     type Error = ();
 
     fn list_devices(&self) -> Vec<String> {
-        let mut devices = Vec::<String>::new();
-        devices.push(String::from("Software Host"));
-        devices
+        vec![String::from("Software Host")]
     }
 
-    fn connect(&mut self, device: &String) -> Result<(), Self::Error> {
+    fn connect(&mut self, _device: &String) -> Result<(), Self::Error> {
         Ok(())
     }
 
@@ -70,7 +68,7 @@ impl<const PACKET_SIZE: usize> Channel<PACKET_SIZE> for FlemSoftwareHost<PACKET_
         let listening_clone_device = self.listening.clone();
 
         // Tx Thread - Transmit packets to the "device"
-        let tx_handle = thread::spawn(move || {
+        let _tx_handle = thread::spawn(move || {
             while *listening_clone_tx.lock().unwrap() {
                 // Check if there is a packet to transmit, use recv_timeout to prevent a blocking thread
                 if let Ok(tx_packet) =
@@ -89,9 +87,9 @@ impl<const PACKET_SIZE: usize> Channel<PACKET_SIZE> for FlemSoftwareHost<PACKET_
             }
         });
 
-        let device_flem_handler = self.flem_packet_handler.clone();
+        let device_flem_handler = self.flem_packet_handler;
 
-        let simulated_device_thread = thread::spawn(move || {
+        let _simulated_device_thread = thread::spawn(move || {
             let mut packet = flem::Packet::<PACKET_DEVICE_SIZE>::new();
 
             while *listening_clone_device.lock().unwrap() {
@@ -107,14 +105,8 @@ impl<const PACKET_SIZE: usize> Channel<PACKET_SIZE> for FlemSoftwareHost<PACKET_
                                 packet.get_checksum()
                             );
 
-                            if device_flem_handler.is_none() {
-                                println!("Packet handler not set, working as a loop-back");
-                                for byte in packet.bytes() {
-                                    simulated_hardware_device_tx.send(*byte).unwrap();
-                                }
-                            } else {
+                            if let Some(handler) = device_flem_handler.as_ref() {
                                 println!("Packet handler set, calling handler");
-                                let handler = device_flem_handler.as_ref().unwrap();
                                 let response = handler(&packet);
                                 for byte in response.bytes() {
                                     simulated_hardware_device_tx.send(*byte).unwrap();
@@ -123,6 +115,11 @@ impl<const PACKET_SIZE: usize> Channel<PACKET_SIZE> for FlemSoftwareHost<PACKET_
                                     "Raw packet from device to host in bytes: {:?}",
                                     response.bytes()
                                 );
+                            } else {
+                                println!("Packet handler not set, working as a loop-back");
+                                for byte in packet.bytes() {
+                                    simulated_hardware_device_tx.send(*byte).unwrap();
+                                }
                             }
 
                             println!("Packet sent from device successfully");
@@ -146,7 +143,7 @@ impl<const PACKET_SIZE: usize> Channel<PACKET_SIZE> for FlemSoftwareHost<PACKET_
         });
 
         // Rx Thread - Receive packets from the "device"
-        let rx_handle = thread::spawn(move || {
+        let _rx_handle = thread::spawn(move || {
             let mut packet = flem::Packet::<PACKET_SIZE>::new();
 
             while *listening_clone_rx.lock().unwrap() {
@@ -161,7 +158,7 @@ impl<const PACKET_SIZE: usize> Channel<PACKET_SIZE> for FlemSoftwareHost<PACKET_
                                 "Packet received successfully with checksum {}",
                                 packet.get_checksum()
                             );
-                            validated_packet.send(packet);
+                            validated_packet.send(packet).unwrap();
 
                             println!("Packet sent to program");
 
@@ -206,7 +203,7 @@ fn main() {
                 // Respond with ID
                 response.set_request(flem::request::ID);
                 response.set_response(flem::response::SUCCESS);
-                response.pack_id(&id, true);
+                response.pack_id(&id, true).unwrap();
             }
             _ => {
                 response.set_request(flem::request::ID);
@@ -237,7 +234,7 @@ fn main() {
             // Do stuff with the packet
             match packet.get_request() {
                 flem::request::ID => {
-                    let id = DataId::from(&packet.get_data()).unwrap();
+                    let id = packet.parse_id().unwrap();
                     println!(
                         "DataId Message: {}, max packet size: {}, Major: {}, Minor: {}, Patch: {}",
                         String::from_iter(id.get_name().iter()),
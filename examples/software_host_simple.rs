@@ -34,12 +34,10 @@ impl<const PACKET_SIZE: usize> Channel<PACKET_SIZE> for FlemSoftwareHost<PACKET_
     type Error = ();
 
     fn list_devices(&self) -> Vec<String> {
-        let mut devices = Vec::<String>::new();
-        devices.push(String::from("Software Host"));
-        devices
+        vec![String::from("Software Host")]
     }
 
-    fn connect(&mut self, device: &String) -> Result<(), Self::Error> {
+    fn connect(&mut self, _device: &String) -> Result<(), Self::Error> {
         Ok(())
     }
 
@@ -47,7 +45,7 @@ impl<const PACKET_SIZE: usize> Channel<PACKET_SIZE> for FlemSoftwareHost<PACKET_
         Ok(())
     }
 
-    fn listen(&mut self, rx_sleep_time_ms: u64, tx_sleep_time_ms: u64,) -> (Sender<Packet<PACKET_SIZE>>, Receiver<Packet<PACKET_SIZE>>) {        
+    fn listen(&mut self, _rx_sleep_time_ms: u64, tx_sleep_time_ms: u64,) -> (Sender<Packet<PACKET_SIZE>>, Receiver<Packet<PACKET_SIZE>>) {        
         // Tx packets are marshalled into a single queue, and dispatched over hardware.
         let (tx_packet_from_program, packet_to_transmit) = mpsc::channel::<flem::Packet<PACKET_SIZE>>();
 
@@ -58,21 +56,20 @@ impl<const PACKET_SIZE: usize> Channel<PACKET_SIZE> for FlemSoftwareHost<PACKET_
 
         let listening_clone = self.listening.clone();
 
-        let device_flem_handler = self.flem_packet_handler.clone();
+        let device_flem_handler = self.flem_packet_handler;
 
         // Tx Thread - Transmit packets to the "device"
-        let device_handle = thread::spawn(move || {            
+        let _device_handle = thread::spawn(move || {            
             while *listening_clone.lock().unwrap() {
                 // Check if there is a packet to transmit, use recv_timeout to prevent a blocking thread
                 if let Ok(tx_packet) = packet_to_transmit.recv_timeout(Duration::from_millis(tx_sleep_time_ms)) {
-                    if device_flem_handler.is_none() {
-                        println!("Packet handler not set, working as a loop-back");
-                        validated_packet.send(tx_packet);
-                    }else{
+                    if let Some(handler) = device_flem_handler.as_ref() {
                         println!("Packet handler set, calling handler");
-                        let handler = device_flem_handler.as_ref().unwrap();
                         let response = handler(&tx_packet);
-                        validated_packet.send(response);
+                        validated_packet.send(response).unwrap();
+                    }else{
+                        println!("Packet handler not set, working as a loop-back");
+                        validated_packet.send(tx_packet).unwrap();
                     }
                 }
             }
@@ -101,7 +98,7 @@ fn main() {
                 // Respond with ID
                 response.set_request(flem::request::ID);
                 response.set_response(flem::response::SUCCESS);
-                response.pack_id(&id, true);
+                response.pack_id(&id, true).unwrap();
             },
             _ => {
                 response.set_request(packet.get_request());
@@ -133,7 +130,7 @@ fn main() {
              // Do stuff with the packet
             match packet.get_request() {
                 flem::request::ID => {
-                    let id = DataId::from(&packet.get_data()).unwrap();
+                    let id = packet.parse_id().unwrap();
                     println!(
                         "DataId Message: {}, max packet size: {}, Major: {}, Minor: {}, Patch: {}", 
                         String::from_iter(id.get_name().iter()),
@@ -0,0 +1,30 @@
+//! Feeds arbitrary byte streams to `Packet::construct` and the slice parsers. None of them
+//! may panic, and every packet `construct` accepts must be a valid frame.
+//!
+//! Run with `cargo +nightly fuzz run construct` from the repository root.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fn feed<const T: usize>(data: &[u8]) {
+    let mut packet = flem::Packet::<T>::new();
+    for byte in data {
+        if packet.construct(*byte).is_ok() {
+            assert!(packet.length() <= flem::FLEM_HEADER_SIZE + T);
+            assert_eq!(flem::validate_slice(packet.bytes()), Ok(()));
+            let _ = packet.expected_remaining();
+            packet.take().unwrap();
+        }
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    // No payload, a small payload and a typical payload
+    feed::<0>(data);
+    feed::<4>(data);
+    feed::<64>(data);
+
+    let _ = flem::PacketHeader::parse(data);
+    let _ = flem::validate_slice(data);
+});
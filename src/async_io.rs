@@ -0,0 +1,142 @@
+//! Sends and receives packets over `embedded_io_async` streams, for example an async UART
+//! with Embassy. Requires features = ["async"].
+
+use embedded_io_async::{Read, Write};
+
+use crate::{Packet, Status};
+
+/// Bytes read from the stream at a time
+const RX_CHUNK: usize = 32;
+/// Bytes written to the stream at a time
+const TX_CHUNK: usize = 32;
+
+/// Sends packets
+#[allow(async_fn_in_trait)]
+pub trait AsyncPacketTx<const T: usize> {
+    /// Sends a packed packet, using `get_byte()` to walk the packet. Returns
+    /// `Status::IoError` if the stream fails.
+    async fn send(&mut self, tx: &mut Packet<T>) -> Result<(), Status>;
+}
+
+/// Receives packets
+#[allow(async_fn_in_trait)]
+pub trait AsyncPacketRx<const T: usize> {
+    /// Waits for a complete, validated packet. Bytes before the header are skipped.
+    /// Returns a packet error (`ChecksumError`, `InvalidDataLengthDetected`, ...) or
+    /// `Status::IoError` if the stream fails or ends.
+    async fn recv(&mut self) -> Result<(), Status>;
+
+    /// The packet received by the last successful `recv()`
+    fn packet(&self) -> &Packet<T>;
+}
+
+/// Wraps an async stream and a receive packet. Implements `AsyncPacketTx` if the stream is
+/// `embedded_io_async::Write` and `AsyncPacketRx` if it is `embedded_io_async::Read`.
+///
+/// Bytes read past the end of a packet are kept for the next `recv()`.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use flem::async_io::{AsyncLink, AsyncPacketRx, AsyncPacketTx};
+///     use flem::Packet;
+///
+///     pollster::block_on(async {
+///         let mut tx = Packet::<16>::new();
+///         tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+///
+///         let mut stream = [0u8; 64];
+///         let mut writer = AsyncLink::<_, 16>::new(&mut stream[..]);
+///         writer.send(&mut tx).await.unwrap();
+///
+///         let mut reader = AsyncLink::<_, 16>::new(&stream[..]);
+///         reader.recv().await.unwrap();
+///         assert_eq!(*reader.packet(), tx);
+///     });
+/// }
+/// ```
+pub struct AsyncLink<IO, const T: usize> {
+    io: IO,
+    packet: Packet<T>,
+    buffer: [u8; RX_CHUNK],
+    start: usize,
+    end: usize,
+}
+
+impl<IO, const T: usize> AsyncLink<IO, T> {
+    pub fn new(io: IO) -> Self {
+        Self {
+            io,
+            packet: Packet::new(),
+            buffer: [0u8; RX_CHUNK],
+            start: 0,
+            end: 0,
+        }
+    }
+
+    /// Moves the received packet out and resets the receive packet, see `Packet::take()`
+    pub fn take(&mut self) -> Result<Packet<T>, Status> {
+        self.packet.take()
+    }
+
+    /// Returns the stream, dropping any bytes read but not yet used
+    pub fn into_inner(self) -> IO {
+        self.io
+    }
+}
+
+impl<IO: Write, const T: usize> AsyncPacketTx<T> for AsyncLink<IO, T> {
+    async fn send(&mut self, tx: &mut Packet<T>) -> Result<(), Status> {
+        let mut chunk = [0u8; TX_CHUNK];
+        loop {
+            let count = tx.get_bytes_into(&mut chunk);
+            if count == 0 {
+                break;
+            }
+
+            self.io
+                .write_all(&chunk[..count])
+                .await
+                .map_err(|_| Status::IoError)?;
+        }
+
+        self.io.flush().await.map_err(|_| Status::IoError)
+    }
+}
+
+impl<IO: Read, const T: usize> AsyncPacketRx<T> for AsyncLink<IO, T> {
+    async fn recv(&mut self) -> Result<(), Status> {
+        self.packet.reset_lazy();
+
+        loop {
+            if self.start == self.end {
+                self.start = 0;
+                self.end = match self.io.read(&mut self.buffer).await {
+                    Ok(0) | Err(_) => {
+                        self.end = 0;
+                        return Err(Status::IoError);
+                    }
+                    Ok(count) => count,
+                };
+            }
+
+            let byte = self.buffer[self.start];
+            self.start += 1;
+
+            match self.packet.construct(byte) {
+                Ok(_) => return Ok(()),
+                Err(Status::PacketBuilding)
+                | Err(Status::HeaderBytesNotFound)
+                | Err(Status::DiscardingOversized) => {}
+                Err(status) => {
+                    self.packet.reset_lazy();
+                    return Err(status);
+                }
+            }
+        }
+    }
+
+    fn packet(&self) -> &Packet<T> {
+        &self.packet
+    }
+}
@@ -0,0 +1,70 @@
+//! Blocking send / receive over `embedded_io` devices. Enabled with features = ["embedded-io"].
+
+use embedded_io::{Error, ErrorKind, Read, Write};
+
+use crate::{Packet, Status};
+
+/// Bytes written to the device at a time
+const TX_CHUNK: usize = 32;
+
+impl<const T: usize> Packet<T> {
+    /// Writes a packed packet to `writer`, using `get_byte()` to walk the packet.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut tx = flem::Packet::<16>::new();
+    ///     tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+    ///
+    ///     let mut uart = [0u8; 64];
+    ///     tx.send_blocking(&mut &mut uart[..]).unwrap();
+    ///
+    ///     let mut rx = flem::Packet::<16>::new();
+    ///     rx.recv_blocking(&mut &uart[..]).unwrap();
+    ///     assert_eq!(rx, tx);
+    /// }
+    /// ```
+    pub fn send_blocking<W: Write>(&mut self, writer: &mut W) -> Result<(), W::Error> {
+        let mut chunk = [0u8; TX_CHUNK];
+        loop {
+            let count = self.get_bytes_into(&mut chunk);
+            if count == 0 {
+                break;
+            }
+            writer.write_all(&chunk[..count])?;
+        }
+        writer.flush()
+    }
+
+    /// Reads from `reader` until this packet holds a complete, validated packet. Bytes
+    /// before the header are skipped. The device is read a byte at a time so no bytes of
+    /// the next packet are consumed, and reads interrupted with `ErrorKind::Interrupted`
+    /// are retried.
+    ///
+    /// Returns a packet error (`ChecksumError`, `InvalidDataLengthDetected`, ...), or
+    /// `Status::IoError` if the device fails or has no more bytes.
+    pub fn recv_blocking<R: Read>(&mut self, reader: &mut R) -> Result<(), Status> {
+        self.reset_lazy();
+
+        let mut byte = [0u8; 1];
+        loop {
+            match reader.read(&mut byte) {
+                Ok(0) => return Err(Status::IoError),
+                Ok(_) => {}
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => return Err(Status::IoError),
+            }
+
+            match self.construct(byte[0]) {
+                Ok(_) => return Ok(()),
+                Err(Status::PacketBuilding)
+                | Err(Status::HeaderBytesNotFound)
+                | Err(Status::DiscardingOversized) => {}
+                Err(status) => {
+                    self.reset_lazy();
+                    return Err(status);
+                }
+            }
+        }
+    }
+}
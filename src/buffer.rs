@@ -1,3 +1,9 @@
+use core::cell::{Cell, UnsafeCell};
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{Packet, Status};
+
 #[derive(Debug, Clone, Copy)]
 pub enum DataBufferErrors {
     NotEnoughRoomInBuffer,
@@ -26,7 +32,7 @@ pub fn f32_to_le_buffer(
 
 /// Convert a little endian byte array from a buffer to an f32
 pub fn le_buffer_to_f32(buffer: &[u8], offset: &mut usize) -> Result<f32, DataBufferErrors> {
-    let mut tmp = [0 as u8; 4];
+    let mut tmp = [0_u8; 4];
 
     if *offset + tmp.len() > buffer.len() {
         Err(DataBufferErrors::ConversionWouldOverflow)
@@ -62,7 +68,7 @@ pub fn u32_to_le_buffer(
 
 /// Convert a little endian byte array from a buffer to an u32
 pub fn le_buffer_to_u32(buffer: &[u8], offset: &mut usize) -> Result<u32, DataBufferErrors> {
-    let mut tmp = [0 as u8; 4];
+    let mut tmp = [0_u8; 4];
 
     if *offset + tmp.len() > buffer.len() {
         Err(DataBufferErrors::ConversionWouldOverflow)
@@ -98,7 +104,7 @@ pub fn i32_to_le_buffer(
 
 /// Convert a little endian byte array from a buffer to an i32
 pub fn le_buffer_to_i32(buffer: &[u8], offset: &mut usize) -> Result<i32, DataBufferErrors> {
-    let mut tmp = [0 as u8; 4];
+    let mut tmp = [0_u8; 4];
 
     if *offset + tmp.len() > buffer.len() {
         Err(DataBufferErrors::ConversionWouldOverflow)
@@ -132,7 +138,7 @@ pub fn u16_to_le_buffer(
 
 /// Convert a little endian byte array from a buffer to an u16
 pub fn le_buffer_to_u16(buffer: &[u8], offset: &mut usize) -> Result<u16, DataBufferErrors> {
-    let mut tmp = [0 as u8; 2];
+    let mut tmp = [0_u8; 2];
 
     if *offset + tmp.len() > buffer.len() {
         Err(DataBufferErrors::ConversionWouldOverflow)
@@ -163,7 +169,7 @@ pub fn i16_to_le_buffer(
 }
 
 pub fn le_buffer_to_i16(buffer: &[u8], offset: &mut usize) -> Result<i16, DataBufferErrors> {
-    let mut tmp = [0 as u8; 2];
+    let mut tmp = [0_u8; 2];
 
     if *offset + tmp.len() > buffer.len() {
         Err(DataBufferErrors::ConversionWouldOverflow)
@@ -174,3 +180,321 @@ pub fn le_buffer_to_i16(buffer: &[u8], offset: &mut usize) -> Result<i16, DataBu
         Ok(i16::from_le_bytes(tmp))
     }
 }
+
+/// Fixed size single-producer / single-consumer byte ring, to pass bytes from an interrupt
+/// to a task without a critical section. Holds up to `N` bytes.
+///
+/// `split()` hands out one `Producer` (usually for the receive interrupt) and one `Consumer`
+/// (usually for a task). The borrow checker keeps it to one of each, so no two contexts
+/// push or pop at the same time. For a ring shared with an interrupt, keep it in a static
+/// that gives out `&'static mut` once (for example `static_cell::StaticCell`) and split it
+/// at start-up. A ring used from a single context can call `push` and `pop` directly.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use flem::buffer::ByteRing;
+///
+///     let mut ring = ByteRing::<64>::new();
+///     let (mut producer, mut consumer) = ring.split();
+///
+///     let mut tx = flem::Packet::<16>::new();
+///     tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+///
+///     // Receive interrupt
+///     for byte in tx.bytes() {
+///         producer.push(*byte).unwrap();
+///     }
+///
+///     // Task
+///     let mut rx = flem::Packet::<16>::new();
+///     assert_eq!(consumer.drain_into_packet(&mut rx), flem::Status::PacketReceived);
+///     assert_eq!(rx, tx);
+/// }
+/// ```
+pub struct ByteRing<const N: usize> {
+    buffer: UnsafeCell<[u8; N]>,
+    /// Bytes pushed modulo 2 * N, only written by the producer
+    write: AtomicUsize,
+    /// Bytes popped modulo 2 * N, only written by the consumer
+    read: AtomicUsize,
+}
+
+// SAFETY: Through a shared reference only the atomic counters are read. Pushing and
+// popping need `&mut ByteRing`, the `Producer` or the `Consumer`, of which `split()` hands
+// out one each. The producer only writes the slot at `write` before publishing it, the
+// consumer only reads the slot at `read` after it was published, so the two never touch
+// the same slot at the same time.
+unsafe impl<const N: usize> Sync for ByteRing<N> {}
+
+impl<const N: usize> ByteRing<N> {
+    pub const fn new() -> Self {
+        assert!(N > 0, "<N> should be at least 1");
+        assert!(N <= usize::MAX / 2, "<N> should be at most usize::MAX / 2");
+        Self {
+            buffer: UnsafeCell::new([0u8; N]),
+            write: AtomicUsize::new(0),
+            read: AtomicUsize::new(0),
+        }
+    }
+
+    /// Splits the ring into its producer and consumer halves, which can be moved to
+    /// different contexts
+    pub fn split(&mut self) -> (Producer<'_, N>, Consumer<'_, N>) {
+        (Producer { ring: self }, Consumer { ring: self })
+    }
+
+    /// Adds a byte. Returns the byte back if the ring is full.
+    pub fn push(&mut self, byte: u8) -> Result<(), u8> {
+        // SAFETY: `&mut self` rules out another producer
+        unsafe { self.push_shared(byte) }
+    }
+
+    /// Removes the oldest byte. Returns None if the ring is empty.
+    pub fn pop(&mut self) -> Option<u8> {
+        // SAFETY: `&mut self` rules out another consumer
+        unsafe { self.pop_shared() }
+    }
+
+    /// Number of bytes in the ring
+    pub fn len(&self) -> usize {
+        let read = self.read.load(Ordering::Acquire);
+        let write = self.write.load(Ordering::Acquire);
+        (write + 2 * N - read) % (2 * N)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() >= N
+    }
+
+    /// Pops bytes into `packet.construct()` until the ring is empty or a packet completes.
+    /// Bytes after a complete packet stay in the ring. See `Packet::read_from()` for the
+    /// returned status.
+    pub fn drain_into_packet<const T: usize>(&mut self, packet: &mut Packet<T>) -> Status {
+        packet.read_from(&mut || self.pop())
+    }
+
+    /// Next value of a counter, counters run from 0 to 2 * N - 1 so a full ring can be told
+    /// from an empty one for any N
+    fn advance(counter: usize) -> usize {
+        if counter + 1 == 2 * N {
+            0
+        } else {
+            counter + 1
+        }
+    }
+
+    /// # Safety
+    /// Only one context may push at a time
+    unsafe fn push_shared(&self, byte: u8) -> Result<(), u8> {
+        let write = self.write.load(Ordering::Relaxed);
+        let read = self.read.load(Ordering::Acquire);
+        if (write + 2 * N - read) % (2 * N) >= N {
+            return Err(byte);
+        }
+
+        // SAFETY: The slot isn't readable by the consumer until `write` is published below
+        unsafe {
+            (*self.buffer.get())[write % N] = byte;
+        }
+        self.write.store(Self::advance(write), Ordering::Release);
+        Ok(())
+    }
+
+    /// # Safety
+    /// Only one context may pop at a time
+    unsafe fn pop_shared(&self) -> Option<u8> {
+        let read = self.read.load(Ordering::Relaxed);
+        let write = self.write.load(Ordering::Acquire);
+        if read == write {
+            return None;
+        }
+
+        // SAFETY: The slot was published by the producer and isn't reused until `read` is
+        // published below
+        let byte = unsafe { (*self.buffer.get())[read % N] };
+        self.read.store(Self::advance(read), Ordering::Release);
+        Some(byte)
+    }
+}
+
+impl<const N: usize> Default for ByteRing<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The pushing half of a `ByteRing`, see `ByteRing::split`
+pub struct Producer<'a, const N: usize> {
+    ring: &'a ByteRing<N>,
+}
+
+impl<const N: usize> Producer<'_, N> {
+    /// Adds a byte. Returns the byte back if the ring is full.
+    pub fn push(&mut self, byte: u8) -> Result<(), u8> {
+        // SAFETY: There is only one producer and it is borrowed mutably
+        unsafe { self.ring.push_shared(byte) }
+    }
+
+    /// Number of bytes in the ring
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.ring.is_full()
+    }
+}
+
+/// The popping half of a `ByteRing`, see `ByteRing::split`
+pub struct Consumer<'a, const N: usize> {
+    ring: &'a ByteRing<N>,
+}
+
+impl<const N: usize> Consumer<'_, N> {
+    /// Removes the oldest byte. Returns None if the ring is empty.
+    pub fn pop(&mut self) -> Option<u8> {
+        // SAFETY: There is only one consumer and it is borrowed mutably
+        unsafe { self.ring.pop_shared() }
+    }
+
+    /// Same as `ByteRing::drain_into_packet`
+    pub fn drain_into_packet<const T: usize>(&mut self, packet: &mut Packet<T>) -> Status {
+        packet.read_from(&mut || self.pop())
+    }
+
+    /// Number of bytes in the ring
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.ring.is_full()
+    }
+}
+
+/// Fixed pool of `N` reusable packets, no heap. `acquire()` takes a free packet and returns
+/// a `PoolGuard` to it, dropping the guard resets the packet with `reset_lazy()` and makes it
+/// available again.
+///
+/// Without features the pool is for a single context and isn't `Sync`. With the
+/// `critical-section` feature the pool is `Sync`, so it can be a static shared with
+/// interrupts, and taking or returning a packet runs in a critical section.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use flem::buffer::PacketPool;
+///
+///     let pool = PacketPool::<32, 2>::new();
+///
+///     let rx = pool.acquire().unwrap();
+///     let mut tx = pool.acquire().unwrap();
+///     tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+///     assert!(pool.acquire().is_none());
+///
+///     assert_eq!(tx.payload(), [1, 2, 3]);
+///     drop(tx);
+///     drop(rx);
+///     assert_eq!(pool.available(), 2);
+/// }
+/// ```
+pub struct PacketPool<const T: usize, const N: usize> {
+    packets: [UnsafeCell<Packet<T>>; N],
+    in_use: [Cell<bool>; N],
+}
+
+// SAFETY: `in_use` is only read and written in critical sections, and a packet is only
+// reachable through the one guard that marked it in use.
+#[cfg(feature = "critical-section")]
+unsafe impl<const T: usize, const N: usize> Sync for PacketPool<T, N> {}
+
+impl<const T: usize, const N: usize> PacketPool<T, N> {
+    /// Creates a pool of N reset packets, `const` so a pool can be a static, see
+    /// `Packet::new`
+    pub const fn new() -> Self {
+        Self {
+            packets: [const { UnsafeCell::new(Packet::new()) }; N],
+            in_use: [const { Cell::new(false) }; N],
+        }
+    }
+
+    /// Takes a free packet, None if all N are in use
+    pub fn acquire(&self) -> Option<PoolGuard<'_, T>> {
+        let slot = locked(|| {
+            let slot = self.in_use.iter().position(|in_use| !in_use.get())?;
+            self.in_use[slot].set(true);
+            Some(slot)
+        })?;
+
+        // SAFETY: The slot was free, so no other guard refers to its packet, and it can't
+        // be acquired again until this guard is dropped
+        let packet = unsafe { &mut *self.packets[slot].get() };
+        Some(PoolGuard {
+            packet,
+            in_use: &self.in_use[slot],
+        })
+    }
+
+    /// Number of packets that can be acquired
+    pub fn available(&self) -> usize {
+        locked(|| self.in_use.iter().filter(|in_use| !in_use.get()).count())
+    }
+}
+
+/// A packet taken from a `PacketPool`, derefs to the packet. Dropping the guard returns the
+/// packet to the pool with everything but the data buffer as `Packet::new()` sets it, the
+/// header fields as well as settings such as the sync word or the checksum algorithm.
+pub struct PoolGuard<'a, const T: usize> {
+    packet: &'a mut Packet<T>,
+    in_use: &'a Cell<bool>,
+}
+
+impl<const T: usize> Deref for PoolGuard<'_, T> {
+    type Target = Packet<T>;
+
+    fn deref(&self) -> &Packet<T> {
+        self.packet
+    }
+}
+
+impl<const T: usize> DerefMut for PoolGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Packet<T> {
+        self.packet
+    }
+}
+
+impl<const T: usize> Drop for PoolGuard<'_, T> {
+    fn drop(&mut self) {
+        self.packet.reset_for_reuse();
+        locked(|| self.in_use.set(false));
+    }
+}
+
+#[cfg(feature = "critical-section")]
+fn locked<R>(f: impl FnOnce() -> R) -> R {
+    critical_section::with(|_| f())
+}
+
+#[cfg(not(feature = "critical-section"))]
+fn locked<R>(f: impl FnOnce() -> R) -> R {
+    f()
+}
+
+impl<const T: usize, const N: usize> Default for PacketPool<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,82 @@
+//! Fluent construction of outgoing packets.
+
+use crate::{Packet, Status};
+
+/// Builds an outgoing packet. `build()` always packs the packet, so the header and checksum
+/// can't be forgotten.
+///
+/// Data that doesn't fit is caught when `data()` is called, the error is kept and returned
+/// by `build()`.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use flem::builder::PacketBuilder;
+///
+///     let tx = PacketBuilder::<16>::new()
+///         .request(0x10)
+///         .response(flem::response::SUCCESS)
+///         .data(&[1, 2])
+///         .data(&[3])
+///         .build()
+///         .unwrap();
+///
+///     assert_eq!(tx.get_request(), 0x10);
+///     assert_eq!(tx.get_data()[..3], [1, 2, 3]);
+///
+///     let too_big = PacketBuilder::<16>::new().data(&[0; 17]).build();
+///     assert!(too_big.is_err());
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct PacketBuilder<const T: usize> {
+    packet: Packet<T>,
+    error: Option<Status>,
+}
+
+impl<const T: usize> PacketBuilder<T> {
+    pub fn new() -> Self {
+        Self {
+            packet: Packet::new(),
+            error: None,
+        }
+    }
+
+    /// Sets the request field
+    pub fn request(mut self, request: u16) -> Self {
+        self.packet.set_request(request);
+        self
+    }
+
+    /// Sets the response field, `response::ASYNC` if not set
+    pub fn response(mut self, response: u16) -> Self {
+        self.packet.set_response(response);
+        self
+    }
+
+    /// Appends `data` to the payload. If it doesn't fit, nothing is added and `build()`
+    /// returns the error (`PacketOverflow`).
+    pub fn data(mut self, data: &[u8]) -> Self {
+        if self.error.is_none() {
+            if let Err(error) = self.packet.add_data(data) {
+                self.error = Some(error);
+            }
+        }
+        self
+    }
+
+    /// Packs the packet, or returns the first error from `data()`
+    pub fn build(mut self) -> Result<Packet<T>, Status> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        self.packet.pack();
+        Ok(self.packet)
+    }
+}
+
+impl<const T: usize> Default for PacketBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
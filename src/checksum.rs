@@ -0,0 +1,188 @@
+//! Checksum algorithms. FLEM uses CRC-16/IBM (also known as CRC-16/ARC) by default, the
+//! algorithm of every existing partner. Both partners must use the same algorithm, see
+//! `Packet::set_checksum_algorithm`.
+//!
+//! Other algorithms, for example a CRC peripheral, implement `Checksum` and are selected
+//! with `ChecksumAlgorithm::custom`.
+
+use core::fmt::{self, Debug, Formatter};
+
+/// A 16 bit checksum computed over one or more byte slices
+pub trait Checksum {
+    /// Starts a new checksum
+    fn reset(&mut self);
+    /// Adds `bytes` to the checksum
+    fn update(&mut self, bytes: &[u8]);
+    /// The checksum of the bytes added since the last `reset()`
+    fn finalize(&self) -> u16;
+}
+
+/// Reflected polynomial of CRC-16/IBM
+pub const CRC16_IBM_POLY: u16 = 0xA001;
+
+/// The 256 entry CRC-16/IBM lookup table, one entry per byte value, computed at compile
+/// time from `CRC16_IBM_POLY`
+pub const fn build_crc16_table() -> [u16; 256] {
+    reflected_table(CRC16_IBM_POLY, 8)
+}
+
+/// The 16 entry CRC-16/IBM lookup table of features = ["small-crc"], one entry per nibble
+#[cfg(feature = "small-crc")]
+pub(crate) const fn build_crc16_nibble_table() -> [u16; 16] {
+    reflected_table(CRC16_IBM_POLY, 4)
+}
+
+/// Table of a reflected CRC: entry `i` is `i` shifted through `poly` `bits` times
+const fn reflected_table<const N: usize>(poly: u16, bits: u32) -> [u16; N] {
+    let mut table = [0; N];
+    let mut index = 0;
+    while index < N {
+        let mut crc = index as u16;
+        let mut bit = 0;
+        while bit < bits {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ poly
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+}
+
+/// CRC-16/IBM: reflected polynomial 0xA001, initial value 0, the FLEM default
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Crc16Ibm {
+    crc: u16,
+}
+
+impl Checksum for Crc16Ibm {
+    fn reset(&mut self) {
+        self.crc = 0;
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        self.crc = crate::crc16_update(self.crc, bytes.iter());
+    }
+
+    fn finalize(&self) -> u16 {
+        self.crc
+    }
+}
+
+/// CRC-16/CCITT-FALSE: polynomial 0x1021, initial value 0xFFFF, not reflected
+#[derive(Debug, Clone, Copy)]
+pub struct Crc16Ccitt {
+    crc: u16,
+}
+
+impl Default for Crc16Ccitt {
+    fn default() -> Self {
+        Self { crc: 0xFFFF }
+    }
+}
+
+impl Checksum for Crc16Ccitt {
+    fn reset(&mut self) {
+        self.crc = 0xFFFF;
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.crc ^= (*byte as u16) << 8;
+            for _ in 0..8 {
+                self.crc = if self.crc & 0x8000 != 0 {
+                    (self.crc << 1) ^ 0x1021
+                } else {
+                    self.crc << 1
+                };
+            }
+        }
+    }
+
+    fn finalize(&self) -> u16 {
+        self.crc
+    }
+}
+
+/// The checksum algorithm of a packet, kept as a plain value so `Packet` stays `Copy`.
+#[derive(Clone, Copy, Default)]
+pub enum ChecksumAlgorithm {
+    /// CRC-16/IBM, wire compatible with existing partners
+    #[default]
+    Crc16Ibm,
+    /// CRC-16/CCITT-FALSE
+    Crc16Ccitt,
+    /// A user implementation, created with `ChecksumAlgorithm::custom`
+    Custom(fn(&[&[u8]]) -> u16),
+}
+
+impl ChecksumAlgorithm {
+    /// Uses `C` for the checksum. A new `C` is created with `Default` for every checksum,
+    /// so a CRC peripheral is best wrapped in a zero sized handle.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     use flem::checksum::{Checksum, ChecksumAlgorithm};
+    ///
+    ///     #[derive(Default)]
+    ///     struct Sum(u16);
+    ///
+    ///     impl Checksum for Sum {
+    ///         fn reset(&mut self) {
+    ///             self.0 = 0;
+    ///         }
+    ///         fn update(&mut self, bytes: &[u8]) {
+    ///             for byte in bytes {
+    ///                 self.0 = self.0.wrapping_add(*byte as u16);
+    ///             }
+    ///         }
+    ///         fn finalize(&self) -> u16 {
+    ///             self.0
+    ///         }
+    ///     }
+    ///
+    ///     let mut tx = flem::Packet::<16>::new();
+    ///     tx.set_checksum_algorithm(ChecksumAlgorithm::custom::<Sum>());
+    ///     tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+    ///
+    ///     // request 0x10 + response 1 + length 3 + data 1, 2, 3
+    ///     assert_eq!(tx.get_checksum(), 0x10 + 1 + 3 + 6);
+    /// }
+    /// ```
+    pub fn custom<C: Checksum + Default>() -> Self {
+        ChecksumAlgorithm::Custom(compute::<C>)
+    }
+
+    /// Checksum of `parts`, in order
+    pub fn compute(self, parts: &[&[u8]]) -> u16 {
+        match self {
+            ChecksumAlgorithm::Crc16Ibm => compute::<Crc16Ibm>(parts),
+            ChecksumAlgorithm::Crc16Ccitt => compute::<Crc16Ccitt>(parts),
+            ChecksumAlgorithm::Custom(checksum) => checksum(parts),
+        }
+    }
+}
+
+impl Debug for ChecksumAlgorithm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ChecksumAlgorithm::Crc16Ibm => f.write_str("Crc16Ibm"),
+            ChecksumAlgorithm::Crc16Ccitt => f.write_str("Crc16Ccitt"),
+            ChecksumAlgorithm::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
+
+fn compute<C: Checksum + Default>(parts: &[&[u8]]) -> u16 {
+    let mut checksum = C::default();
+    checksum.reset();
+    for part in parts {
+        checksum.update(part);
+    }
+    checksum.finalize()
+}
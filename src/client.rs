@@ -0,0 +1,230 @@
+//! The request/response loop of a client (device): receive a request, dispatch it and send
+//! the response, driven from a superloop or a timer tick.
+
+use crate::{dispatch::Dispatcher, response, Packet, Status};
+
+/// Bytes `Engine::poll` reads or writes per call unless changed with `set_byte_budget`
+pub const DEFAULT_BYTE_BUDGET: usize = 64;
+
+/// What happened during `Engine::poll`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum EngineEvent {
+    /// No request was completed, the engine may still be receiving or sending
+    Idle,
+    /// A request was dispatched, the response is being sent
+    RequestHandled(u16),
+    /// A request was received again with the same transaction ID, its cached response is
+    /// being sent again and the handler wasn't called. See `Engine` for duplicate detection.
+    Duplicate(u16),
+    /// A request failed. `ChecksumError` is answered with `response::CHECKSUM_ERROR`, handler
+    /// errors and oversized packets aren't answered.
+    Error(Status),
+}
+
+/// Owns the receive and transmit packets and a `Dispatcher` with up to `N` handlers.
+/// Requests without a handler are answered with `response::UNKNOWN_REQUEST` by the
+/// default fallback of the dispatcher.
+///
+/// With protocol-v2 and `D` greater than 0 the engine detects duplicated requests, for
+/// example a request the host sent again because the response was lost. The host tags
+/// every request with a wrapping transaction ID (`Packet::pack_data_seq`, or
+/// `host::Host::set_transactions`), which sets `flags::TRANSACTION`. The engine remembers
+/// the request code, transaction ID and response of the last `D` handled tagged requests,
+/// and answers a tagged request it has already seen with the remembered response instead
+/// of calling the handler again. Untagged requests always reach the handler, their first
+/// payload byte is data.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use flem::client::{Engine, EngineEvent};
+///     use flem::{dispatch::Dispatcher, Packet, Status};
+///
+///     fn echo(rx: &Packet<16>, tx: &mut Packet<16>) -> Result<(), Status> {
+///         tx.pack_data(rx.get_request(), rx.payload())
+///     }
+///
+///     let mut dispatcher = Dispatcher::<16, 4>::new();
+///     dispatcher.register(0x10, echo).unwrap();
+///     let mut engine = Engine::new(dispatcher);
+///
+///     let request = Packet::<16>::with_payload(0x10, &[1, 2]).unwrap();
+///     let mut incoming = request.bytes().iter().copied();
+///     let mut outgoing = Vec::new();
+///
+///     let event = engine.poll(|| incoming.next(), |byte| {
+///         outgoing.push(byte);
+///         true
+///     });
+///
+///     assert_eq!(event, EngineEvent::RequestHandled(0x10));
+///     assert_eq!(outgoing, request.bytes());
+/// }
+/// ```
+pub struct Engine<const T: usize, const N: usize = 8, const D: usize = 0> {
+    rx: Packet<T>,
+    tx: Packet<T>,
+    dispatcher: Dispatcher<T, N>,
+    sending: bool,
+    byte_budget: usize,
+    /// Request code, transaction ID and response of the last D handled requests
+    handled: [Option<(u16, u8, Packet<T>)>; D],
+    next_handled: usize,
+}
+
+impl<const T: usize, const N: usize> Engine<T, N> {
+    /// Creates an engine without duplicate detection
+    pub fn new(dispatcher: Dispatcher<T, N>) -> Self {
+        Engine::build(dispatcher)
+    }
+}
+
+impl<const T: usize, const N: usize, const D: usize> Engine<T, N, D> {
+    /// Creates an engine that remembers the last `D` handled tagged requests, see `Engine`
+    #[cfg(feature = "protocol-v2")]
+    pub fn with_duplicate_detection(dispatcher: Dispatcher<T, N>) -> Self {
+        Engine::build(dispatcher)
+    }
+
+    fn build(dispatcher: Dispatcher<T, N>) -> Self {
+        Self {
+            rx: Packet::new(),
+            tx: Packet::new(),
+            dispatcher,
+            sending: false,
+            byte_budget: DEFAULT_BYTE_BUDGET,
+            handled: [None; D],
+            next_handled: 0,
+        }
+    }
+
+    /// Limits the bytes read and the bytes written by one `poll()`, at least 1
+    pub fn set_byte_budget(&mut self, bytes: usize) {
+        self.byte_budget = bytes.max(1);
+    }
+
+    /// Does a bounded amount of work: sends up to the byte budget of a pending response,
+    /// otherwise reads up to the byte budget from `read` until a request is complete. A
+    /// completed request is dispatched and the first byte budget of its response sent.
+    ///
+    /// `read` returns None when no byte is available, `write` returns false when the byte
+    /// can't be taken right now, the same byte is offered again on the next call.
+    pub fn poll<R, W>(&mut self, mut read: R, mut write: W) -> EngineEvent
+    where
+        R: FnMut() -> Option<u8>,
+        W: FnMut(u8) -> bool,
+    {
+        if self.sending {
+            self.send(&mut write);
+            return EngineEvent::Idle;
+        }
+
+        for _ in 0..self.byte_budget {
+            let Some(byte) = read() else {
+                break;
+            };
+
+            let event = match self.rx.construct(byte) {
+                Ok(_) => {
+                    let request = self.rx.get_request();
+                    let transaction = self.tagged_transaction();
+                    if let Some(response) = self.handled_response(request, transaction) {
+                        self.tx = response;
+                        self.rx.reset_lazy();
+                        EngineEvent::Duplicate(request)
+                    } else {
+                        let result = self.dispatcher.dispatch(&self.rx, &mut self.tx);
+                        self.rx.reset_lazy();
+                        match result {
+                            Ok(_) => {
+                                self.remember(request, transaction);
+                                EngineEvent::RequestHandled(request)
+                            }
+                            Err(status) => return EngineEvent::Error(status),
+                        }
+                    }
+                }
+                Err(Status::ChecksumError) => {
+                    // Can't fail, the error response has no payload
+                    let _ = self
+                        .tx
+                        .pack_error_code(self.rx.get_request(), response::CHECKSUM_ERROR);
+                    EngineEvent::Error(Status::ChecksumError)
+                }
+                Err(Status::PacketBuilding)
+                | Err(Status::HeaderBytesNotFound)
+                | Err(Status::DiscardingOversized) => continue,
+                Err(status) => return EngineEvent::Error(status),
+            };
+
+            self.sending = true;
+            self.send(&mut write);
+            return event;
+        }
+
+        EngineEvent::Idle
+    }
+
+    /// Returns true while a response is being sent, no requests are read until it is done
+    pub fn is_sending(&self) -> bool {
+        self.sending
+    }
+
+    /// The dispatcher, to register more handlers
+    pub fn dispatcher_mut(&mut self) -> &mut Dispatcher<T, N> {
+        &mut self.dispatcher
+    }
+
+    /// The packet being received, for its statistics
+    pub fn rx(&self) -> &Packet<T> {
+        &self.rx
+    }
+
+    /// The last response
+    pub fn tx(&self) -> &Packet<T> {
+        &self.tx
+    }
+
+    /// The transaction ID of a tagged request, only tagged requests are checked for
+    /// duplicates
+    fn tagged_transaction(&self) -> Option<u8> {
+        #[cfg(feature = "protocol-v2")]
+        return self.rx.get_tagged_transaction();
+        #[cfg(not(feature = "protocol-v2"))]
+        return None;
+    }
+
+    /// The response to a request already handled, None if it is new
+    fn handled_response(&self, request: u16, transaction: Option<u8>) -> Option<Packet<T>> {
+        let transaction = transaction?;
+        self.handled
+            .iter()
+            .flatten()
+            .find(|(handled, id, _)| *handled == request && *id == transaction)
+            .map(|(_, _, response)| *response)
+    }
+
+    fn remember(&mut self, request: u16, transaction: Option<u8>) {
+        if let (Some(transaction), Some(slot)) =
+            (transaction, self.handled.get_mut(self.next_handled))
+        {
+            *slot = Some((request, transaction, self.tx));
+            self.next_handled = (self.next_handled + 1) % D;
+        }
+    }
+
+    fn send<W: FnMut(u8) -> bool>(&mut self, write: &mut W) {
+        let mut remaining = self.byte_budget;
+        let status = self.tx.write_to(&mut |byte| {
+            if remaining == 0 || !write(byte) {
+                return false;
+            }
+            remaining -= 1;
+            true
+        });
+        if status == Status::GetByteFinished {
+            self.sending = false;
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! COBS (Consistent Overhead Byte Stuffing) framing. Enabled with features = ["cobs"].
+//!
+//! A COBS frame never contains a 0x00 byte except for the delimiter at the end
+//! of the frame, so frame boundaries are unambiguous even on transports that
+//! don't preserve byte boundaries (TCP streams, log files, concatenated radio
+//! payloads). The FLEM packet is encoded as-is, the header and checksum are
+//! still present and validated on the receiving side.
+
+use crate::{Packet, Status};
+
+/// Largest value of a COBS code byte, a block of 254 non-zero bytes
+const COBS_MAX_CODE: u8 = 0xFF;
+
+/// COBS frame delimiter
+pub const COBS_DELIMITER: u8 = 0x00;
+
+/// Worst case size of a COBS frame, **including the trailing delimiter**, for `length`
+/// bytes of input. COBS adds at most 1 byte for every 254 bytes of input, plus 1 code
+/// byte and 1 delimiter byte.
+///
+/// For a packet, use `max_encoded_len(packet.length())`.
+pub const fn max_encoded_len(length: usize) -> usize {
+    length + length / 254 + 2
+}
+
+/// Encodes a packed packet into `out` as a COBS frame terminated with 0x00. Returns the
+/// number of bytes written to `out`.
+///
+/// `out` must be at least `max_encoded_len(packet.length())` bytes long, otherwise
+/// `Status::PacketOverflow` is returned and nothing is written.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use flem::{cobs, Packet};
+///
+///     let mut tx = Packet::<16>::new();
+///     tx.pack_data(0x10, &[0x00, 0x55, 0x00]).unwrap();
+///
+///     let mut frame = [0u8; cobs::max_encoded_len(flem::FLEM_HEADER_SIZE + 16)];
+///     let frame_length = cobs::encode_into(&tx, &mut frame).unwrap();
+///
+///     let mut receiver = cobs::CobsReceiver::<16>::new();
+///     let mut received = false;
+///     for byte in &frame[..frame_length] {
+///         if receiver.push(*byte).is_ok() {
+///             received = true;
+///         }
+///     }
+///
+///     assert!(received, "Packet should have been received");
+///     assert_eq!(receiver.packet().bytes(), tx.bytes());
+/// }
+/// ```
+pub fn encode_into<const T: usize>(packet: &Packet<T>, out: &mut [u8]) -> Result<usize, Status> {
+    let src = packet.bytes();
+
+    if out.len() < max_encoded_len(src.len()) {
+        return Err(Status::PacketOverflow);
+    }
+
+    let mut code_index = 0;
+    let mut code: u8 = 1;
+    let mut write_index = 1;
+
+    for byte in src {
+        if *byte == 0 {
+            out[code_index] = code;
+            code_index = write_index;
+            write_index += 1;
+            code = 1;
+        } else {
+            out[write_index] = *byte;
+            write_index += 1;
+            code += 1;
+            if code == COBS_MAX_CODE {
+                out[code_index] = code;
+                code_index = write_index;
+                write_index += 1;
+                code = 1;
+            }
+        }
+    }
+
+    out[code_index] = code;
+    out[write_index] = COBS_DELIMITER;
+    write_index += 1;
+
+    Ok(write_index)
+}
+
+/// Receives COBS frames a byte at a time, removes the COBS layer and feeds the decoded
+/// bytes through `Packet::construct`.
+#[derive(Debug, Clone, Copy)]
+pub struct CobsReceiver<const T: usize> {
+    packet: Packet<T>,
+    block_code: u8,
+    block_remaining: u8,
+    frame_started: bool,
+    frame_status: Status,
+}
+
+impl<const T: usize> CobsReceiver<T> {
+    pub fn new() -> Self {
+        Self {
+            packet: Packet::new(),
+            block_code: 0,
+            block_remaining: 0,
+            frame_started: false,
+            frame_status: Status::Ok,
+        }
+    }
+
+    /// Adds a single raw (COBS encoded) byte. Returns `Ok(())` when a frame delimiter is
+    /// received and the frame decoded into a complete, validated packet. The packet stays
+    /// available through `packet()` until the first byte of the next frame arrives.
+    ///
+    /// Otherwise the Status is returned as an error:
+    /// - PacketBuilding - The frame is still being received
+    /// - HeaderBytesNotFound - An empty frame was received or the frame didn't start with a header
+    /// - InvalidDataLengthDetected - The frame ended before the packet was complete
+    /// - PacketOverflow - The frame contained more bytes than the packet
+    /// - ChecksumError - The decoded packet failed the checksum
+    pub fn push(&mut self, byte: u8) -> Result<(), Status> {
+        if byte == COBS_DELIMITER {
+            let status = self.frame_status;
+            self.reset_frame();
+
+            return match status {
+                Status::PacketReceived => Ok(()),
+                Status::Ok => Err(Status::HeaderBytesNotFound),
+                Status::PacketBuilding => Err(Status::InvalidDataLengthDetected),
+                error => Err(error),
+            };
+        }
+
+        if !self.frame_started {
+            // The frame delimiter already marks the start of the packet
+            self.packet.reset_lazy();
+            self.packet.stop_discarding();
+            self.frame_started = true;
+            self.frame_status = Status::PacketBuilding;
+        }
+
+        if self.block_remaining == 0 {
+            // Code byte, the zero removed by the encoder goes between blocks
+            if self.block_code != 0 && self.block_code != COBS_MAX_CODE {
+                self.feed(0);
+            }
+            self.block_code = byte;
+            self.block_remaining = byte - 1;
+        } else {
+            self.feed(byte);
+            self.block_remaining -= 1;
+        }
+
+        match self.frame_status {
+            Status::PacketReceived => Err(Status::PacketBuilding),
+            status => Err(status),
+        }
+    }
+
+    /// The packet decoded from the most recent frame
+    pub fn packet(&self) -> &Packet<T> {
+        &self.packet
+    }
+
+    /// Clears the COBS state and the packet
+    pub fn reset(&mut self) {
+        self.reset_frame();
+        self.packet.reset_lazy();
+    }
+
+    fn reset_frame(&mut self) {
+        self.block_code = 0;
+        self.block_remaining = 0;
+        self.frame_started = false;
+        self.frame_status = Status::Ok;
+    }
+
+    fn feed(&mut self, byte: u8) {
+        match self.frame_status {
+            Status::PacketBuilding => match self.packet.construct(byte) {
+                Ok(_) => self.frame_status = Status::PacketReceived,
+                Err(Status::PacketBuilding) => {}
+                Err(error) => self.frame_status = error,
+            },
+            Status::PacketReceived => {
+                self.frame_status = Status::PacketOverflow;
+            }
+            _ => {
+                // Frame already failed, drop bytes until the delimiter
+            }
+        }
+    }
+}
+
+impl<const T: usize> Default for CobsReceiver<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,172 @@
+//! Debug text from the client to the host over the FLEM link, instead of a second UART.
+//!
+//! The client formats text into a `LogSink` and flushes it into `request::LOG` packets
+//! sent as events (`response::ASYNC`), whenever the link is free. The host passes the
+//! events it receives to a `LogCollector`, which joins the text of several packets and
+//! hands out complete lines. Log packets arriving while the host waits for a response are
+//! passed to `host::Host::request_with_events` and skipped by `correlation::Correlator`.
+
+use core::fmt;
+
+use crate::{request, Packet, Status};
+
+/// Buffers formatted text on the client until it is flushed into a packet, up to `T`
+/// bytes, the payload of one packet.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use core::fmt::Write;
+///     use flem::console::{LogCollector, LogSink};
+///
+///     let mut sink = LogSink::<32>::new();
+///     write!(sink, "Battery {}%", 87).unwrap();
+///
+///     // Flushed before the line is complete, the collector waits for the rest
+///     let mut tx = flem::Packet::<32>::new();
+///     assert_eq!(sink.flush(&mut tx), Ok(true));
+///     let mut collector = LogCollector::<64>::new();
+///     assert!(collector.collect(&tx, |_| panic!("The line isn't complete")));
+///
+///     writeln!(sink, ", charging").unwrap();
+///     sink.flush(&mut tx).unwrap();
+///     let mut lines = Vec::new();
+///     collector.collect(&tx, |line| lines.push(line.to_string()));
+///     assert_eq!(lines, ["Battery 87%, charging"]);
+/// }
+/// ```
+pub struct LogSink<const T: usize> {
+    buffer: [u8; T],
+    length: usize,
+    dropped: usize,
+}
+
+impl<const T: usize> LogSink<T> {
+    pub const fn new() -> Self {
+        Self {
+            buffer: [0; T],
+            length: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Number of buffered bytes
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    /// Returns true if there is nothing to flush
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Number of bytes dropped because the buffer was full, flush more often if it grows
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Packs the buffered text into `tx` as a `request::LOG` event and empties the buffer.
+    /// Returns false and leaves `tx` untouched if there is nothing to flush, otherwise the
+    /// errors of `Packet::pack_async`.
+    pub fn flush(&mut self, tx: &mut Packet<T>) -> Result<bool, Status> {
+        if self.is_empty() {
+            return Ok(false);
+        }
+        tx.pack_async(request::LOG, &self.buffer[..self.length])?;
+        self.length = 0;
+        Ok(true)
+    }
+}
+
+impl<const T: usize> Default for LogSink<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const T: usize> fmt::Write for LogSink<T> {
+    /// Appends `text`. If the buffer is full the rest of the text is dropped, counted in
+    /// `dropped()`, and `fmt::Error` is returned.
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        let bytes = text.as_bytes();
+        let fits = bytes.len().min(T - self.length);
+        self.buffer[self.length..self.length + fits].copy_from_slice(&bytes[..fits]);
+        self.length += fits;
+
+        if fits < bytes.len() {
+            self.dropped += bytes.len() - fits;
+            return Err(fmt::Error);
+        }
+        Ok(())
+    }
+}
+
+/// Joins the text of `request::LOG` events on the host into lines of up to `N` bytes.
+/// Lines end with `\n`, a `\r` in front of it is removed. A line longer than `N` bytes is
+/// handed out in pieces of `N` bytes.
+pub struct LogCollector<const N: usize> {
+    line: [u8; N],
+    length: usize,
+}
+
+impl<const N: usize> LogCollector<N> {
+    pub const fn new() -> Self {
+        Self {
+            line: [0; N],
+            length: 0,
+        }
+    }
+
+    /// Adds the text of `packet` if it is a `request::LOG` event and calls `on_line` for
+    /// every line it completes. Returns false if `packet` isn't a log event, it is left for
+    /// the caller, for example a response.
+    ///
+    /// A line with bytes that aren't valid UTF-8 is handed out up to the first invalid byte.
+    pub fn collect<const T: usize, F: FnMut(&str)>(
+        &mut self,
+        packet: &Packet<T>,
+        mut on_line: F,
+    ) -> bool {
+        if !packet.is_async() || packet.get_request() != request::LOG {
+            return false;
+        }
+
+        for byte in packet.payload() {
+            if *byte == b'\n' {
+                self.emit(&mut on_line);
+                continue;
+            }
+            if self.length == N {
+                self.emit(&mut on_line);
+            }
+            self.line[self.length] = *byte;
+            self.length += 1;
+        }
+        true
+    }
+
+    /// The start of the line not completed yet
+    pub fn pending(&self) -> &[u8] {
+        &self.line[..self.length]
+    }
+
+    fn emit<F: FnMut(&str)>(&mut self, on_line: &mut F) {
+        let mut line = &self.line[..self.length];
+        if let [start @ .., b'\r'] = line {
+            line = start;
+        }
+        let text = match core::str::from_utf8(line) {
+            Ok(text) => text,
+            // Can't fail, the bytes up to valid_up_to are valid UTF-8
+            Err(error) => core::str::from_utf8(&line[..error.valid_up_to()]).unwrap_or(""),
+        };
+        on_line(text);
+        self.length = 0;
+    }
+}
+
+impl<const N: usize> Default for LogCollector<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
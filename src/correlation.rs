@@ -0,0 +1,168 @@
+//! Matches responses to outstanding requests when several requests are in flight, even
+//! with the same request code.
+//!
+//! The host tags each request with a transaction ID, the first payload byte (see
+//! `Packet::pack_data_seq`), and the device echoes it back as the first payload byte of the
+//! response. This is separate from the protocol-v2 header sequence number, which numbers
+//! the fragments of a payload.
+//!
+//! Peers that don't tag their responses still work as long as only one request with a
+//! given request code is in flight, the response is matched on the request code alone.
+
+use crate::{Packet, Status};
+
+/// Result of `Correlator::complete`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Correlation {
+    /// The response carried the transaction ID of an outstanding request, the data starts
+    /// after the first payload byte
+    Tagged { transaction: u8, request: u16 },
+    /// The response wasn't tagged, it was matched to the only outstanding request with its
+    /// request code. The whole payload is data.
+    Untagged { transaction: u8, request: u16 },
+    /// No outstanding request matches the response
+    Orphan,
+    /// The packet is an event sent without a request (`response::ASYNC`), for example
+    /// `console` log text. It isn't matched and isn't counted as an orphan.
+    Unsolicited,
+}
+
+/// Table of up to `N` outstanding requests.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use flem::correlation::{Correlation, Correlator};
+///     use flem::Packet;
+///
+///     const READ: u16 = 0x10;
+///     let mut correlator = Correlator::<4>::new();
+///
+///     // Two reads in flight at once
+///     let first = correlator.begin(READ).unwrap();
+///     let second = correlator.begin(READ).unwrap();
+///
+///     // The device answers the second one first, echoing the transaction ID
+///     let mut response = Packet::<16>::new();
+///     response.pack_data_seq(READ, second, &[0xBB]).unwrap();
+///
+///     assert_eq!(
+///         correlator.complete(&response),
+///         Correlation::Tagged { transaction: second, request: READ }
+///     );
+///     assert_eq!(correlator.outstanding(), 1);
+/// #   let _ = first;
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Correlator<const N: usize> {
+    slots: [Option<(u8, u16)>; N],
+    next: u8,
+    orphans: u32,
+}
+
+impl<const N: usize> Correlator<N> {
+    pub const fn new() -> Self {
+        Self {
+            slots: [None; N],
+            next: 0,
+            orphans: 0,
+        }
+    }
+
+    /// Registers an outstanding request and returns the transaction ID to send with it.
+    /// IDs count up and wrap around, skipping IDs that are still outstanding.
+    ///
+    /// Returns `Status::PacketOverflow` if N requests are already outstanding, or if all
+    /// 256 transaction IDs are outstanding (only possible with N over 256).
+    pub fn begin(&mut self, request: u16) -> Result<u8, Status> {
+        let free = self
+            .slots
+            .iter()
+            .position(|slot| slot.is_none())
+            .ok_or(Status::PacketOverflow)?;
+
+        let transaction = (0..=u8::MAX)
+            .map(|offset| self.next.wrapping_add(offset))
+            .find(|transaction| self.find(*transaction).is_none())
+            .ok_or(Status::PacketOverflow)?;
+        self.next = transaction.wrapping_add(1);
+
+        self.slots[free] = Some((transaction, request));
+        Ok(transaction)
+    }
+
+    /// Matches a received response to an outstanding request and removes the request.
+    /// Orphans are counted, see `orphans()`.
+    pub fn complete<const T: usize>(&mut self, response: &Packet<T>) -> Correlation {
+        if response.is_async() {
+            return Correlation::Unsolicited;
+        }
+
+        let request = response.get_request();
+
+        if let Some(transaction) = response.get_transaction() {
+            if let Some(index) = self.find(transaction) {
+                if self.slots[index] == Some((transaction, request)) {
+                    self.slots[index] = None;
+                    return Correlation::Tagged {
+                        transaction,
+                        request,
+                    };
+                }
+            }
+        }
+
+        // Untagged peer, only safe if a single request with this code is in flight
+        let mut matches = self
+            .slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| matches!(slot, Some((_, code)) if *code == request));
+        if let (Some((index, Some((transaction, _)))), None) = (matches.next(), matches.next()) {
+            let transaction = *transaction;
+            self.slots[index] = None;
+            return Correlation::Untagged {
+                transaction,
+                request,
+            };
+        }
+
+        self.orphans = self.orphans.wrapping_add(1);
+        Correlation::Orphan
+    }
+
+    /// Drops an outstanding request, for example after a timeout. Returns false if the
+    /// transaction ID wasn't outstanding.
+    pub fn cancel(&mut self, transaction: u8) -> bool {
+        match self.find(transaction) {
+            Some(index) => {
+                self.slots[index] = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Number of outstanding requests
+    pub fn outstanding(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.is_some()).count()
+    }
+
+    /// Number of responses that didn't match an outstanding request
+    pub fn orphans(&self) -> u32 {
+        self.orphans
+    }
+
+    fn find(&self, transaction: u8) -> Option<usize> {
+        self.slots
+            .iter()
+            .position(|slot| matches!(slot, Some((id, _)) if *id == transaction))
+    }
+}
+
+impl<const N: usize> Default for Correlator<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
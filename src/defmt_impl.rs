@@ -0,0 +1,50 @@
+//! `defmt::Format` for `DataId` and `Packet`. Enabled with features = ["defmt"].
+//!
+//! Log transport is slow on most targets, the packet only prints the fields needed to
+//! follow a conversation and at most the first `PAYLOAD_PREVIEW` bytes of the payload.
+
+use defmt::{Format, Formatter};
+
+use crate::{DataId, Packet, FLEM_HEADER_SIZE};
+
+/// Payload bytes printed for a packet
+const PAYLOAD_PREVIEW: usize = 8;
+
+impl<const N: usize> Format for DataId<N> {
+    fn format(&self, f: Formatter) {
+        defmt::write!(f, "DataId {{ name: \"");
+        for c in self.name.iter().take_while(|c| **c != '\0') {
+            defmt::write!(f, "{}", c);
+        }
+        defmt::write!(
+            f,
+            "\", version: {=u8}.{=u8}.{=u8}, max_packet_size: {=u16} }}",
+            self.major,
+            self.minor,
+            self.patch,
+            self.max_packet_size
+        );
+    }
+}
+
+impl<const T: usize> Format for Packet<T> {
+    fn format(&self, f: Formatter) {
+        let payload = &self.bytes()[FLEM_HEADER_SIZE..];
+        let preview = &payload[..payload.len().min(PAYLOAD_PREVIEW)];
+
+        defmt::write!(
+            f,
+            "Packet {{ request: {=u16:#x}, response: {=u16:#x}, length: {=u16}, checksum: {=u16:#x}, status: {}, data: {=[u8]:#x}",
+            self.get_request(),
+            self.get_response(),
+            payload.len() as u16,
+            self.get_checksum(),
+            self.status,
+            preview
+        );
+        if payload.len() > PAYLOAD_PREVIEW {
+            defmt::write!(f, "..");
+        }
+        defmt::write!(f, " }}");
+    }
+}
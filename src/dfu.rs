@@ -0,0 +1,305 @@
+//! Firmware update (DFU) over FLEM requests. Enabled with features = ["dfu"].
+//!
+//! The host erases the image, writes it a chunk at a time, has the client check the length
+//! and CRC of what was written and then starts the new image:
+//! - `ERASE` - No payload. Erases the image area.
+//! - `WRITE` - u32 offset (little endian) followed by the data to write at that offset. A
+//!   failed write is answered with the error response and the u32 offset as the detail.
+//! - `VERIFY` - u32 length and u16 CRC-16/IBM of the whole image, see `Verify`. A CRC
+//!   mismatch is answered with `response::INVALID_ARGUMENT` and the u16 CRC of the written
+//!   image as the detail.
+//! - `BOOT` - No payload. Starts the new image once the response is sent.
+//!
+//! The host side is `DfuHost`, the client side a `DfuBackend` registered on a `Dispatcher`
+//! with `register`.
+
+use crate::dispatch::Dispatcher;
+use crate::host::{ByteChannel, Host};
+use crate::{request, response, Packet, Status};
+
+/// Erases the image area, no payload
+pub const ERASE: u16 = request::DFU_ERASE;
+/// Writes a chunk of the image, u32 offset followed by the data
+pub const WRITE: u16 = request::DFU_WRITE;
+/// Checks the written image, payload is a `Verify`
+pub const VERIFY: u16 = request::DFU_VERIFY;
+/// Starts the new image, no payload
+pub const BOOT: u16 = request::DFU_BOOT;
+
+/// Bytes in front of the data of a `WRITE` payload, the u32 offset
+pub const WRITE_OFFSET_SIZE: usize = 4;
+
+/// Bytes read back from the backend at a time to compute the CRC of a `VERIFY`
+const VERIFY_CHUNK: usize = 32;
+
+/// Payload of a `VERIFY` request: the length of the image and its CRC-16/IBM, the FLEM
+/// checksum algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Verify {
+    pub length: u32,
+    pub crc: u16,
+}
+
+impl Verify {
+    /// Size of the payload: length (4 bytes) and CRC (2 bytes), little endian
+    pub const SIZE: usize = 6;
+
+    /// Decodes a `VERIFY` payload. Returns `Status::PayloadUnderflow` if it is shorter than
+    /// `Verify::SIZE`.
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Status> {
+        if payload.len() < Self::SIZE {
+            return Err(Status::PayloadUnderflow);
+        }
+        Ok(Self {
+            length: u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]),
+            crc: u16::from_le_bytes([payload[4], payload[5]]),
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        bytes[..4].copy_from_slice(&self.length.to_le_bytes());
+        bytes[4..].copy_from_slice(&self.crc.to_le_bytes());
+        bytes
+    }
+}
+
+/// Why a firmware update failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DfuError {
+    /// The request couldn't be exchanged, see `Host::request`
+    Link(Status),
+    /// The client answered `request` with the error `response`. `offset` is the offset of
+    /// the failed write for `WRITE`, 0 for the other requests.
+    Rejected {
+        request: u16,
+        response: u16,
+        offset: u32,
+    },
+    /// The written image doesn't match, `image` is the CRC sent by the host and `written`
+    /// the CRC the client computed
+    ChecksumMismatch { image: u16, written: u16 },
+}
+
+impl From<Status> for DfuError {
+    fn from(status: Status) -> Self {
+        DfuError::Link(status)
+    }
+}
+
+/// Sends an image to a client with a `DfuBackend` registered.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use flem::dfu::DfuHost;
+///     use flem::host::ByteChannel;
+///     use flem::Status;
+///
+///     // No client connected
+///     struct Unplugged;
+///
+///     impl ByteChannel for Unplugged {
+///         fn write(&mut self, _bytes: &[u8]) -> Result<(), Status> {
+///             Ok(())
+///         }
+///         fn read(&mut self) -> Option<u8> {
+///             None
+///         }
+///     }
+///
+///     let image = [0x55_u8; 100];
+///     let mut dfu = DfuHost::<64>::new();
+///     dfu.host_mut().set_read_budget(16);
+///     assert_eq!(
+///         dfu.send_image(&mut Unplugged, image.chunks(32)),
+///         Err(flem::dfu::DfuError::Link(Status::NoResponse))
+///     );
+/// }
+/// ```
+pub struct DfuHost<const T: usize> {
+    host: Host<T>,
+}
+
+impl<const T: usize> DfuHost<T> {
+    pub fn new() -> Self {
+        Self { host: Host::new() }
+    }
+
+    /// The host sending the requests, to change its read budget. Erasing and verifying
+    /// can take a while on the client.
+    pub fn host_mut(&mut self) -> &mut Host<T> {
+        &mut self.host
+    }
+
+    /// Erases, writes and verifies `image`, given as chunks of any size. Chunks larger
+    /// than the payload of a `WRITE` are split, the last write is shorter if the image
+    /// doesn't fill it. Returns the length and CRC the client verified. Call `boot()` to
+    /// start the new image.
+    ///
+    /// Returns `Status::PacketOverflow` if T has no room for data after the write offset.
+    pub fn send_image<'a, C, I>(&mut self, channel: &mut C, image: I) -> Result<Verify, DfuError>
+    where
+        C: ByteChannel,
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        if T <= WRITE_OFFSET_SIZE {
+            return Err(DfuError::Link(Status::PacketOverflow));
+        }
+
+        self.exchange(channel, ERASE, &[])?;
+
+        let mut buffer = [0u8; T];
+        let mut offset: u32 = 0;
+        let mut crc = 0;
+        for chunk in image {
+            for piece in chunk.chunks(T - WRITE_OFFSET_SIZE) {
+                buffer[..WRITE_OFFSET_SIZE].copy_from_slice(&offset.to_le_bytes());
+                buffer[WRITE_OFFSET_SIZE..WRITE_OFFSET_SIZE + piece.len()].copy_from_slice(piece);
+                self.exchange(channel, WRITE, &buffer[..WRITE_OFFSET_SIZE + piece.len()])?;
+
+                crc = crate::crc16_update(crc, piece.iter());
+                offset += piece.len() as u32;
+            }
+        }
+
+        let verify = Verify {
+            length: offset,
+            crc,
+        };
+        self.exchange(channel, VERIFY, &verify.to_bytes())?;
+        Ok(verify)
+    }
+
+    /// Asks the client to start the new image
+    pub fn boot<C: ByteChannel>(&mut self, channel: &mut C) -> Result<(), DfuError> {
+        self.exchange(channel, BOOT, &[])
+    }
+
+    fn exchange<C: ByteChannel>(
+        &mut self,
+        channel: &mut C,
+        request: u16,
+        payload: &[u8],
+    ) -> Result<(), DfuError> {
+        let rx = self.host.request(channel, request, payload)?;
+        if rx.is_success() {
+            return Ok(());
+        }
+
+        let response = rx.get_response();
+        match (request, response) {
+            (VERIFY, response::INVALID_ARGUMENT) if rx.get_length() == 2 => {
+                let written = rx.read_u16_le(0)?;
+                let image = Verify::from_payload(payload)?.crc;
+                Err(DfuError::ChecksumMismatch { image, written })
+            }
+            (WRITE, _) => {
+                // The offset sent, if the client didn't report one
+                let sent = u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]);
+                Err(DfuError::Rejected {
+                    request,
+                    response,
+                    offset: rx.read_u32_le(0).unwrap_or(sent),
+                })
+            }
+            _ => Err(DfuError::Rejected {
+                request,
+                response,
+                offset: 0,
+            }),
+        }
+    }
+}
+
+impl<const T: usize> Default for DfuHost<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The flash of a client, written by the `dfu` requests. The errors are the response
+/// codes sent to the host, e.g. `response::OUT_OF_RANGE`.
+///
+/// `Dispatcher` handlers are plain function pointers, so the functions don't take `self`:
+/// implement the trait on a zero sized type that reaches the flash through its
+/// peripheral or a static.
+pub trait DfuBackend {
+    /// Erases the image area
+    fn erase() -> Result<(), u16>;
+    /// Writes `data` at `offset` of the image area
+    fn write(offset: u32, data: &[u8]) -> Result<(), u16>;
+    /// Reads `out.len()` bytes at `offset` of the image area, to verify it
+    fn read(offset: u32, out: &mut [u8]) -> Result<(), u16>;
+    /// Starts the new image. Called before the response is sent, so schedule the start
+    /// (e.g. set a flag checked after the response went out) instead of jumping directly.
+    fn boot() -> Result<(), u16>;
+}
+
+/// Registers handlers for `ERASE`, `WRITE`, `VERIFY` and `BOOT` backed by `B`. Returns
+/// `Status::PacketOverflow` if the dispatcher doesn't have 4 free slots, some handlers
+/// may have been registered.
+///
+/// Malformed payloads are answered with `response::INVALID_ARGUMENT`.
+pub fn register<B: DfuBackend, const T: usize, const N: usize>(
+    dispatcher: &mut Dispatcher<T, N>,
+) -> Result<(), Status> {
+    dispatcher.register(ERASE, erase::<B, T>)?;
+    dispatcher.register(WRITE, write::<B, T>)?;
+    dispatcher.register(VERIFY, verify::<B, T>)?;
+    dispatcher.register(BOOT, boot::<B, T>)
+}
+
+fn erase<B: DfuBackend, const T: usize>(rx: &Packet<T>, tx: &mut Packet<T>) -> Result<(), Status> {
+    match B::erase() {
+        Ok(_) => tx.pack_data(rx.get_request(), &[]),
+        Err(error) => tx.pack_error_code(rx.get_request(), error),
+    }
+}
+
+fn write<B: DfuBackend, const T: usize>(rx: &Packet<T>, tx: &mut Packet<T>) -> Result<(), Status> {
+    let Ok(offset) = rx.read_u32_le(0) else {
+        return tx.pack_error_code(rx.get_request(), response::INVALID_ARGUMENT);
+    };
+    match B::write(offset, &rx.payload()[WRITE_OFFSET_SIZE..]) {
+        Ok(_) => tx.pack_data(rx.get_request(), &[]),
+        Err(error) => tx.pack_error_with_detail(rx.get_request(), error, &offset.to_le_bytes()),
+    }
+}
+
+fn verify<B: DfuBackend, const T: usize>(rx: &Packet<T>, tx: &mut Packet<T>) -> Result<(), Status> {
+    let Ok(expected) = Verify::from_payload(rx.payload()) else {
+        return tx.pack_error_code(rx.get_request(), response::INVALID_ARGUMENT);
+    };
+
+    let mut buffer = [0u8; VERIFY_CHUNK];
+    let mut crc = 0;
+    let mut offset = 0;
+    while offset < expected.length {
+        let length = (expected.length - offset).min(VERIFY_CHUNK as u32) as usize;
+        if let Err(error) = B::read(offset, &mut buffer[..length]) {
+            return tx.pack_error_code(rx.get_request(), error);
+        }
+        crc = crate::crc16_update(crc, buffer[..length].iter());
+        offset += length as u32;
+    }
+
+    if crc == expected.crc {
+        tx.pack_data(rx.get_request(), &[])
+    } else {
+        tx.pack_error_with_detail(
+            rx.get_request(),
+            response::INVALID_ARGUMENT,
+            &crc.to_le_bytes(),
+        )
+    }
+}
+
+fn boot<B: DfuBackend, const T: usize>(rx: &Packet<T>, tx: &mut Packet<T>) -> Result<(), Status> {
+    match B::boot() {
+        Ok(_) => tx.pack_data(rx.get_request(), &[]),
+        Err(error) => tx.pack_error_code(rx.get_request(), error),
+    }
+}
@@ -0,0 +1,190 @@
+//! Routes received packets to a handler registered for their request.
+//!
+//! The dispatcher is a fixed size table, so it works without an allocator. Handlers are
+//! plain function pointers that read the received packet and pack the response.
+//!
+//! On the smallest targets the table can be a `static` slice instead, see
+//! `dispatch_static`. Both answer requests without a handler with
+//! `response::UNKNOWN_REQUEST`, a corrupted request is answered with
+//! `response::CHECKSUM_ERROR` by the receive loop (`client::Engine`) before either is called.
+
+use crate::{response, Packet, Status};
+
+/// Handles a received packet (`rx`) and packs the response into `tx`
+pub type Handler<const T: usize> = fn(&Packet<T>, &mut Packet<T>) -> Result<(), Status>;
+
+/// Maps up to `N` request codes to handlers. Requests without a handler go to the fallback,
+/// which by default responds with `response::UNKNOWN_REQUEST`.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use flem::{dispatch::Dispatcher, Packet, Status};
+///
+///     const GET_VERSION: u16 = 0x10;
+///
+///     fn get_version(rx: &Packet<16>, tx: &mut Packet<16>) -> Result<(), Status> {
+///         tx.pack_data(rx.get_request(), &[1, 2, 3])
+///     }
+///
+///     let mut dispatcher = Dispatcher::<16, 4>::new();
+///     dispatcher.register(GET_VERSION, get_version).unwrap();
+///
+///     let mut rx = Packet::<16>::new();
+///     rx.set_request(GET_VERSION);
+///     rx.pack();
+///
+///     let mut tx = Packet::<16>::new();
+///     dispatcher.dispatch(&rx, &mut tx).unwrap();
+///
+///     assert_eq!(tx.get_response(), flem::response::SUCCESS);
+/// }
+/// ```
+#[derive(Clone, Copy)]
+pub struct Dispatcher<const T: usize, const N: usize> {
+    handlers: [Option<(u16, Handler<T>)>; N],
+    fallback: Handler<T>,
+}
+
+impl<const T: usize, const N: usize> Dispatcher<T, N> {
+    pub const fn new() -> Self {
+        Self {
+            handlers: [None; N],
+            fallback: unknown_request::<T>,
+        }
+    }
+
+    /// Registers `handler` for `request`, replacing the handler already registered for it.
+    /// Returns `Status::PacketOverflow` if all N slots are taken.
+    pub fn register(&mut self, request: u16, handler: Handler<T>) -> Result<(), Status> {
+        let mut free = None;
+        for (index, slot) in self.handlers.iter_mut().enumerate() {
+            match slot {
+                Some((registered, existing)) if *registered == request => {
+                    *existing = handler;
+                    return Ok(());
+                }
+                None if free.is_none() => free = Some(index),
+                _ => {}
+            }
+        }
+
+        match free {
+            Some(index) => {
+                self.handlers[index] = Some((request, handler));
+                Ok(())
+            }
+            None => Err(Status::PacketOverflow),
+        }
+    }
+
+    /// Removes the handler for `request`, returns true if there was one
+    pub fn unregister(&mut self, request: u16) -> bool {
+        for slot in self.handlers.iter_mut() {
+            if matches!(slot, Some((registered, _)) if *registered == request) {
+                *slot = None;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Replaces the handler for requests that have no handler registered
+    pub fn set_fallback(&mut self, handler: Handler<T>) {
+        self.fallback = handler;
+    }
+
+    /// Returns true if a handler is registered for `request`
+    pub fn handles(&self, request: u16) -> bool {
+        self.handler(request).is_some()
+    }
+
+    /// Calls the handler registered for the request of `rx`, or the fallback. `tx` holds
+    /// the response to send when `Ok(())` is returned, including the `UNKNOWN_REQUEST`
+    /// response of the default fallback.
+    pub fn dispatch(&self, rx: &Packet<T>, tx: &mut Packet<T>) -> Result<(), Status> {
+        match self.handler(rx.get_request()) {
+            Some(handler) => handler(rx, tx),
+            None => (self.fallback)(rx, tx),
+        }
+    }
+
+    fn handler(&self, request: u16) -> Option<Handler<T>> {
+        self.handlers
+            .iter()
+            .flatten()
+            .find(|(registered, _)| *registered == request)
+            .map(|(_, handler)| *handler)
+    }
+}
+
+impl<const T: usize, const N: usize> Default for Dispatcher<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Calls the handler for the request of `rx` from a table sorted by request code, for
+/// example a `static`, without registering anything at runtime. Requests without a handler
+/// are answered with `response::UNKNOWN_REQUEST` like the default fallback of `Dispatcher`.
+///
+/// The table is binary searched, so it must be sorted by request code without duplicates.
+/// Debug builds panic if it isn't, check it at compile time with `is_sorted_table`.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use flem::dispatch::{dispatch_static, is_sorted_table, Handler};
+///     use flem::{Packet, Status};
+///
+///     const GET_DATA: u16 = 0x10;
+///
+///     fn get_data(rx: &Packet<16>, tx: &mut Packet<16>) -> Result<(), Status> {
+///         tx.pack_data(rx.get_request(), &[1, 2, 3])
+///     }
+///
+///     static HANDLERS: &[(u16, Handler<16>)] = &[(GET_DATA, get_data)];
+///     const _: () = assert!(is_sorted_table(HANDLERS));
+///
+///     let rx = Packet::<16>::with_payload(GET_DATA, &[]).unwrap();
+///     let mut tx = Packet::<16>::new();
+///     dispatch_static(HANDLERS, &rx, &mut tx).unwrap();
+///     assert_eq!(tx.payload(), [1, 2, 3]);
+/// }
+/// ```
+pub fn dispatch_static<const T: usize>(
+    handlers: &[(u16, Handler<T>)],
+    rx: &Packet<T>,
+    tx: &mut Packet<T>,
+) -> Result<(), Status> {
+    debug_assert!(
+        is_sorted_table(handlers),
+        "Handler table must be sorted by request code without duplicates"
+    );
+    match handlers.binary_search_by_key(&rx.get_request(), |(request, _)| *request) {
+        Ok(index) => (handlers[index].1)(rx, tx),
+        Err(_) => unknown_request(rx, tx),
+    }
+}
+
+/// Returns true if `handlers` is sorted by request code without duplicates, as
+/// `dispatch_static` requires. A `const fn`, so a static table can be checked at compile
+/// time: `const _: () = assert!(is_sorted_table(HANDLERS));`
+pub const fn is_sorted_table<const T: usize>(handlers: &[(u16, Handler<T>)]) -> bool {
+    let mut index = 1;
+    while index < handlers.len() {
+        if handlers[index - 1].0 >= handlers[index].0 {
+            return false;
+        }
+        index += 1;
+    }
+    true
+}
+
+/// Default fallback, echoes the request with `response::UNKNOWN_REQUEST`
+pub(crate) fn unknown_request<const T: usize>(
+    rx: &Packet<T>,
+    tx: &mut Packet<T>,
+) -> Result<(), Status> {
+    tx.pack_error_code(rx.get_request(), response::UNKNOWN_REQUEST)
+}
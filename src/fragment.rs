@@ -0,0 +1,97 @@
+//! Splits a payload larger than one packet into packed packets. Requires
+//! features = ["protocol-v2"].
+//!
+//! The counterpart of `reassembly::Reassembler`: the packets have consecutive sequence
+//! numbers, every packet except the last one has `flags::MORE_FRAGMENTS` set and, if the
+//! payload takes more than one packet, every packet has `flags::FRAGMENT` set.
+
+use crate::{flags, Packet};
+
+/// Iterator over the packed packets of `data`, `T` bytes of payload each. The last packet
+/// holds the rest, an empty `data` gives a single packet without payload.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use flem::fragment::FrameEncoder;
+///
+///     let payload = [0xA5_u8; 40];
+///     let packets: Vec<flem::Packet<16>> = FrameEncoder::new(0x10, &payload).collect();
+///
+///     assert_eq!(packets.len(), 3);
+///     assert!(packets[0].has_more_fragments());
+///     assert!(!packets[2].has_more_fragments());
+///     assert_eq!(packets[2].get_sequence(), 2);
+///     assert_eq!(packets[2].payload(), &payload[32..]);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FrameEncoder<'a, const T: usize> {
+    request: u16,
+    data: &'a [u8],
+    sequence: u16,
+    fragmented: bool,
+    finished: bool,
+}
+
+impl<'a, const T: usize> FrameEncoder<'a, T> {
+    /// Encodes `data` as `request` packets, starting at sequence number 0
+    pub fn new(request: u16, data: &'a [u8]) -> Self {
+        assert!(T > 0, "<T> should be at least 1 to hold a fragment");
+        Self {
+            request,
+            data,
+            sequence: 0,
+            fragmented: data.len() > T,
+            finished: false,
+        }
+    }
+
+    /// Starts the sequence numbers at `sequence` instead of 0, for a sender that keeps
+    /// counting across payloads. Sequence numbers wrap around at u16::MAX.
+    pub fn with_sequence(mut self, sequence: u16) -> Self {
+        self.sequence = sequence;
+        self
+    }
+
+    /// Number of packets not returned yet
+    pub fn remaining(&self) -> usize {
+        match (self.finished, self.data.len()) {
+            (true, _) => 0,
+            (false, 0) => 1,
+            (false, length) => length.div_ceil(T),
+        }
+    }
+}
+
+impl<const T: usize> Iterator for FrameEncoder<'_, T> {
+    type Item = Packet<T>;
+
+    fn next(&mut self) -> Option<Packet<T>> {
+        if self.finished {
+            return None;
+        }
+
+        let (chunk, rest) = self.data.split_at(self.data.len().min(T));
+        let mut packet = Packet::new();
+        packet.set_sequence(self.sequence);
+        packet.set_more_fragments(!rest.is_empty());
+        packet.set_flag(flags::FRAGMENT, self.fragmented);
+        // Can't fail, the chunk fits the payload
+        packet.pack_data(self.request, chunk).ok()?;
+
+        self.data = rest;
+        self.sequence = self.sequence.wrapping_add(1);
+        self.finished = rest.is_empty();
+        Some(packet)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.remaining();
+        (remaining, Some(remaining))
+    }
+}
+
+impl<const T: usize> ExactSizeIterator for FrameEncoder<'_, T> {}
+
+impl<const T: usize> core::iter::FusedIterator for FrameEncoder<'_, T> {}
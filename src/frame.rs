@@ -0,0 +1,293 @@
+//! Byte header frames shared by `packet32::Packet32` and `packet_be::PacketBe`.
+//!
+//! Both keep the header as the bytes sent on the wire and only differ in the header value,
+//! the width of the checksum field, the byte order and the checksum algorithm. A `Layout`
+//! gives those, the field offsets follow from the checksum width:
+//!
+//! header (2 bytes), checksum (`CHECKSUM_SIZE`), request (2), response (2), length (2),
+//! with protocol-v2 sequence (2), flags (1) and channel (1), then the data. The checksum
+//! covers everything after the checksum bytes.
+
+use core::marker::PhantomData;
+
+use crate::{response, Status, FLEM_HEADER_SIZE};
+
+/// What sets a frame format apart
+pub(crate) trait Layout {
+    /// Default header value, the first two bytes of every frame
+    const HEADER: u16;
+    /// Width of the checksum field in bytes, at most 4
+    const CHECKSUM_SIZE: usize;
+    /// Byte order of the multi byte fields
+    const BIG_ENDIAN: bool;
+
+    /// Checksum of the header bytes after the checksum field followed by the payload
+    fn checksum(fields: &[u8], payload: &[u8]) -> u32;
+}
+
+/// A frame with an `H` byte header and room for `T` payload bytes. `H` has to match the
+/// checksum width of `L`, `new()` panics otherwise.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Frame<L, const H: usize, const T: usize> {
+    header: [u8; H],
+    data: [u8; T],
+    internal_counter: usize,
+    status: Status,
+    sync_word: u16,
+    layout: PhantomData<L>,
+}
+
+impl<L: Layout, const H: usize, const T: usize> Frame<L, H, T> {
+    const CHECKSUM: usize = 2;
+    const REQUEST: usize = Self::CHECKSUM + L::CHECKSUM_SIZE;
+    const RESPONSE: usize = Self::REQUEST + 2;
+    const LENGTH: usize = Self::RESPONSE + 2;
+    #[cfg(feature = "protocol-v2")]
+    const SEQUENCE: usize = Self::LENGTH + 2;
+    #[cfg(feature = "protocol-v2")]
+    const FLAGS: usize = Self::SEQUENCE + 2;
+    #[cfg(feature = "protocol-v2")]
+    const CHANNEL: usize = Self::FLAGS + 1;
+
+    pub(crate) fn new() -> Self {
+        assert!(T < u16::MAX as usize, "<T> should be u16::MAX or less");
+        assert_eq!(
+            H,
+            FLEM_HEADER_SIZE - 2 + L::CHECKSUM_SIZE,
+            "<H> should fit the checksum width"
+        );
+        Self {
+            header: [0; H],
+            data: [0; T],
+            internal_counter: 0,
+            status: Status::Ok,
+            sync_word: L::HEADER,
+            layout: PhantomData,
+        }
+    }
+
+    pub(crate) fn set_request(&mut self, request: u16) {
+        self.set_field(Self::REQUEST, 2, request as u32);
+    }
+
+    pub(crate) fn get_request(&self) -> u16 {
+        self.get_field(Self::REQUEST, 2) as u16
+    }
+
+    pub(crate) fn set_response(&mut self, response: u16) {
+        self.set_field(Self::RESPONSE, 2, response as u32);
+    }
+
+    pub(crate) fn get_response(&self) -> u16 {
+        self.get_field(Self::RESPONSE, 2) as u16
+    }
+
+    pub(crate) fn get_header(&self) -> u16 {
+        self.get_field(0, 2) as u16
+    }
+
+    pub(crate) fn get_checksum(&self) -> u32 {
+        self.get_field(Self::CHECKSUM, L::CHECKSUM_SIZE)
+    }
+
+    pub(crate) fn get_length(&self) -> u16 {
+        self.get_field(Self::LENGTH, 2) as u16
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    pub(crate) fn set_sequence(&mut self, sequence: u16) {
+        self.set_field(Self::SEQUENCE, 2, sequence as u32);
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    pub(crate) fn get_sequence(&self) -> u16 {
+        self.get_field(Self::SEQUENCE, 2) as u16
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    pub(crate) fn set_flags(&mut self, flags: u8) {
+        self.header[Self::FLAGS] = flags;
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    pub(crate) fn get_flags(&self) -> u8 {
+        self.header[Self::FLAGS]
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    pub(crate) fn set_channel(&mut self, channel: u8) {
+        self.header[Self::CHANNEL] = channel;
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    pub(crate) fn get_channel(&self) -> u8 {
+        self.header[Self::CHANNEL]
+    }
+
+    pub(crate) fn set_sync_word(&mut self, sync_word: u16) {
+        self.sync_word = sync_word;
+    }
+
+    pub(crate) fn get_sync_word(&self) -> u16 {
+        self.sync_word
+    }
+
+    pub(crate) fn get_status(&self) -> Status {
+        self.status
+    }
+
+    pub(crate) fn add_data(&mut self, data: &[u8]) -> Result<(), Status> {
+        let start = self.get_length() as usize;
+        if data.len() > T - start {
+            self.status = Status::PacketOverflow;
+            return Err(self.status);
+        }
+        self.data[start..start + data.len()].copy_from_slice(data);
+        self.set_field(Self::LENGTH, 2, (start + data.len()) as u32);
+        Ok(())
+    }
+
+    pub(crate) fn pack_data(&mut self, request: u16, data: &[u8]) -> Result<(), Status> {
+        self.reset();
+        self.set_request(request);
+        self.add_data(data)?;
+        self.set_response(response::SUCCESS);
+        self.pack();
+        Ok(())
+    }
+
+    pub(crate) fn pack(&mut self) {
+        self.set_field(0, 2, self.sync_word as u32);
+        let checksum = self.checksum();
+        self.set_field(Self::CHECKSUM, L::CHECKSUM_SIZE, checksum);
+        self.internal_counter = 0;
+    }
+
+    pub(crate) fn checksum(&self) -> u32 {
+        L::checksum(&self.header[Self::REQUEST..], self.payload())
+    }
+
+    pub(crate) fn validate(&self) -> bool {
+        self.checksum() == self.get_checksum()
+    }
+
+    pub(crate) fn payload(&self) -> &[u8] {
+        &self.data[..(self.get_length() as usize).min(T)]
+    }
+
+    pub(crate) fn length(&self) -> usize {
+        H + self.payload().len()
+    }
+
+    pub(crate) fn get_byte(&mut self) -> Result<u8, Status> {
+        let index = self.internal_counter;
+        let byte = if index < H {
+            self.header[index]
+        } else if index < self.length() {
+            self.data[index - H]
+        } else {
+            self.status = Status::GetByteFinished;
+            return Err(self.status);
+        };
+        self.internal_counter += 1;
+        self.status = Status::Ok;
+        Ok(byte)
+    }
+
+    pub(crate) fn write_wire(&self, out: &mut [u8]) -> Result<usize, Status> {
+        let length = self.length();
+        if out.len() < length {
+            return Err(Status::BufferTooSmall);
+        }
+        out[..H].copy_from_slice(&self.header);
+        out[H..length].copy_from_slice(self.payload());
+        Ok(length)
+    }
+
+    pub(crate) fn construct(&mut self, byte: u8) -> Result<(), Status> {
+        let position = self.internal_counter;
+
+        if position < 2 && byte != self.header_byte(position) {
+            // The rejected byte can still start the header of the next frame
+            if position == 1 && byte == self.header_byte(0) {
+                self.header[0] = byte;
+                self.status = Status::HeaderBytesNotFound;
+                return Err(self.status);
+            }
+            return self.construct_done(Status::HeaderBytesNotFound);
+        }
+        if position < H {
+            self.header[position] = byte;
+        } else {
+            self.data[position - H] = byte;
+        }
+        self.internal_counter += 1;
+
+        if self.internal_counter == H && self.get_length() as usize > T {
+            return self.construct_done(Status::InvalidDataLengthDetected);
+        }
+        if self.internal_counter >= H && self.internal_counter == self.length() {
+            if !self.validate() {
+                return self.construct_done(Status::ChecksumError);
+            }
+            self.internal_counter = 0;
+            self.status = Status::PacketReceived;
+            return Ok(());
+        }
+
+        self.status = Status::PacketBuilding;
+        Err(self.status)
+    }
+
+    pub(crate) fn construct_slice(&mut self, bytes: &[u8]) -> Result<usize, Status> {
+        for (index, byte) in bytes.iter().enumerate() {
+            match self.construct(*byte) {
+                Ok(_) => return Ok(index + 1),
+                Err(Status::PacketBuilding) | Err(Status::HeaderBytesNotFound) => {}
+                Err(status) => return Err(status),
+            }
+        }
+        Err(Status::PacketBuilding)
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.header = [0; H];
+        self.internal_counter = 0;
+        self.status = Status::Ok;
+    }
+
+    fn construct_done(&mut self, status: Status) -> Result<(), Status> {
+        self.internal_counter = 0;
+        self.status = status;
+        Err(status)
+    }
+
+    fn header_byte(&self, position: usize) -> u8 {
+        if L::BIG_ENDIAN {
+            self.sync_word.to_be_bytes()[position]
+        } else {
+            self.sync_word.to_le_bytes()[position]
+        }
+    }
+
+    fn get_field(&self, offset: usize, size: usize) -> u32 {
+        (0..size).fold(0, |value, index| {
+            value | (self.header[offset + index] as u32) << Self::shift(size, index)
+        })
+    }
+
+    fn set_field(&mut self, offset: usize, size: usize, value: u32) {
+        for index in 0..size {
+            self.header[offset + index] = (value >> Self::shift(size, index)) as u8;
+        }
+    }
+
+    /// Bit position of byte `index` of a `size` byte field
+    fn shift(size: usize, index: usize) -> usize {
+        if L::BIG_ENDIAN {
+            8 * (size - 1 - index)
+        } else {
+            8 * index
+        }
+    }
+}
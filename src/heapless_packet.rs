@@ -0,0 +1,198 @@
+//! Packet with a `heapless::Vec` payload. Enabled with features = ["heapless"].
+
+use heapless::Vec;
+
+use crate::{crc16, Packet, Status, FLEM_HEADER, FLEM_HEADER_SIZE};
+
+/// Outgoing packet that keeps the payload in a `heapless::Vec`, for code that already
+/// builds payloads with `heapless`. The wire format is the same as `Packet`, convert with
+/// `to_packet()` / `From<&Packet<T>>` or write the frame with `write_wire()`. The checksum
+/// is computed when the frame is written, with `ChecksumCoverage::PayloadOnly`.
+///
+/// `heapless::Vec` stores its capacity inline, so a `HeaplessPacket<T>` takes about as much
+/// RAM as a `Packet<T>`. To queue many small packets cheaply, use a smaller `T`.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use flem::heapless_packet::HeaplessPacket;
+///
+///     let mut tx = HeaplessPacket::<16>::new();
+///     tx.set_request(0x10);
+///     tx.add_data(&[1, 2, 3]).unwrap();
+///
+///     let mut wire = [0u8; flem::FLEM_HEADER_SIZE + 16];
+///     let length = tx.write_wire(&mut wire).unwrap();
+///
+///     let mut rx = flem::Packet::<16>::new();
+///     assert_eq!(rx.construct_slice(&wire[..length]), Ok(length));
+///     assert_eq!(rx.payload(), [1, 2, 3]);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaplessPacket<const T: usize> {
+    request: u16,
+    response: u16,
+    #[cfg(feature = "protocol-v2")]
+    sequence: u16,
+    #[cfg(feature = "protocol-v2")]
+    flags: u8,
+    #[cfg(feature = "protocol-v2")]
+    channel: u8,
+    data: Vec<u8, T>,
+}
+
+impl<const T: usize> HeaplessPacket<T> {
+    pub fn new() -> Self {
+        assert!(T < u16::MAX as usize, "<T> should be u16::MAX or less");
+        Self {
+            request: 0,
+            response: 0,
+            #[cfg(feature = "protocol-v2")]
+            sequence: 0,
+            #[cfg(feature = "protocol-v2")]
+            flags: 0,
+            #[cfg(feature = "protocol-v2")]
+            channel: 0,
+            data: Vec::new(),
+        }
+    }
+
+    /// Sets the request, debug builds panic on reserved codes as `Packet::set_request`
+    pub fn set_request(&mut self, request: u16) {
+        crate::debug_assert_request(request);
+        self.request = request;
+    }
+
+    pub fn get_request(&self) -> u16 {
+        self.request
+    }
+
+    pub fn set_response(&mut self, response: u16) {
+        self.response = response;
+    }
+
+    pub fn get_response(&self) -> u16 {
+        self.response
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    pub fn set_sequence(&mut self, sequence: u16) {
+        self.sequence = sequence;
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    pub fn get_sequence(&self) -> u16 {
+        self.sequence
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    pub fn set_flags(&mut self, flags: u8) {
+        self.flags = flags;
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    pub fn get_flags(&self) -> u8 {
+        self.flags
+    }
+
+    /// Sets the channel, see `Packet::set_channel`
+    #[cfg(feature = "protocol-v2")]
+    pub fn set_channel(&mut self, channel: u8) {
+        self.channel = channel;
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    pub fn get_channel(&self) -> u8 {
+        self.channel
+    }
+
+    /// Adds data to the payload. Returns `Status::PacketOverflow` and leaves the payload
+    /// untouched if it doesn't fit.
+    pub fn add_data(&mut self, data: &[u8]) -> Result<(), Status> {
+        self.data
+            .extend_from_slice(data)
+            .map_err(|_| Status::PacketOverflow)
+    }
+
+    /// The payload
+    pub fn payload(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Empties the payload and clears the header fields
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Number of bytes the packet takes on the wire, header and payload
+    pub fn wire_len(&self) -> usize {
+        FLEM_HEADER_SIZE + self.data.len()
+    }
+
+    /// Writes the frame, header with the checksum and payload, to `out`. Returns the number
+    /// of bytes written, or `Status::BufferTooSmall` if `out` is shorter than `wire_len()`.
+    pub fn write_wire(&self, out: &mut [u8]) -> Result<usize, Status> {
+        let length = self.wire_len();
+        if out.len() < length {
+            return Err(Status::BufferTooSmall);
+        }
+
+        out[0..2].copy_from_slice(&FLEM_HEADER.to_le_bytes());
+        out[4..6].copy_from_slice(&self.request.to_le_bytes());
+        out[6..8].copy_from_slice(&self.response.to_le_bytes());
+        out[8..10].copy_from_slice(&(self.data.len() as u16).to_le_bytes());
+        #[cfg(feature = "protocol-v2")]
+        {
+            out[10..12].copy_from_slice(&self.sequence.to_le_bytes());
+            out[12] = self.flags;
+            out[13] = self.channel;
+        }
+        out[FLEM_HEADER_SIZE..length].copy_from_slice(&self.data);
+
+        let checksum = crc16(out[4..length].iter());
+        out[2..4].copy_from_slice(&checksum.to_le_bytes());
+
+        Ok(length)
+    }
+
+    /// Converts to a packed `Packet`
+    pub fn to_packet(&self) -> Packet<T> {
+        let mut packet = Packet::new();
+        packet.request = self.request;
+        packet.set_response(self.response);
+        #[cfg(feature = "protocol-v2")]
+        {
+            packet.sequence = self.sequence;
+            packet.flags = self.flags;
+            packet.channel = self.channel;
+        }
+        // Can't fail, the Vec holds at most T bytes
+        let _ = packet.add_data(&self.data);
+        packet.pack();
+        packet
+    }
+}
+
+impl<const T: usize> Default for HeaplessPacket<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const T: usize> From<&Packet<T>> for HeaplessPacket<T> {
+    fn from(packet: &Packet<T>) -> Self {
+        let mut converted = Self::new();
+        converted.request = packet.get_request();
+        converted.response = packet.get_response();
+        #[cfg(feature = "protocol-v2")]
+        {
+            converted.sequence = packet.get_sequence();
+            converted.flags = packet.get_flags();
+            converted.channel = packet.get_channel();
+        }
+        // Can't fail, the payload of a Packet<T> is at most T bytes
+        let _ = converted.data.extend_from_slice(packet.payload());
+        converted
+    }
+}
@@ -0,0 +1,125 @@
+//! Keep-alive packets, so the host notices a dead client during long idle periods.
+//!
+//! The client sends a `request::HEARTBEAT` event (`response::ASYNC`) every interval with a
+//! wrapping u16 count as the payload, the host watches for them and counts the missed
+//! ones. Time is given as ticks by the caller, any monotonic counter that wraps at
+//! `u32::MAX` (milliseconds, timer ticks, ...), so nothing depends on a clock.
+
+use crate::{request, Packet};
+
+/// Sends a heartbeat every `interval` ticks from the client.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use flem::heartbeat::{Heartbeat, HeartbeatMonitor};
+///
+///     let mut heartbeat = Heartbeat::<8>::new(1000);
+///     let mut monitor = HeartbeatMonitor::new();
+///
+///     let beat = heartbeat.tick(0).unwrap();
+///     assert!(monitor.observe(&beat, 5));
+///     assert!(heartbeat.tick(500).is_none(), "Not due yet");
+///
+///     assert!(monitor.is_alive(2000, 3000));
+///     assert!(!monitor.is_alive(4000, 3000));
+/// }
+/// ```
+pub struct Heartbeat<const T: usize> {
+    interval: u32,
+    last_sent: Option<u32>,
+    count: u16,
+}
+
+impl<const T: usize> Heartbeat<T> {
+    /// Sends the first heartbeat on the first `tick()`, then every `interval` ticks
+    pub const fn new(interval: u32) -> Self {
+        Self {
+            interval,
+            last_sent: None,
+            count: 0,
+        }
+    }
+
+    /// Changes the interval, the next heartbeat is due `interval` ticks after the last one
+    pub fn set_interval(&mut self, interval: u32) {
+        self.interval = interval;
+    }
+
+    /// Returns the packed heartbeat to send if it is due at `now`, otherwise None. Also
+    /// None if T is too small for the 2 byte count.
+    pub fn tick(&mut self, now: u32) -> Option<Packet<T>> {
+        if let Some(last_sent) = self.last_sent {
+            if now.wrapping_sub(last_sent) < self.interval {
+                return None;
+            }
+        }
+
+        let mut packet = Packet::new();
+        packet
+            .pack_async(request::HEARTBEAT, &self.count.to_le_bytes())
+            .ok()?;
+        self.last_sent = Some(now);
+        self.count = self.count.wrapping_add(1);
+        Some(packet)
+    }
+}
+
+/// Watches the heartbeats of a client on the host.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeartbeatMonitor {
+    last_seen: Option<u32>,
+    last_count: Option<u16>,
+    missed: u32,
+}
+
+impl HeartbeatMonitor {
+    pub const fn new() -> Self {
+        Self {
+            last_seen: None,
+            last_count: None,
+            missed: 0,
+        }
+    }
+
+    /// Records `packet` if it is a heartbeat received at `now`. Gaps in the count are
+    /// added to `missed()`, except before a count of 0, a restarted client. A count that
+    /// isn't ahead of the last one, a repeated or reordered heartbeat, only updates
+    /// `last_seen()`. Returns false if `packet` isn't a heartbeat, it is left for the caller.
+    pub fn observe<const T: usize>(&mut self, packet: &Packet<T>, now: u32) -> bool {
+        if !packet.is_async() || packet.get_request() != request::HEARTBEAT {
+            return false;
+        }
+        let Ok(count) = packet.read_u16_le(0) else {
+            return false;
+        };
+
+        self.last_seen = Some(now);
+        if let Some(last_count) = self.last_count.filter(|_| count != 0) {
+            // Counts up to half the u16 range behind the last one are late, not ahead
+            let ahead = count.wrapping_sub(last_count);
+            if ahead == 0 || ahead >= 0x8000 {
+                return true;
+            }
+            self.missed = self.missed.saturating_add(ahead as u32 - 1);
+        }
+        self.last_count = Some(count);
+        true
+    }
+
+    /// Returns true if a heartbeat was received at most `timeout` ticks before `now`
+    pub fn is_alive(&self, now: u32, timeout: u32) -> bool {
+        self.last_seen
+            .is_some_and(|last_seen| now.wrapping_sub(last_seen) <= timeout)
+    }
+
+    /// Number of heartbeats missing from the counts received so far
+    pub fn missed(&self) -> u32 {
+        self.missed
+    }
+
+    /// Tick of the last heartbeat, None if none was received
+    pub fn last_seen(&self) -> Option<u32> {
+        self.last_seen
+    }
+}
@@ -0,0 +1,282 @@
+//! The blocking request/response call of a host: send a request to a client and wait for its
+//! response. The counterpart of `client::Engine`.
+//!
+//! There are no timers in `no_std`, so the wait is bounded by a read budget instead: the
+//! number of times the channel is asked for a byte, whether or not one is available.
+
+use crate::{request, response, DataId, Packet, ProtocolInfo, Status};
+
+/// Reads `Host::request` makes while waiting for a response unless changed with
+/// `set_read_budget`
+pub const DEFAULT_READ_BUDGET: usize = 4096;
+
+/// The byte link between a host and a client
+pub trait ByteChannel {
+    /// Sends all of `bytes`. Returns `Status::IoError` if the link fails.
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Status>;
+    /// Returns the next received byte, or None if no byte is available right now
+    fn read(&mut self) -> Option<u8>;
+}
+
+/// Sends requests and receives their responses over a `ByteChannel`, one at a time.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use flem::host::{ByteChannel, Host};
+///     use flem::{Packet, Status};
+///
+///     // A client that answers every request with the same response
+///     struct Canned(Vec<u8>);
+///
+///     impl ByteChannel for Canned {
+///         fn write(&mut self, _bytes: &[u8]) -> Result<(), Status> {
+///             Ok(())
+///         }
+///         fn read(&mut self) -> Option<u8> {
+///             (!self.0.is_empty()).then(|| self.0.remove(0))
+///         }
+///     }
+///
+///     let response = Packet::<16>::with_payload(0x10, &[7]).unwrap();
+///     let mut channel = Canned(response.bytes().to_vec());
+///
+///     let mut host = Host::<16>::new();
+///     let rx = host.request(&mut channel, 0x10, &[1, 2]).unwrap();
+///     assert_eq!(rx.payload(), &[7]);
+///
+///     // Nothing more is sent, the read budget runs out
+///     assert_eq!(host.request(&mut channel, 0x10, &[]).err(), Some(Status::NoResponse));
+/// }
+/// ```
+pub struct Host<const T: usize> {
+    tx: Packet<T>,
+    rx: Packet<T>,
+    read_budget: usize,
+    peer: Option<ProtocolInfo>,
+    /// Transaction ID of the next request, None if requests aren't tagged
+    transaction: Option<u8>,
+}
+
+impl<const T: usize> Host<T> {
+    pub fn new() -> Self {
+        Self {
+            tx: Packet::new(),
+            rx: Packet::new(),
+            read_budget: DEFAULT_READ_BUDGET,
+            peer: None,
+            transaction: None,
+        }
+    }
+
+    /// Limits the reads of one `request()`, at least 1
+    pub fn set_read_budget(&mut self, reads: usize) {
+        self.read_budget = reads.max(1);
+    }
+
+    /// Tags every request with a wrapping transaction ID in front of the payload, see
+    /// `Packet::pack_data_seq`. The client's `client::Engine` then answers a request it
+    /// has already handled from its cache (with protocol-v2, see
+    /// `Engine::with_duplicate_detection`). The ID of the last request is the first byte
+    /// of `tx().payload()`.
+    ///
+    /// The ID advances with every `request()`. After `Status::NoResponse` use `retry()`,
+    /// which sends the request again with the same ID so the client doesn't handle it
+    /// twice. With protocol-v2, responses tagged with another ID, late responses to an
+    /// earlier request, are skipped.
+    pub fn set_transactions(&mut self, enabled: bool) {
+        self.transaction = if enabled {
+            Some(self.transaction.unwrap_or(0))
+        } else {
+            None
+        };
+    }
+
+    /// Sends `request` with `payload` and waits for the response. Returns the response, or:
+    /// - PacketOverflow - The payload doesn't fit the packet
+    /// - IoError - The channel failed to send the request
+    /// - NoResponse - No complete response arrived within the read budget
+    /// - ChecksumError - The response was corrupted, or the client received a corrupted
+    ///   request and answered with `response::CHECKSUM_ERROR`
+    /// - UnrecognizedRequest - The client answered with `response::UNKNOWN_REQUEST`, or the
+    ///   response belongs to another request
+    /// - InvalidDataLengthDetected - The response is longer than the packet
+    ///
+    /// Other error responses, for example `response::BUSY`, are returned as packets.
+    /// Events the client sends while the host waits (`response::ASYNC`) are skipped, see
+    /// `request_with_events` to handle them.
+    pub fn request<C: ByteChannel>(
+        &mut self,
+        channel: &mut C,
+        request: u16,
+        payload: &[u8],
+    ) -> Result<Packet<T>, Status> {
+        self.request_with_events(channel, request, payload, |_| {})
+    }
+
+    /// Same as `request()`, events the client sends while the host waits for the response,
+    /// for example `console` log text, are passed to `on_event` instead of being skipped.
+    pub fn request_with_events<C, E>(
+        &mut self,
+        channel: &mut C,
+        request: u16,
+        payload: &[u8],
+        on_event: E,
+    ) -> Result<Packet<T>, Status>
+    where
+        C: ByteChannel,
+        E: FnMut(&Packet<T>),
+    {
+        match self.transaction {
+            Some(transaction) => {
+                self.tx.pack_data_seq(request, transaction, payload)?;
+                self.transaction = Some(transaction.wrapping_add(1));
+            }
+            None => self.tx.pack_data(request, payload)?,
+        }
+        self.exchange(channel, on_event)
+    }
+
+    /// Sends the last request again, unchanged, and waits for the response. Meant for
+    /// `Status::NoResponse`: with `set_transactions` the request keeps its transaction ID,
+    /// so a client that already handled it answers from its cache instead of handling it
+    /// again. Returns the errors of `request()`, and `Status::UnspecifiedError` if no
+    /// request was sent yet.
+    pub fn retry<C: ByteChannel>(&mut self, channel: &mut C) -> Result<Packet<T>, Status> {
+        self.retry_with_events(channel, |_| {})
+    }
+
+    /// Same as `retry()`, events are passed to `on_event`, see `request_with_events()`
+    pub fn retry_with_events<C, E>(
+        &mut self,
+        channel: &mut C,
+        on_event: E,
+    ) -> Result<Packet<T>, Status>
+    where
+        C: ByteChannel,
+        E: FnMut(&Packet<T>),
+    {
+        if self.tx.get_header() != self.tx.get_sync_word() {
+            return Err(Status::UnspecifiedError);
+        }
+        self.exchange(channel, on_event)
+    }
+
+    /// Sends `tx` and waits for its response
+    fn exchange<C, E>(&mut self, channel: &mut C, mut on_event: E) -> Result<Packet<T>, Status>
+    where
+        C: ByteChannel,
+        E: FnMut(&Packet<T>),
+    {
+        channel.write(self.tx.bytes())?;
+
+        let request = self.tx.get_request();
+        self.rx.reset_lazy();
+        for _ in 0..self.read_budget {
+            let Some(byte) = channel.read() else {
+                continue;
+            };
+
+            match self.rx.construct(byte) {
+                Ok(_) if self.rx.is_async() => {
+                    on_event(&self.rx);
+                    self.rx.reset_lazy();
+                }
+                Ok(_) if self.is_stale() => self.rx.reset_lazy(),
+                Ok(_) => return self.check_response(request),
+                Err(Status::PacketBuilding)
+                | Err(Status::HeaderBytesNotFound)
+                | Err(Status::DiscardingOversized) => {}
+                Err(status) => return Err(status),
+            }
+        }
+
+        Err(Status::NoResponse)
+    }
+
+    /// Asks the client for its `DataId`, packed with `Packet::pack_id`. Returns the errors of
+    /// `request()` and `Packet::parse_id`.
+    pub fn request_id<C: ByteChannel>(&mut self, channel: &mut C) -> Result<DataId, Status> {
+        self.request(channel, request::ID, &[])?.parse_id()
+    }
+
+    /// Asks the client for its `ProtocolInfo` with `request::PROTOCOL`, send it before
+    /// anything else. The info is recorded, see `peer()`, and requests are limited to the
+    /// client's max packet size.
+    ///
+    /// Returns None if the client predates `request::PROTOCOL` and answered with
+    /// `response::UNKNOWN_REQUEST`, it speaks the same wire format as this host but newer
+    /// requests can't be relied on. Otherwise returns the errors of `request()` and
+    /// `ProtocolInfo::from_payload`.
+    pub fn negotiate<C: ByteChannel>(
+        &mut self,
+        channel: &mut C,
+    ) -> Result<Option<ProtocolInfo>, Status> {
+        self.peer = None;
+        self.tx.set_peer_limit(u16::MAX);
+        match self.request(channel, request::PROTOCOL, &[]) {
+            Ok(rx) => {
+                let info = ProtocolInfo::from_payload(rx.payload())?;
+                self.tx.set_peer_limit(info.max_packet_size);
+                self.peer = Some(info);
+                Ok(Some(info))
+            }
+            Err(Status::UnrecognizedRequest)
+                if self.rx.get_response() == response::UNKNOWN_REQUEST =>
+            {
+                Ok(None)
+            }
+            Err(status) => Err(status),
+        }
+    }
+
+    /// The info of the client from the last `negotiate()`, None if it didn't answer
+    pub fn peer(&self) -> Option<ProtocolInfo> {
+        self.peer
+    }
+
+    /// The last request sent
+    pub fn tx(&self) -> &Packet<T> {
+        &self.tx
+    }
+
+    /// The packet being received, for its statistics
+    pub fn rx(&self) -> &Packet<T> {
+        &self.rx
+    }
+
+    /// Returns true if the response is tagged with another transaction ID than the request,
+    /// it answers an earlier request that timed out
+    #[cfg(feature = "protocol-v2")]
+    fn is_stale(&self) -> bool {
+        let sent = self.tx.get_tagged_transaction();
+        match (sent, self.rx.get_tagged_transaction()) {
+            (Some(sent), Some(echoed)) => sent != echoed,
+            _ => false,
+        }
+    }
+
+    /// Without protocol-v2 a transaction ID can't be told apart from data
+    #[cfg(not(feature = "protocol-v2"))]
+    fn is_stale(&self) -> bool {
+        false
+    }
+
+    fn check_response(&self, request: u16) -> Result<Packet<T>, Status> {
+        match self.rx.get_response() {
+            response::CHECKSUM_ERROR => return Err(Status::ChecksumError),
+            response::UNKNOWN_REQUEST => return Err(Status::UnrecognizedRequest),
+            _ => {}
+        }
+        if self.rx.get_request() != request {
+            return Err(Status::UnrecognizedRequest);
+        }
+        Ok(self.rx)
+    }
+}
+
+impl<const T: usize> Default for Host<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,11 +1,54 @@
 #![no_std]
 
-use core::fmt::{Debug, Error, Formatter};
+use core::fmt::{self, Debug, Error, Formatter};
+use core::mem::MaybeUninit;
+use core::ops::{Index, IndexMut};
+use core::slice::SliceIndex;
 
+use checksum::ChecksumAlgorithm;
+
+#[cfg(feature = "async")]
+pub mod async_io;
+#[cfg(feature = "embedded-io")]
+mod blocking;
 pub mod buffer;
+pub mod builder;
+pub mod checksum;
+pub mod client;
+#[cfg(feature = "cobs")]
+pub mod cobs;
+pub mod console;
+pub mod correlation;
+#[cfg(feature = "defmt")]
+mod defmt_impl;
+#[cfg(feature = "dfu")]
+pub mod dfu;
+pub mod dispatch;
+#[cfg(feature = "protocol-v2")]
+pub mod fragment;
+mod frame;
+#[cfg(feature = "heapless")]
+pub mod heapless_packet;
+pub mod heartbeat;
+pub mod host;
+#[cfg(feature = "nb")]
+pub mod nb;
+pub mod packet32;
+pub mod packet_be;
+#[cfg(all(feature = "std", feature = "protocol-v2"))]
+pub mod reassembly;
+pub mod router;
+#[cfg(feature = "serde")]
+mod serialization;
+#[cfg(feature = "sim")]
+pub mod sim;
+#[cfg(feature = "std")]
+pub mod stdio;
 pub mod traits;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Status {
     Ok,
     PacketReceived,
@@ -19,50 +62,208 @@ pub enum Status {
     UnspecifiedError,
     UnrecognizedRequest,
     InvalidDataLengthDetected,
+    /// A packet's sequence number didn't follow the previous packet of the same payload
+    SequenceGap,
+    /// The payload of a packet longer than the data buffer is being skipped
+    DiscardingOversized,
+    /// The stream or transport under the packet failed
+    IoError,
+    /// The buffer passed in is shorter than the data that has to be copied into it
+    BufferTooSmall,
+    /// No response arrived within the read budget, see `host::Host`
+    NoResponse,
+    /// A read past the end of the payload, see `Packet::read_bytes`
+    PayloadUnderflow,
+    /// The payload isn't valid UTF-8, see `Packet::payload_as_str`
+    InvalidUtf8,
+}
+
+/// A receive error with the context needed to debug a link, returned by
+/// `Packet::construct_detailed` and `Packet::construct_slice_detailed`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ConstructError {
+    /// The status `construct()` returned
+    pub status: Status,
+    /// Position of the byte within the current frame, 0 is the first header byte
+    pub byte_index: u32,
+    /// Checksum computed over the received bytes, only set for `Status::ChecksumError`
+    pub expected_checksum: Option<u16>,
+    /// Checksum field of the received packet, only set for `Status::ChecksumError`
+    pub received_checksum: Option<u16>,
+}
+
+impl From<ConstructError> for Status {
+    fn from(error: ConstructError) -> Self {
+        error.status
+    }
+}
+
+/// What a packet holds and where its parser is, returned by `Packet::explain`. The
+/// `Display` output fits on one log line, for example
+/// `hdr=0x5555 req=0x0010 rsp=0x0001 len=4 rx=4 crc=0x1234/0x1234 idx=0 st=PacketReceived`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PacketDiagnostics {
+    /// Header field, the sync word once packed or received
+    pub header: u16,
+    pub request: u16,
+    pub response: u16,
+    /// Length field, the declared payload length
+    pub length: u16,
+    /// Payload bytes received so far while a packet is being received, otherwise the
+    /// payload bytes in the data buffer
+    pub payload_received: usize,
+    /// Checksum field
+    pub stored_checksum: u16,
+    /// Checksum computed over the packet, None while a packet is being received
+    pub computed_checksum: Option<u16>,
+    pub status: Status,
+    /// Position of the next byte `construct()` or `get_byte()` handles
+    pub byte_index: u32,
+}
+
+impl PacketDiagnostics {
+    /// True if the checksum was computed and matches the checksum field
+    pub fn checksum_matches(&self) -> bool {
+        self.computed_checksum == Some(self.stored_checksum)
+    }
+}
+
+impl fmt::Display for PacketDiagnostics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "hdr={:#06x} req={:#06x} rsp={:#06x} len={} rx={} crc={:#06x}/",
+            self.header,
+            self.request,
+            self.response,
+            self.length,
+            self.payload_received,
+            self.stored_checksum
+        )?;
+        match self.computed_checksum {
+            Some(computed) => write!(f, "{:#06x}", computed)?,
+            None => f.write_str("-")?,
+        }
+        write!(f, " idx={} st={:?}", self.byte_index, self.status)
+    }
 }
 
 const FLEM_ID_NAME_SIZE: usize = 25;
 
-/// Const ID Size:
-///     - 25 bytes Name buffer
-///     - 2 bytes for packet size
-///     - 3 bytes for major, minor, patch
-const FLEM_ID_SIZE: usize = FLEM_ID_NAME_SIZE + (u16::BITS as usize / 8 as usize) + 3;
+/// Why a `DataId` couldn't be decoded, see `TryFrom<&[u8]> for DataId`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DataIdError {
+    /// The data is shorter than `DataId::<N>::SIZE`
+    TooShort,
+    /// The max packet size is 0, no partner can receive packets that small
+    ZeroPacketSize,
+}
+
+/// Both errors are reported as `Status::InvalidDataLengthDetected`
+impl From<DataIdError> for Status {
+    fn from(_: DataIdError) -> Self {
+        Status::InvalidDataLengthDetected
+    }
+}
+
+/// Identifies a device: name, version and the max packet size it can receive. The name
+/// buffer holds `N` characters and defaults to 25.
 #[repr(C)]
-pub struct DataId {
+pub struct DataId<const N: usize = FLEM_ID_NAME_SIZE> {
     major: u8,
     minor: u8,
     patch: u8,
     max_packet_size: u16,
-    name: [char; FLEM_ID_NAME_SIZE as usize],
+    name: [char; N],
 }
 
 impl DataId {
+    /// Creates a DataId with the default 25 character name buffer. Use
+    /// `DataId::<N>::with_name` for other sizes.
     pub fn new(name: &str, major: u8, minor: u8, patch: u8, packet_size: usize) -> DataId {
+        DataId::with_name(name, major, minor, patch, packet_size)
+    }
+
+    /// Decodes a DataId with the default 25 character name buffer. Use
+    /// `DataId::<N>::parse` for other sizes. Returns None if `data` is shorter than
+    /// `DataId::SIZE` or the max packet size is 0, see `TryFrom<&[u8]>` for the reason.
+    pub fn from(data: &[u8]) -> Option<DataId> {
+        DataId::try_from(data).ok()
+    }
+
+    /// Decodes a DataId with the default 25 character name buffer, see `DataId::<N>::parse`.
+    pub fn from_bytes(data: &[u8]) -> Result<DataId, Status> {
+        DataId::parse(data)
+    }
+}
+
+impl<const N: usize> DataId<N> {
+    /// Size of the ID on the wire:
+    ///     - N bytes Name buffer
+    ///     - 2 bytes for packet size
+    ///     - 3 bytes for major, minor, patch
+    pub const SIZE: usize = N + (u16::BITS as usize / 8_usize) + 3;
+
+    /// Size of the ID on the wire when packed with `pack_id(id, false)`, the name is sent
+    /// as N little endian u32 unicode scalar values.
+    pub const UNICODE_SIZE: usize = 4 * N + (u16::BITS as usize / 8_usize) + 3;
+
+    /// Creates a DataId with a name buffer of const N: usize characters
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let id = flem::DataId::<40>::with_name("A name longer than 25 characters", 1, 0, 0, 512);
+    ///
+    ///     assert_eq!(flem::DataId::<40>::SIZE, 45);
+    /// }
+    /// ```
+    pub fn with_name(name: &str, major: u8, minor: u8, patch: u8, packet_size: usize) -> Self {
         let mut id = DataId {
-            major: major,
-            minor: minor,
-            patch: patch,
-            name: ['\0'; FLEM_ID_NAME_SIZE as usize],
+            major,
+            minor,
+            patch,
+            name: ['\0'; N],
             max_packet_size: packet_size as u16,
         };
 
         let version_size: usize = name.len();
 
         assert!(
-            version_size <= FLEM_ID_NAME_SIZE,
-            "Version should be 25 characters or less"
+            version_size <= N,
+            "Version should be {} characters or less",
+            N
         );
 
         for a in 0..version_size {
-            id.name[a as usize] = name.as_bytes()[a as usize] as char;
+            id.name[a] = name.as_bytes()[a] as char;
         }
         id
     }
 
-    pub fn from(data: &[u8]) -> Option<DataId> {
-        let mut buffer = ['\0'; FLEM_ID_NAME_SIZE as usize];
-        let mut packet_length_buffer = [0 as u8; 2];
+    /// Decodes a DataId with a name buffer of const N: usize characters, as packed by
+    /// `pack_id(id, true)`. Bytes past `SIZE` are ignored, so the whole data buffer of a
+    /// packet can be passed in.
+    ///
+    /// Returns `Status::InvalidDataLengthDetected` if `data` is shorter than `SIZE` or the
+    /// max packet size is 0, no partner can receive packets that small.
+    pub fn parse(data: &[u8]) -> Result<Self, Status> {
+        Self::try_from(data).map_err(Status::from)
+    }
+
+    fn parse_ascii(data: &[u8]) -> Result<Self, DataIdError> {
+        if data.len() < Self::SIZE {
+            return Err(DataIdError::TooShort);
+        }
+        if data[3] == 0 && data[4] == 0 {
+            return Err(DataIdError::ZeroPacketSize);
+        }
+
+        let mut buffer = ['\0'; N];
+        let mut packet_length_buffer = [0_u8; 2];
         let mut major: u8 = 0;
         let mut minor: u8 = 0;
         let mut patch: u8 = 0;
@@ -85,7 +286,7 @@ impl DataId {
                     packet_length_buffer[packet_size_counter] = *byte;
                     packet_size_counter += 1;
                 }
-                i if (5 <= i && i < FLEM_ID_NAME_SIZE + 5) => {
+                i if (5..N + 5).contains(&i) => {
                     buffer[name_counter] = *byte as char;
                     name_counter += 1;
                 }
@@ -93,7 +294,7 @@ impl DataId {
             }
         }
 
-        Some(DataId {
+        Ok(DataId {
             major,
             minor,
             patch,
@@ -102,7 +303,28 @@ impl DataId {
         })
     }
 
-    pub fn get_name(&self) -> &[char; FLEM_ID_NAME_SIZE] {
+    /// Decodes a DataId packed by `pack_id(id, false)`, `data` must be exactly `UNICODE_SIZE` bytes
+    fn parse_unicode(data: &[u8]) -> Result<Self, Status> {
+        if data.len() != Self::UNICODE_SIZE || (data[3] == 0 && data[4] == 0) {
+            return Err(Status::InvalidDataLengthDetected);
+        }
+
+        let mut name = ['\0'; N];
+        for (c, bytes) in name.iter_mut().zip(data[5..].chunks_exact(4)) {
+            let scalar = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            *c = char::from_u32(scalar).ok_or(Status::InvalidDataLengthDetected)?;
+        }
+
+        Ok(DataId {
+            major: data[0],
+            minor: data[1],
+            patch: data[2],
+            name,
+            max_packet_size: u16::from_le_bytes([data[3], data[4]]),
+        })
+    }
+
+    pub fn get_name(&self) -> &[char; N] {
         &self.name
     }
 
@@ -122,14 +344,315 @@ impl DataId {
         self.max_packet_size
     }
 
+    /// Largest payload both partners can receive: the smaller of this ID's max packet size
+    /// and `local_max`. Pass the result to `Packet::set_peer_limit`.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let peer = flem::DataId::new("Client", 0, 1, 0, 100);
+    ///
+    ///     let mut tx = flem::Packet::<512>::new();
+    ///     tx.set_peer_limit(peer.negotiate(512));
+    ///
+    ///     assert!(tx.pack_data(0x10, &[0u8; 200]).is_err(), "Client can only receive 100 bytes");
+    /// }
+    /// ```
+    pub fn negotiate(&self, local_max: usize) -> u16 {
+        let local_max = u16::try_from(local_max).unwrap_or(u16::MAX);
+        self.max_packet_size.min(local_max)
+    }
+
+    /// Returns true if a `Packet<T>` can hold the largest payload of this ID's partner,
+    /// `max_packet_size <= T`. If it can't, the partner's packets may be rejected with
+    /// `Status::InvalidDataLengthDetected`. Limit what is sent to the partner with
+    /// `negotiate()`.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let peer = flem::DataId::new("Client", 0, 1, 0, 100);
+    ///
+    ///     assert!(peer.packet_size_ok::<128>());
+    ///     assert!(!peer.packet_size_ok::<64>());
+    /// }
+    /// ```
+    pub fn packet_size_ok<const T: usize>(&self) -> bool {
+        self.max_packet_size as usize <= T
+    }
+
     pub fn as_u8_array(&self) -> &[u8] {
         let stream: &[u8] = unsafe {
-            ::core::slice::from_raw_parts((self as *const DataId) as *const u8, FLEM_ID_SIZE)
+            ::core::slice::from_raw_parts((self as *const DataId<N>) as *const u8, Self::SIZE)
         };
         stream
     }
 }
 
+/// Decodes a DataId as packed by `pack_id(id, true)`, see `DataId::parse`. Unlike `parse`
+/// the error tells why the data couldn't be decoded.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use flem::{DataId, DataIdError};
+///
+///     let short = [1, 0, 0];
+///     assert_eq!(DataId::<25>::try_from(&short[..]).err(), Some(DataIdError::TooShort));
+/// }
+/// ```
+impl<const N: usize> TryFrom<&[u8]> for DataId<N> {
+    type Error = DataIdError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        Self::parse_ascii(data)
+    }
+}
+
+/// Receive statistics, counted by `Packet::construct`. Useful for checking the
+/// health of a link, see `Packet::stats()`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Stats {
+    /// Packets received with a valid checksum
+    pub packets_received: u32,
+    /// Packets received with an invalid checksum
+    pub checksum_errors: u32,
+    /// Packets that were too long for the data buffer (PacketOverflow and InvalidDataLengthDetected)
+    pub overflows: u32,
+    /// Times the header bytes were not found
+    pub header_errors: u32,
+    /// Bytes thrown away while searching for the header bytes
+    pub resync_bytes: u32,
+}
+
+impl Stats {
+    /// Size of the stats as a byte array, see `to_bytes()`
+    pub const SIZE: usize = 5 * (u32::BITS as usize / 8);
+
+    pub const fn new() -> Self {
+        Stats {
+            packets_received: 0,
+            checksum_errors: 0,
+            overflows: 0,
+            header_errors: 0,
+            resync_bytes: 0,
+        }
+    }
+
+    /// Converts the stats to a little endian byte array that can be added to a packet,
+    /// in the order: packets_received, checksum_errors, overflows, header_errors, resync_bytes.
+    pub fn to_bytes(&self) -> [u8; Stats::SIZE] {
+        let mut bytes = [0u8; Stats::SIZE];
+        let counters = [
+            self.packets_received,
+            self.checksum_errors,
+            self.overflows,
+            self.header_errors,
+            self.resync_bytes,
+        ];
+        for (chunk, counter) in bytes.chunks_exact_mut(4).zip(counters.iter()) {
+            chunk.copy_from_slice(&counter.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Converts a little endian byte array from `to_bytes()` back into stats. Returns None
+    /// if there aren't enough bytes.
+    pub fn from_bytes(data: &[u8]) -> Option<Stats> {
+        if data.len() < Stats::SIZE {
+            return None;
+        }
+
+        let mut counters = [0u32; 5];
+        for (counter, chunk) in counters.iter_mut().zip(data.chunks_exact(4)) {
+            *counter = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+
+        Some(Stats {
+            packets_received: counters[0],
+            checksum_errors: counters[1],
+            overflows: counters[2],
+            header_errors: counters[3],
+            resync_bytes: counters[4],
+        })
+    }
+
+    fn record(&mut self, result: Result<(), Status>, discarded: u32) {
+        match result {
+            Ok(_) => self.packets_received = self.packets_received.wrapping_add(1),
+            Err(Status::ChecksumError) => {
+                self.checksum_errors = self.checksum_errors.wrapping_add(1);
+            }
+            Err(Status::PacketOverflow) | Err(Status::InvalidDataLengthDetected) => {
+                self.overflows = self.overflows.wrapping_add(1);
+            }
+            Err(Status::HeaderBytesNotFound) => {
+                self.header_errors = self.header_errors.wrapping_add(1);
+                self.resync_bytes = self.resync_bytes.wrapping_add(discarded);
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+/// Header fields of a frame, read straight from the wire bytes without a `Packet`. Useful
+/// for tools that only inspect frames, like a log dissector.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     let mut tx = flem::Packet::<16>::new();
+///     tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+///     let wire = tx.bytes();
+///
+///     let header = flem::PacketHeader::parse(wire).unwrap();
+///     assert_eq!(header.request, 0x10);
+///     assert_eq!(header.length, 3);
+///     assert_eq!(header.payload(wire), [1, 2, 3]);
+/// }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PacketHeader {
+    pub header: u16,
+    pub checksum: u16,
+    pub request: u16,
+    pub response: u16,
+    /// Length of the payload
+    pub length: u16,
+    #[cfg(feature = "protocol-v2")]
+    pub sequence: u16,
+    #[cfg(feature = "protocol-v2")]
+    pub flags: u8,
+    #[cfg(feature = "protocol-v2")]
+    pub channel: u8,
+    /// Offset of the payload in the frame, the payload is `length` bytes from here
+    pub payload_offset: usize,
+}
+
+impl PacketHeader {
+    /// Reads the header of the frame at the start of `bytes`. The checksum isn't checked.
+    ///
+    /// Returns:
+    /// - HeaderBytesNotFound - `bytes` doesn't start with `FLEM_HEADER`
+    /// - InvalidDataLengthDetected - `bytes` is shorter than the header and the payload
+    pub fn parse(bytes: &[u8]) -> Result<PacketHeader, Status> {
+        if bytes.len() < FLEM_HEADER_SIZE {
+            return Err(Status::InvalidDataLengthDetected);
+        }
+
+        let field = |index: usize| u16::from_le_bytes([bytes[index], bytes[index + 1]]);
+
+        if field(0) != FLEM_HEADER {
+            return Err(Status::HeaderBytesNotFound);
+        }
+
+        let header = PacketHeader {
+            header: field(0),
+            checksum: field(2),
+            request: field(4),
+            response: field(6),
+            length: field(8),
+            #[cfg(feature = "protocol-v2")]
+            sequence: field(10),
+            #[cfg(feature = "protocol-v2")]
+            flags: bytes[12],
+            #[cfg(feature = "protocol-v2")]
+            channel: bytes[13],
+            payload_offset: FLEM_HEADER_SIZE,
+        };
+
+        if bytes.len() < header.wire_len() {
+            return Err(Status::InvalidDataLengthDetected);
+        }
+
+        Ok(header)
+    }
+
+    /// Number of bytes the frame takes on the wire, header and payload
+    pub fn wire_len(&self) -> usize {
+        self.payload_offset + self.length as usize
+    }
+
+    /// The payload of the frame in `bytes`, the slice passed to `parse()`
+    pub fn payload<'a>(&self, bytes: &'a [u8]) -> &'a [u8] {
+        &bytes[self.payload_offset..self.wire_len()]
+    }
+}
+
+/// CRC16 IBM of `bytes`, the FLEM checksum
+fn crc16<'a>(bytes: impl Iterator<Item = &'a u8>) -> u16 {
+    crc16_update(0, bytes)
+}
+
+/// Continues a CRC16 IBM checksum with `bytes`
+#[cfg(not(feature = "small-crc"))]
+pub(crate) fn crc16_update<'a>(mut crc: u16, bytes: impl Iterator<Item = &'a u8>) -> u16 {
+    for byte in bytes {
+        let ptr = *byte as u16;
+        let lut_index = (crc ^ ptr) as u8;
+        let mut tmp_crc = CRC16_TAB[lut_index as usize];
+        tmp_crc ^= crc >> 8;
+        crc = tmp_crc;
+    }
+    crc
+}
+
+/// Continues a CRC16 IBM checksum with `bytes`, a nibble at a time
+#[cfg(feature = "small-crc")]
+pub(crate) fn crc16_update<'a>(mut crc: u16, bytes: impl Iterator<Item = &'a u8>) -> u16 {
+    for byte in bytes {
+        let byte = *byte as u16;
+        crc = (crc >> 4) ^ CRC16_NIBBLE_TAB[((crc ^ byte) & 0x0F) as usize];
+        crc = (crc >> 4) ^ CRC16_NIBBLE_TAB[((crc ^ (byte >> 4)) & 0x0F) as usize];
+    }
+    crc
+}
+
+/// Checks a complete frame in one go, without a `Packet`: the header bytes, the length
+/// against the size of `bytes` and the checksum (`ChecksumCoverage::PayloadOnly`).
+///
+/// Returns:
+/// - HeaderBytesNotFound - `bytes` doesn't start with `FLEM_HEADER`
+/// - InvalidDataLengthDetected - `bytes` is shorter than the header and the payload
+/// - PacketOverflow - `bytes` is longer than the header and the payload
+/// - ChecksumError - The computed checksum does not match the sent checksum
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     let mut tx = flem::Packet::<16>::new();
+///     tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+///
+///     assert!(flem::validate_slice(tx.bytes()).is_ok());
+/// }
+/// ```
+pub fn validate_slice(bytes: &[u8]) -> Result<(), Status> {
+    let header = PacketHeader::parse(bytes)?;
+    if bytes.len() > header.wire_len() {
+        return Err(Status::PacketOverflow);
+    }
+    if crc16(bytes[4..].iter()) != header.checksum {
+        return Err(Status::ChecksumError);
+    }
+    Ok(())
+}
+
+/// Bytes covered by the checksum. Both partners must use the same coverage.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ChecksumCoverage {
+    /// Everything after the checksum bytes, the FLEM default
+    #[default]
+    PayloadOnly,
+    /// The header bytes and everything after the checksum bytes, for partners that
+    /// checksum the whole frame
+    IncludeHeader,
+    /// Raw mode, no checksum. `pack()` leaves the checksum at 0 and `validate()` accepts
+    /// every packet. Only for trusted buses (shared memory, an on-chip FIFO), this trades
+    /// integrity for throughput. See `Packet::pack_raw()`.
+    Disabled,
+}
+
 #[derive(Copy, Clone)]
 #[repr(C, packed)]
 pub struct Packet<const T: usize> {
@@ -138,77 +661,500 @@ pub struct Packet<const T: usize> {
     request: u16,
     response: u16,
     length: u16,
-    data: [u8; T],
+    #[cfg(feature = "protocol-v2")]
+    sequence: u16,
+    #[cfg(feature = "protocol-v2")]
+    flags: u8,
+    #[cfg(feature = "protocol-v2")]
+    channel: u8,
+    data: [MaybeUninit<u8>; T],
     internal_counter: u32,
     data_length_counter: usize,
+    // `data[..initialized]` is initialized, never less than the payload
+    initialized: u16,
     status: Status,
+    stats: Stats,
+    discarded: u32,
+    discard_remaining: u16,
+    oversized_length: u16,
+    peer_limit: u16,
+    coverage: ChecksumCoverage,
+    algorithm: ChecksumAlgorithm,
+    sync_word: u16,
+    dirty: bool,
 }
 
+/// Response codes. Codes from `ERROR_START` (0xFFF0) to 0xFFFF are reserved for the
+/// crate, application defined responses should stay below them.
 pub mod response {
     pub const ASYNC: u16 = 0x0000;
     pub const SUCCESS: u16 = 0x0001;
+    /// The partner is handling another request, try again later
+    pub const BUSY: u16 = 0xFFF0;
+    /// The partner can't handle the request yet, for example it is still starting up
+    pub const NOT_READY: u16 = 0xFFF1;
+    /// The request data couldn't be decoded
+    pub const INVALID_ARGUMENT: u16 = 0xFFF2;
+    /// The request data was decoded, but a value is outside the allowed range
+    pub const OUT_OF_RANGE: u16 = 0xFFF3;
     pub const UNKNOWN_REQUEST: u16 = 0xFFFE;
     pub const CHECKSUM_ERROR: u16 = 0xFFFF;
+
+    /// Responses from this value up to 0xFFFF are reserved for errors
+    pub const ERROR_START: u16 = 0xFFF0;
+
+    /// Returns true if `response` is one of the reserved error responses (0xFFF0 and up)
+    pub const fn is_error(response: u16) -> bool {
+        response >= ERROR_START
+    }
 }
 
-/// Pre-defined requests
+/// Pre-defined requests. Codes 0x0000 to `RESERVED_END` (0x000F) are reserved for the
+/// crate, application defined requests should start at 0x0010.
 pub mod request {
     pub const ID: u16 = 0x0001;
+    /// Asks the partner for its `ProtocolInfo`, answered with `Packet::pack_protocol_info`
+    pub const PROTOCOL: u16 = 0x0002;
+    /// Debug text sent by the client without a request, see `console`
+    pub const LOG: u16 = 0x0003;
+    /// Keep-alive sent by the client without a request, see `heartbeat`
+    pub const HEARTBEAT: u16 = 0x0004;
+    /// Firmware update: erase the image, see `dfu`
+    pub const DFU_ERASE: u16 = 0x0008;
+    /// Firmware update: write a chunk of the image at an offset, see `dfu`
+    pub const DFU_WRITE: u16 = 0x0009;
+    /// Firmware update: check the length and CRC of the written image, see `dfu`
+    pub const DFU_VERIFY: u16 = 0x000A;
+    /// Firmware update: start the new image, see `dfu`
+    pub const DFU_BOOT: u16 = 0x000B;
+
+    /// Last request code reserved for the crate
+    pub const RESERVED_END: u16 = 0x000F;
+
+    /// Returns true if `request` is in the reserved range (0x0000 to 0x000F)
+    pub const fn is_reserved(request: u16) -> bool {
+        request <= RESERVED_END
+    }
+}
+
+/// Requests defined by the crate, see `request`
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WellKnownRequest {
+    /// `request::ID`, asks the partner for its `DataId`
+    Id,
+    /// `request::PROTOCOL`, asks the partner for its `ProtocolInfo`
+    Protocol,
+    /// `request::LOG`, debug text from the partner
+    Log,
+    /// `request::HEARTBEAT`, keep-alive of the partner
+    Heartbeat,
+    /// `request::DFU_ERASE`
+    DfuErase,
+    /// `request::DFU_WRITE`
+    DfuWrite,
+    /// `request::DFU_VERIFY`
+    DfuVerify,
+    /// `request::DFU_BOOT`
+    DfuBoot,
+}
+
+impl TryFrom<u16> for WellKnownRequest {
+    /// The code, if it isn't a well known request
+    type Error = u16;
+
+    fn try_from(code: u16) -> Result<Self, Self::Error> {
+        match code {
+            request::ID => Ok(WellKnownRequest::Id),
+            request::PROTOCOL => Ok(WellKnownRequest::Protocol),
+            request::LOG => Ok(WellKnownRequest::Log),
+            request::HEARTBEAT => Ok(WellKnownRequest::Heartbeat),
+            request::DFU_ERASE => Ok(WellKnownRequest::DfuErase),
+            request::DFU_WRITE => Ok(WellKnownRequest::DfuWrite),
+            request::DFU_VERIFY => Ok(WellKnownRequest::DfuVerify),
+            request::DFU_BOOT => Ok(WellKnownRequest::DfuBoot),
+            code => Err(code),
+        }
+    }
+}
+
+impl From<WellKnownRequest> for u16 {
+    fn from(request: WellKnownRequest) -> Self {
+        match request {
+            WellKnownRequest::Id => request::ID,
+            WellKnownRequest::Protocol => request::PROTOCOL,
+            WellKnownRequest::Log => request::LOG,
+            WellKnownRequest::Heartbeat => request::HEARTBEAT,
+            WellKnownRequest::DfuErase => request::DFU_ERASE,
+            WellKnownRequest::DfuWrite => request::DFU_WRITE,
+            WellKnownRequest::DfuVerify => request::DFU_VERIFY,
+            WellKnownRequest::DfuBoot => request::DFU_BOOT,
+        }
+    }
+}
+
+/// Panics in debug builds if `request` is reserved and not a well known request. Only the
+/// `set_request` setters check, see `Packet::set_request`.
+pub(crate) fn debug_assert_request(request: u16) {
+    debug_assert!(
+        !request::is_reserved(request) || WellKnownRequest::try_from(request).is_ok(),
+        "Request {:#06x} is reserved, application requests start at 0x0010",
+        request
+    );
+}
+
+/// Responses defined by the crate, see `response`
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WellKnownResponse {
+    Async,
+    Success,
+    Busy,
+    NotReady,
+    InvalidArgument,
+    OutOfRange,
+    UnknownRequest,
+    ChecksumError,
+}
+
+impl TryFrom<u16> for WellKnownResponse {
+    /// The code, if it isn't a well known response
+    type Error = u16;
+
+    fn try_from(code: u16) -> Result<Self, Self::Error> {
+        match code {
+            response::ASYNC => Ok(WellKnownResponse::Async),
+            response::SUCCESS => Ok(WellKnownResponse::Success),
+            response::BUSY => Ok(WellKnownResponse::Busy),
+            response::NOT_READY => Ok(WellKnownResponse::NotReady),
+            response::INVALID_ARGUMENT => Ok(WellKnownResponse::InvalidArgument),
+            response::OUT_OF_RANGE => Ok(WellKnownResponse::OutOfRange),
+            response::UNKNOWN_REQUEST => Ok(WellKnownResponse::UnknownRequest),
+            response::CHECKSUM_ERROR => Ok(WellKnownResponse::ChecksumError),
+            code => Err(code),
+        }
+    }
+}
+
+impl From<WellKnownResponse> for u16 {
+    fn from(response: WellKnownResponse) -> Self {
+        match response {
+            WellKnownResponse::Async => response::ASYNC,
+            WellKnownResponse::Success => response::SUCCESS,
+            WellKnownResponse::Busy => response::BUSY,
+            WellKnownResponse::NotReady => response::NOT_READY,
+            WellKnownResponse::InvalidArgument => response::INVALID_ARGUMENT,
+            WellKnownResponse::OutOfRange => response::OUT_OF_RANGE,
+            WellKnownResponse::UnknownRequest => response::UNKNOWN_REQUEST,
+            WellKnownResponse::ChecksumError => response::CHECKSUM_ERROR,
+        }
+    }
+}
+
+/// Wire format and crate version of a partner and the largest payload it can receive, the
+/// payload of a `request::PROTOCOL` response. A host asks for it before using newer
+/// requests, partners that predate it answer with `response::UNKNOWN_REQUEST`.
+///
+/// On the wire: wire version, crate major, crate minor, then the max packet size as a
+/// little endian u16.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ProtocolInfo {
+    /// `FLEM_PROTOCOL_VERSION` of the partner
+    pub wire_version: u8,
+    pub crate_major: u8,
+    pub crate_minor: u8,
+    /// Largest payload the partner can receive
+    pub max_packet_size: u16,
+}
+
+impl ProtocolInfo {
+    /// Size of the info on the wire
+    pub const SIZE: usize = 5;
+
+    /// The info of this build of the crate, for a partner receiving up to `max_packet_size`
+    /// payload bytes
+    pub fn new(max_packet_size: usize) -> Self {
+        ProtocolInfo {
+            wire_version: FLEM_PROTOCOL_VERSION,
+            crate_major: env!("CARGO_PKG_VERSION_MAJOR").parse().unwrap_or(0),
+            crate_minor: env!("CARGO_PKG_VERSION_MINOR").parse().unwrap_or(0),
+            max_packet_size: u16::try_from(max_packet_size).unwrap_or(u16::MAX),
+        }
+    }
+
+    /// Decodes the info from a response payload. Bytes past `SIZE` are ignored, so later
+    /// versions can append fields. Returns `Status::InvalidDataLengthDetected` if `payload`
+    /// is shorter than `SIZE`.
+    pub fn from_payload(payload: &[u8]) -> Result<Self, Status> {
+        if payload.len() < Self::SIZE {
+            return Err(Status::InvalidDataLengthDetected);
+        }
+        Ok(ProtocolInfo {
+            wire_version: payload[0],
+            crate_major: payload[1],
+            crate_minor: payload[2],
+            max_packet_size: u16::from_le_bytes([payload[3], payload[4]]),
+        })
+    }
+
+    /// The info as it is sent
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let size = self.max_packet_size.to_le_bytes();
+        [
+            self.wire_version,
+            self.crate_major,
+            self.crate_minor,
+            size[0],
+            size[1],
+        ]
+    }
+}
+
+/// Bits of the protocol version 2 flags field
+#[cfg(feature = "protocol-v2")]
+pub mod flags {
+    /// More packets follow that belong to the same payload. Cleared on the last packet.
+    pub const MORE_FRAGMENTS: u8 = 0x01;
+    /// The sender doesn't expect a response, for fire-and-forget telemetry
+    pub const NO_RESPONSE: u8 = 0x02;
+    /// The packet is one of several packets of a payload, set on every fragment including
+    /// the last one
+    pub const FRAGMENT: u8 = 0x04;
+    /// The payload is compressed, the compression is agreed between the partners
+    pub const COMPRESSED: u8 = 0x08;
+    /// The first payload byte is a transaction ID, see `Packet::set_transaction`. Cleared
+    /// by `Packet::reset_lazy()` with the payload.
+    pub const TRANSACTION: u8 = 0x10;
 }
 
+/// Version of the wire format. Version 2 (features = ["protocol-v2"]) adds a sequence
+/// number, a flags field and a channel field to the header and uses a different header value so that
+/// version 1 and version 2 partners reject each other's packets instead of misreading them.
+#[cfg(not(feature = "protocol-v2"))]
+pub const FLEM_PROTOCOL_VERSION: u8 = 1;
+#[cfg(feature = "protocol-v2")]
+pub const FLEM_PROTOCOL_VERSION: u8 = 2;
+
+#[cfg(not(feature = "protocol-v2"))]
 pub const FLEM_HEADER_SIZE: usize = 10;
+#[cfg(feature = "protocol-v2")]
+pub const FLEM_HEADER_SIZE: usize = 14;
+
+// `FLEM_HEADER_SIZE` is kept by hand, it has to match the wire fields in front of the data
+// or `construct()` and `bytes()` split the packet in the wrong place.
+const _: () = {
+    use core::mem::{offset_of, size_of};
+
+    #[cfg(not(feature = "protocol-v2"))]
+    let fields = 5 * size_of::<u16>();
+    #[cfg(feature = "protocol-v2")]
+    let fields = 6 * size_of::<u16>() + 2 * size_of::<u8>();
+
+    assert!(
+        fields == FLEM_HEADER_SIZE,
+        "FLEM_HEADER_SIZE doesn't match the header fields"
+    );
+    assert!(
+        offset_of!(Packet<0>, data) == FLEM_HEADER_SIZE,
+        "FLEM_HEADER_SIZE doesn't match the offset of the data"
+    );
+};
+
+// Reads a wire field of a packed `Packet` by value through `read_unaligned`, so no
+// reference to the possibly unaligned field is ever created.
+macro_rules! read_field {
+    ($packet:expr, $field:ident) => {
+        // SAFETY: `addr_of!` doesn't create a reference and `read_unaligned` has no
+        // alignment requirement, the field is initialized and Copy.
+        unsafe { core::ptr::addr_of!($packet.$field).read_unaligned() }
+    };
+}
+
+#[cfg(not(feature = "protocol-v2"))]
 pub const FLEM_HEADER: u16 = 0x5555;
-const CRC16_TAB: [u16; 256] = [
-    0x0000, 0xc0c1, 0xc181, 0x0140, 0xc301, 0x03c0, 0x0280, 0xc241, 0xc601, 0x06c0, 0x0780, 0xc741,
-    0x0500, 0xc5c1, 0xc481, 0x0440, 0xcc01, 0x0cc0, 0x0d80, 0xcd41, 0x0f00, 0xcfc1, 0xce81, 0x0e40,
-    0x0a00, 0xcac1, 0xcb81, 0x0b40, 0xc901, 0x09c0, 0x0880, 0xc841, 0xd801, 0x18c0, 0x1980, 0xd941,
-    0x1b00, 0xdbc1, 0xda81, 0x1a40, 0x1e00, 0xdec1, 0xdf81, 0x1f40, 0xdd01, 0x1dc0, 0x1c80, 0xdc41,
-    0x1400, 0xd4c1, 0xd581, 0x1540, 0xd701, 0x17c0, 0x1680, 0xd641, 0xd201, 0x12c0, 0x1380, 0xd341,
-    0x1100, 0xd1c1, 0xd081, 0x1040, 0xf001, 0x30c0, 0x3180, 0xf141, 0x3300, 0xf3c1, 0xf281, 0x3240,
-    0x3600, 0xf6c1, 0xf781, 0x3740, 0xf501, 0x35c0, 0x3480, 0xf441, 0x3c00, 0xfcc1, 0xfd81, 0x3d40,
-    0xff01, 0x3fc0, 0x3e80, 0xfe41, 0xfa01, 0x3ac0, 0x3b80, 0xfb41, 0x3900, 0xf9c1, 0xf881, 0x3840,
-    0x2800, 0xe8c1, 0xe981, 0x2940, 0xeb01, 0x2bc0, 0x2a80, 0xea41, 0xee01, 0x2ec0, 0x2f80, 0xef41,
-    0x2d00, 0xedc1, 0xec81, 0x2c40, 0xe401, 0x24c0, 0x2580, 0xe541, 0x2700, 0xe7c1, 0xe681, 0x2640,
-    0x2200, 0xe2c1, 0xe381, 0x2340, 0xe101, 0x21c0, 0x2080, 0xe041, 0xa001, 0x60c0, 0x6180, 0xa141,
-    0x6300, 0xa3c1, 0xa281, 0x6240, 0x6600, 0xa6c1, 0xa781, 0x6740, 0xa501, 0x65c0, 0x6480, 0xa441,
-    0x6c00, 0xacc1, 0xad81, 0x6d40, 0xaf01, 0x6fc0, 0x6e80, 0xae41, 0xaa01, 0x6ac0, 0x6b80, 0xab41,
-    0x6900, 0xa9c1, 0xa881, 0x6840, 0x7800, 0xb8c1, 0xb981, 0x7940, 0xbb01, 0x7bc0, 0x7a80, 0xba41,
-    0xbe01, 0x7ec0, 0x7f80, 0xbf41, 0x7d00, 0xbdc1, 0xbc81, 0x7c40, 0xb401, 0x74c0, 0x7580, 0xb541,
-    0x7700, 0xb7c1, 0xb681, 0x7640, 0x7200, 0xb2c1, 0xb381, 0x7340, 0xb101, 0x71c0, 0x7080, 0xb041,
-    0x5000, 0x90c1, 0x9181, 0x5140, 0x9301, 0x53c0, 0x5280, 0x9241, 0x9601, 0x56c0, 0x5780, 0x9741,
-    0x5500, 0x95c1, 0x9481, 0x5440, 0x9c01, 0x5cc0, 0x5d80, 0x9d41, 0x5f00, 0x9fc1, 0x9e81, 0x5e40,
-    0x5a00, 0x9ac1, 0x9b81, 0x5b40, 0x9901, 0x59c0, 0x5880, 0x9841, 0x8801, 0x48c0, 0x4980, 0x8941,
-    0x4b00, 0x8bc1, 0x8a81, 0x4a40, 0x4e00, 0x8ec1, 0x8f81, 0x4f40, 0x8d01, 0x4dc0, 0x4c80, 0x8c41,
-    0x4400, 0x84c1, 0x8581, 0x4540, 0x8701, 0x47c0, 0x4680, 0x8641, 0x8201, 0x42c0, 0x4380, 0x8341,
-    0x4100, 0x81c1, 0x8081, 0x4040,
-];
+#[cfg(feature = "protocol-v2")]
+pub const FLEM_HEADER: u16 = 0xAA55;
+#[cfg(feature = "small-crc")]
+const CRC16_NIBBLE_TAB: [u16; 16] = checksum::build_crc16_nibble_table();
+#[cfg(not(feature = "small-crc"))]
+const CRC16_TAB: [u16; 256] = checksum::build_crc16_table();
 
 impl<const T: usize> Packet<T> {
     /// Creates a new Packet with a data buffer of const T: usize bytes
     ///
+    /// The data buffer is zeroed, which takes time for large packets. Use
+    /// `new_uninit_data()` to skip it.
+    ///
     /// # Example
     /// ```
     /// pub fn main() {
     ///     let rx = flem::Packet::<100>::new(); // Create new packet that can send / receive up to 100 bytes per packet
     ///
+    ///     static POOL: [flem::Packet<4096>; 4] = [flem::Packet::new(); 4];
+    ///     assert_eq!(POOL[3].get_length(), 0);
     /// }
     /// ```
-    pub fn new() -> Self {
+    pub const fn new() -> Self {
         assert!(T < u16::MAX as usize, "<T> should be u16::MAX or less"); // Bounds check T, must be less than u16::MAX
-        return Self {
+        Self {
             header: 0,
             checksum: 0,
             request: 0,
             response: 0,
             length: 0,
-            data: [0u8; T],
+            #[cfg(feature = "protocol-v2")]
+            sequence: 0,
+            #[cfg(feature = "protocol-v2")]
+            flags: 0,
+            #[cfg(feature = "protocol-v2")]
+            channel: 0,
+            data: [MaybeUninit::new(0); T],
             internal_counter: 0,
             data_length_counter: 0,
+            initialized: T as u16,
             status: Status::Ok,
-        };
+            stats: Stats::new(),
+            discarded: 0,
+            discard_remaining: 0,
+            oversized_length: 0,
+            peer_limit: u16::MAX,
+            coverage: ChecksumCoverage::PayloadOnly,
+            algorithm: ChecksumAlgorithm::Crc16Ibm,
+            sync_word: FLEM_HEADER,
+            dirty: false,
+        }
+    }
+
+    /// Same as `new()` without zeroing the data buffer, for large packets created at run
+    /// time. Bytes of the buffer are zeroed when the payload first reaches them, so
+    /// `payload()` and `bytes()` never see uninitialized memory. `get_data()` and
+    /// `as_full_bytes()` only return the bytes written so far, see `initialized_len()`.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut tx = flem::Packet::<4096>::new_uninit_data();
+    ///     assert_eq!(tx.initialized_len(), 0);
+    ///
+    ///     tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+    ///     assert_eq!(tx.payload(), &[1, 2, 3]);
+    ///     assert_eq!(tx.initialized_len(), 3);
+    ///     assert_eq!(tx.as_full_bytes().len(), flem::FLEM_HEADER_SIZE + 3);
+    /// }
+    /// ```
+    pub const fn new_uninit_data() -> Self {
+        assert!(T < u16::MAX as usize, "<T> should be u16::MAX or less"); // Bounds check T, must be less than u16::MAX
+        Self {
+            header: 0,
+            checksum: 0,
+            request: 0,
+            response: 0,
+            length: 0,
+            #[cfg(feature = "protocol-v2")]
+            sequence: 0,
+            #[cfg(feature = "protocol-v2")]
+            flags: 0,
+            #[cfg(feature = "protocol-v2")]
+            channel: 0,
+            data: [MaybeUninit::uninit(); T],
+            internal_counter: 0,
+            data_length_counter: 0,
+            initialized: 0,
+            status: Status::Ok,
+            stats: Stats::new(),
+            discarded: 0,
+            discard_remaining: 0,
+            oversized_length: 0,
+            peer_limit: u16::MAX,
+            coverage: ChecksumCoverage::PayloadOnly,
+            algorithm: ChecksumAlgorithm::Crc16Ibm,
+            sync_word: FLEM_HEADER,
+            dirty: false,
+        }
+    }
+
+    /// Creates a packed packet with `data`, the response is set to SUCCESS. Same as
+    /// `pack_data()` on a new packet. See `builder::PacketBuilder` to set other fields.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let tx = flem::Packet::<16>::with_payload(0x10, &[1, 2, 3]).unwrap();
+    ///     assert_eq!(tx.get_data()[..3], [1, 2, 3]);
+    ///     assert!(tx.is_success());
+    /// }
+    /// ```
+    pub fn with_payload(request: u16, data: &[u8]) -> Result<Packet<T>, Status> {
+        let mut packet = Packet::new();
+        packet.pack_data(request, data)?;
+        Ok(packet)
+    }
+
+    /// Creates a packed packet from its fields in one call, for tools and tests that
+    /// synthesize frames. Like `with_payload()` with an explicit response. Any request code
+    /// is accepted, reserved codes included. Returns `Status::PacketOverflow` if `data`
+    /// doesn't fit the packet.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let busy = flem::Packet::<16>::from_parts(0x10, flem::response::BUSY, &[7]).unwrap();
+    ///     assert_eq!(busy.get_response(), flem::response::BUSY);
+    ///     assert_eq!(busy.payload(), [7]);
+    ///     assert!(flem::validate_slice(busy.bytes()).is_ok());
+    /// }
+    /// ```
+    pub fn from_parts(request: u16, response: u16, data: &[u8]) -> Result<Packet<T>, Status> {
+        let mut packet = Packet::new();
+        packet.request = request;
+        packet.response = response;
+        packet.add_data(data)?;
+        packet.pack();
+        Ok(packet)
+    }
+
+    /// Copies the packet into a packet with a data buffer of `U` bytes and packs it, for
+    /// example to forward a packet to a link with a different packet size. The request,
+    /// response, payload and, with protocol-v2, the sequence, flags and channel are copied,
+    /// the checksum is computed again. The sync word and checksum settings are those of a
+    /// new `Packet<U>`. Returns `Status::PacketOverflow` if the payload doesn't fit `U`.
+    ///
+    /// There is no infallible conversion for `U >= T`, the bound can't be expressed on
+    /// const generics, the error can be ignored in that case.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let rx = flem::Packet::<512>::with_payload(0x10, &[1, 2, 3]).unwrap();
+    ///
+    ///     let forward = rx.try_convert::<128>().unwrap();
+    ///     assert_eq!(forward.payload(), [1, 2, 3]);
+    ///     assert!(flem::validate_slice(forward.bytes()).is_ok());
+    ///
+    ///     let large = flem::Packet::<512>::with_payload(0x10, &[0; 200]).unwrap();
+    ///     assert_eq!(large.try_convert::<128>().err(), Some(flem::Status::PacketOverflow));
+    /// }
+    /// ```
+    pub fn try_convert<const U: usize>(&self) -> Result<Packet<U>, Status> {
+        let mut packet = Packet::<U>::new();
+        packet.request = self.get_request();
+        packet.response = self.get_response();
+        #[cfg(feature = "protocol-v2")]
+        {
+            packet.sequence = self.get_sequence();
+            packet.flags = self.get_flags();
+            packet.channel = self.get_channel();
+        }
+        packet.add_data(self.payload())?;
+        packet.pack();
+        Ok(packet)
     }
 
     /// Convenience function to response with data. The response byte is automatically set to SUCCESS.
+    /// Any request code is accepted, see `set_request()`.
     pub fn pack_data(&mut self, request: u16, data: &[u8]) -> Result<(), Status> {
         self.reset_lazy();
         self.request = request;
@@ -222,14 +1168,22 @@ impl<const T: usize> Packet<T> {
         }
     }
 
-    /// Convenience function to respond quickly if an error occurs (without data).
-    pub fn pack_error(&mut self, request: u16, error: u16, data: &[u8]) -> Result<(), Status> {
+    /// Same as `pack_data()` with a transaction ID as the first payload byte, see
+    /// `correlation::Correlator`. A device answers with the transaction ID of the request
+    /// so the host can tell apart responses to requests with the same request code. With
+    /// protocol-v2 `flags::TRANSACTION` is set as well.
+    pub fn pack_data_seq(
+        &mut self,
+        request: u16,
+        transaction: u8,
+        data: &[u8],
+    ) -> Result<(), Status> {
         self.reset_lazy();
         self.request = request;
-        self.response = error;
+        self.set_transaction(transaction)?;
         match self.add_data(data) {
             Ok(_) => {
-                self.response = error;
+                self.response = response::SUCCESS;
                 self.pack();
                 Ok(())
             }
@@ -237,25 +1191,141 @@ impl<const T: usize> Packet<T> {
         }
     }
 
-    /// Convenience function to respond with the ID. If communicating with UTF-8 partners, ascii should be true. This
-    /// can only be used if the data packets are 30 bytes or longer (or twice that if ascii = false).
-    ///
-    /// # Arguments
+    /// Adds a transaction ID as the first payload byte, before any other data is added.
+    /// With protocol-v2 it also sets `flags::TRANSACTION`, so the partner can tell the
+    /// transaction ID from data, see `get_tagged_transaction()`.
     ///
-    /// * `ascii` - Packages the ID as a UTF-8 ID. Used when talking to C/C++ partners.
-    pub fn pack_id(&mut self, id: &DataId, ascii: bool) -> Result<(), Status> {
-        self.reset_lazy();
-        self.request = request::ID;
-        self.response = response::SUCCESS;
+    /// Returns `Status::InvalidDataLengthDetected` if the payload isn't empty, or
+    /// `Status::PacketOverflow` if there is no room.
+    pub fn set_transaction(&mut self, transaction: u8) -> Result<(), Status> {
+        if self.get_length() != 0 {
+            return Err(Status::InvalidDataLengthDetected);
+        }
+        self.add_data(&[transaction])?;
+        #[cfg(feature = "protocol-v2")]
+        self.set_flag(flags::TRANSACTION, true);
+        Ok(())
+    }
 
-        if ascii {
-            self.add_data(&[id.get_major(); 1])?;
-            self.add_data(&[id.get_minor(); 1])?;
-            self.add_data(&[id.get_patch(); 1])?;
-            self.add_data(&id.max_packet_size.to_le_bytes())?;
+    /// The transaction ID, the first payload byte, or None if the payload is empty. Only
+    /// meaningful if the partner tags its packets, see `correlation::Correlator`.
+    pub fn get_transaction(&self) -> Option<u8> {
+        self.payload().first().copied()
+    }
 
-            let mut char_array: [u8; FLEM_ID_NAME_SIZE] = [0; FLEM_ID_NAME_SIZE];
-            for (index, unicode) in id.name.iter().enumerate() {
+    /// The transaction ID if the packet has `flags::TRANSACTION` set, None for packets
+    /// that weren't tagged with `set_transaction()`
+    #[cfg(feature = "protocol-v2")]
+    pub fn get_tagged_transaction(&self) -> Option<u8> {
+        if self.has_flag(flags::TRANSACTION) {
+            self.get_transaction()
+        } else {
+            None
+        }
+    }
+
+    /// Packs an event, a packet sent without a prior request, with a response of
+    /// `response::ASYNC`. The host tells events from responses with `is_async()`.
+    pub fn pack_async(&mut self, request: u16, data: &[u8]) -> Result<(), Status> {
+        self.reset_lazy();
+        self.request = request;
+        match self.add_data(data) {
+            Ok(_) => {
+                self.response = response::ASYNC;
+                self.pack();
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Convenience function to respond quickly if an error occurs, with `data` as the
+    /// payload.
+    #[deprecated(since = "0.7.0", note = "use pack_error_with_detail")]
+    pub fn pack_error(&mut self, request: u16, error: u16, data: &[u8]) -> Result<(), Status> {
+        self.pack_error_with_detail(request, error, data)
+    }
+
+    /// Convenience function to respond quickly if an error occurs, without data.
+    pub fn pack_error_code(&mut self, request: u16, error: u16) -> Result<(), Status> {
+        self.pack_error_with_detail(request, error, &[])
+    }
+
+    /// Same as `pack_error_code()` with `detail` as the payload, e.g. the offending argument.
+    /// If the detail doesn't fit the packet is reset and the error returned.
+    pub fn pack_error_with_detail(
+        &mut self,
+        request: u16,
+        error: u16,
+        detail: &[u8],
+    ) -> Result<(), Status> {
+        self.reset_lazy();
+        self.request = request;
+        match self.add_data(detail) {
+            Ok(_) => {
+                self.response = error;
+                self.pack();
+                Ok(())
+            }
+            Err(e) => {
+                self.reset();
+                Err(e)
+            }
+        }
+    }
+
+    /// Starts the response to `rx`: resets this packet, copies the request of `rx` and sets
+    /// the response to SUCCESS. With protocol-v2 the channel of `rx` is copied as well.
+    /// Add the data, change the response if needed, then `pack()`.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let rx = flem::Packet::<16>::with_payload(0x10, &[1]).unwrap();
+    ///
+    ///     let mut tx = flem::Packet::<16>::new();
+    ///     tx.respond_to(&rx);
+    ///     tx.add_data(&[2, 3]).unwrap();
+    ///     tx.pack();
+    ///
+    ///     assert_eq!(tx.get_request(), 0x10);
+    ///     assert!(tx.is_success());
+    /// }
+    /// ```
+    pub fn respond_to(&mut self, rx: &Packet<T>) {
+        self.reset_lazy();
+        self.request = rx.get_request();
+        self.response = response::SUCCESS;
+        #[cfg(feature = "protocol-v2")]
+        {
+            self.channel = rx.get_channel();
+        }
+        self.dirty = true;
+    }
+
+    /// Convenience function to respond with the ID. If communicating with UTF-8 partners, ascii should be true. This
+    /// can only be used if the data packets are `DataId::<N>::SIZE` bytes or longer (`DataId::<N>::UNICODE_SIZE`
+    /// if ascii = false). Decode the response with `parse_id`.
+    ///
+    /// # Arguments
+    ///
+    /// * `ascii` - Packages the ID as a UTF-8 ID. Used when talking to C/C++ partners. Otherwise each
+    ///   name character is sent as a little endian u32. Before 0.7 the non-ascii ID was the
+    ///   in-memory `DataId` cut to 30 bytes, partners older than 0.7 can't decode the new
+    ///   layout, use `ascii = true` with them.
+    pub fn pack_id<const N: usize>(&mut self, id: &DataId<N>, ascii: bool) -> Result<(), Status> {
+        self.reset_lazy();
+        self.request = request::ID;
+        self.response = response::SUCCESS;
+
+        self.add_data(&[id.get_major(); 1])?;
+        self.add_data(&[id.get_minor(); 1])?;
+        self.add_data(&[id.get_patch(); 1])?;
+        self.add_data(&id.max_packet_size.to_le_bytes())?;
+
+        if ascii {
+            let mut char_array: [u8; N] = [0; N];
+            for (index, unicode) in id.name.iter().enumerate() {
                 char_array[index] = *unicode as u8;
             }
 
@@ -263,7 +1333,9 @@ impl<const T: usize> Packet<T> {
             self.add_data(&char_array)?;
         } else {
             // Send over the array as unicode
-            self.add_data(id.as_u8_array())?;
+            for unicode in id.name.iter() {
+                self.add_data(&(*unicode as u32).to_le_bytes())?;
+            }
         }
 
         self.pack();
@@ -271,6 +1343,59 @@ impl<const T: usize> Packet<T> {
         Ok(())
     }
 
+    /// Decodes the `DataId` from an ID response, packed with either `pack_id(id, true)` or
+    /// `pack_id(id, false)`.
+    ///
+    /// Returns an error if:
+    /// - UnrecognizedRequest - The request isn't `request::ID`
+    /// - InvalidDataLengthDetected - The payload is too short for an ID
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     use flem::{DataId, Packet};
+    ///
+    ///     let mut tx = Packet::<64>::new();
+    ///     tx.pack_id(&DataId::new("Target", 1, 2, 3, 64), true).unwrap();
+    ///
+    ///     let id = tx.parse_id().unwrap();
+    ///     assert_eq!(id.get_major(), 1);
+    ///     assert_eq!(id.get_max_packet_size(), 64);
+    /// }
+    /// ```
+    pub fn parse_id(&self) -> Result<DataId, Status> {
+        if self.get_request() != request::ID {
+            return Err(Status::UnrecognizedRequest);
+        }
+
+        let payload = &self.bytes()[FLEM_HEADER_SIZE..];
+        if payload.len() == DataId::<FLEM_ID_NAME_SIZE>::UNICODE_SIZE {
+            DataId::parse_unicode(payload)
+        } else {
+            DataId::parse(payload)
+        }
+    }
+
+    /// Packs the answer to `request::PROTOCOL`: `info` with a response of SUCCESS. Decode it
+    /// with `ProtocolInfo::from_payload`.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     use flem::{Packet, ProtocolInfo};
+    ///
+    ///     let mut tx = Packet::<64>::new();
+    ///     tx.pack_protocol_info(&ProtocolInfo::new(64)).unwrap();
+    ///
+    ///     let info = ProtocolInfo::from_payload(tx.payload()).unwrap();
+    ///     assert_eq!(info.wire_version, flem::FLEM_PROTOCOL_VERSION);
+    ///     assert_eq!(info.max_packet_size, 64);
+    /// }
+    /// ```
+    pub fn pack_protocol_info(&mut self, info: &ProtocolInfo) -> Result<(), Status> {
+        self.pack_data(request::PROTOCOL, &info.to_bytes())
+    }
+
     /// Pack a packet up: adds header and computes checksum.
     ///
     /// # Example
@@ -281,7 +1406,7 @@ impl<const T: usize> Packet<T> {
     ///
     ///     const PACKET_SIZE: usize = 64; // 64 byte packet
     ///
-    ///     const FLEM_EXAMPLE_REQUEST: u16 = 0xF;
+    ///     const FLEM_EXAMPLE_REQUEST: u16 = 0x10;
     ///
     ///     let mut rx = Packet::<PACKET_SIZE>::new();
     ///
@@ -292,11 +1417,11 @@ impl<const T: usize> Packet<T> {
     ///     rx.add_data(&data);
     ///     rx.set_request(FLEM_EXAMPLE_REQUEST);
     ///     
-    ///     assert_ne!(rx.get_header(), 0x5555, "Packet header hasn't been set, should NOT be 0x5555");
+    ///     assert_ne!(rx.get_header(), flem::FLEM_HEADER, "Packet header hasn't been set, should NOT be 0x5555");
     ///     
     ///     rx.pack();
     ///
-    ///     assert_eq!(rx.get_header(), 0x5555, "Packet header has been set, should be 0x5555");
+    ///     assert_eq!(rx.get_header(), flem::FLEM_HEADER, "Packet header has been set, should be 0x5555");
     ///
     ///     /* Send data */
     ///
@@ -304,36 +1429,345 @@ impl<const T: usize> Packet<T> {
     /// ```
     ///
     pub fn pack(&mut self) {
+        self.header = self.sync_word;
         self.checksum(true);
-        self.header = FLEM_HEADER;
+        self.dirty = false;
+    }
+
+    /// Packs the packet and returns the whole frame, to hand to a DMA transfer or a
+    /// `write_all` in one go instead of sending it with `get_byte()`.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut tx = flem::Packet::<16>::new();
+    ///     tx.set_request(0x10);
+    ///     tx.add_data(&[1, 2, 3]).unwrap();
+    ///
+    ///     let frame = tx.finalize();
+    ///     assert_eq!(frame.len(), flem::FLEM_HEADER_SIZE + 3);
+    /// }
+    /// ```
+    pub fn finalize(&mut self) -> &[u8] {
+        self.pack();
+        self.bytes()
+    }
+
+    /// Sets the header and leaves the checksum at 0, without computing it. Raw mode for
+    /// trusted buses, the receiver has to opt in with `ChecksumCoverage::Disabled`, a
+    /// normal receiver rejects the packet with `ChecksumError`. This trades integrity for
+    /// throughput, don't use it on a link that can corrupt bytes.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut tx = flem::Packet::<16>::new();
+    ///     tx.set_request(0x10);
+    ///     tx.add_data(&[1, 2, 3]).unwrap();
+    ///     tx.pack_raw();
+    ///
+    ///     let mut rx = flem::Packet::<16>::new();
+    ///     rx.set_checksum_coverage(flem::ChecksumCoverage::Disabled);
+    ///     assert!(rx.construct_slice(tx.bytes()).is_ok());
+    /// }
+    /// ```
+    pub fn pack_raw(&mut self) {
+        self.header = self.sync_word;
+        self.checksum = 0;
+        self.dirty = false;
     }
 
     /// Returns a copy of the data part of the packet as a byte array
+    ///
+    /// This copies the whole `T` byte buffer, including the unused part, which can be a lot
+    /// of stack for large packets. Prefer `payload()` or `copy_payload_to()`. For a packet
+    /// from `new_uninit_data()` the bytes after `initialized_len()` are returned as 0.
     pub fn get_data(&self) -> [u8; T] {
-        return self.data;
+        let mut data = [0u8; T];
+        let initialized = self.initialized_data();
+        data[..initialized.len()].copy_from_slice(initialized);
+        data
+    }
+
+    /// Number of bytes at the start of the data buffer that were written or zeroed. Always
+    /// `T` for a packet from `new()`, grows with the payload for one from
+    /// `new_uninit_data()`.
+    pub fn initialized_len(&self) -> usize {
+        self.initialized as usize
+    }
+
+    /// Copies the valid payload bytes into `out` and returns the number of bytes copied.
+    ///
+    /// Returns `Status::BufferTooSmall` and leaves `out` untouched if `out` is shorter than
+    /// the payload.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut packet = flem::Packet::<1024>::new();
+    ///     packet.pack_data(0x10, &[1, 2, 3]).unwrap();
+    ///
+    ///     let mut out = [0u8; 12];
+    ///     let count = packet.copy_payload_to(&mut out).unwrap();
+    ///     assert_eq!(out[..count], [1, 2, 3]);
+    /// }
+    /// ```
+    pub fn copy_payload_to(&self, out: &mut [u8]) -> Result<usize, Status> {
+        let payload = self.payload();
+        if out.len() < payload.len() {
+            return Err(Status::BufferTooSmall);
+        }
+        out[..payload.len()].copy_from_slice(payload);
+        Ok(payload.len())
+    }
+
+    /// Copies the payload of this packet into `dst` starting at `offset` and returns the
+    /// offset after the payload. Used to collect a payload split across several packets
+    /// into a fixed buffer, without allocating.
+    ///
+    /// Returns `Status::PacketOverflow` and leaves `dst` untouched if the payload doesn't fit.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     use flem::Packet;
+    ///
+    ///     let mut scratch = [0u8; 64];
+    ///     let mut offset = 0;
+    ///
+    ///     for chunk in [[1u8; 16], [2u8; 16], [3u8; 16]] {
+    ///         let mut packet = Packet::<16>::new();
+    ///         packet.pack_data(0x10, &chunk).unwrap();
+    ///
+    ///         offset = packet.reassemble_into(&mut scratch, offset).unwrap();
+    ///     }
+    ///
+    ///     assert_eq!(offset, 48);
+    ///     assert_eq!(scratch[16..32], [2u8; 16]);
+    /// }
+    /// ```
+    pub fn reassemble_into(&self, dst: &mut [u8], offset: usize) -> Result<usize, Status> {
+        let payload = &self.bytes()[FLEM_HEADER_SIZE..];
+        let end = match offset.checked_add(payload.len()) {
+            Some(end) if end <= dst.len() => end,
+            _ => return Err(Status::PacketOverflow),
+        };
+
+        dst[offset..end].copy_from_slice(payload);
+        Ok(end)
     }
 
     /// Adds data to a packet if there is room.
+    ///
+    /// Returns an error and leaves the packet untouched if:
+    /// - PacketOverflow - `data` is longer than `u16::MAX` or won't fit in the remaining space,
+    ///   or the payload would be longer than the limit set with `set_peer_limit`
+    /// - InvalidDataLengthDetected - The accumulated length won't fit the u16 length field
     pub fn add_data(&mut self, data: &[u8]) -> Result<(), Status> {
-        if data.len() + self.length as usize > T {
+        if data.len() > u16::MAX as usize {
             self.status = Status::PacketOverflow;
-            Err(Status::PacketOverflow)
-        } else {
-            for i in 0..data.len() {
-                self.data[i + self.length as usize] = data[i];
+            return Err(Status::PacketOverflow);
+        }
+
+        let start = self.get_length() as usize;
+        let length = match start.checked_add(data.len()) {
+            Some(length) if length <= T && length <= self.peer_limit as usize => length,
+            _ => {
+                self.status = Status::PacketOverflow;
+                return Err(Status::PacketOverflow);
+            }
+        };
+
+        let length = match u16::try_from(length) {
+            Ok(length) => length,
+            Err(_) => {
+                self.status = Status::InvalidDataLengthDetected;
+                return Err(Status::InvalidDataLengthDetected);
+            }
+        };
+
+        for (i, byte) in data.iter().enumerate() {
+            self.data[start + i] = MaybeUninit::new(*byte);
+        }
+        self.length = length;
+        self.initialized = self.initialized.max(length);
+        self.dirty = true;
+
+        self.status = Status::Ok;
+        Ok(())
+    }
+
+    /// Adds bytes from an iterator, without collecting them into a slice first.
+    ///
+    /// Bytes are added until the iterator ends or the packet is full. If the packet fills up,
+    /// the bytes added so far are kept, the status is set and `PacketOverflow` returned. The
+    /// payload limit is the same as `add_data`.
+    pub fn add_iter<I: IntoIterator<Item = u8>>(&mut self, iter: I) -> Result<(), Status> {
+        let limit = T.min(self.peer_limit as usize);
+        for byte in iter {
+            let length = self.get_length() as usize;
+            if length >= limit {
+                self.status = Status::PacketOverflow;
+                return Err(Status::PacketOverflow);
             }
-            self.length += data.len() as u16;
+            self.data[length] = MaybeUninit::new(byte);
+            self.length += 1;
+            self.initialized = self.initialized.max(self.length);
+            self.dirty = true;
+        }
+
+        self.status = Status::Ok;
+        Ok(())
+    }
+
+    /// Appends a byte to the payload, see `add_data` for the errors.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut tx = flem::Packet::<16>::new();
+    ///     tx.write_u8(0x01).unwrap();
+    ///     tx.write_u16_le(0x0302).unwrap();
+    ///     tx.write_f32_le(0.5).unwrap();
+    ///     tx.pack();
+    ///
+    ///     assert_eq!(tx.read_u16_le(1), Ok(0x0302));
+    ///     assert_eq!(tx.read_f32_le(3), Ok(0.5));
+    /// }
+    /// ```
+    pub fn write_u8(&mut self, value: u8) -> Result<(), Status> {
+        self.add_data(&[value])
+    }
+
+    /// Appends an i8 to the payload, see `add_data` for the errors
+    pub fn write_i8(&mut self, value: i8) -> Result<(), Status> {
+        self.add_data(&value.to_le_bytes())
+    }
+
+    /// Appends a little endian u16 to the payload, see `add_data` for the errors
+    pub fn write_u16_le(&mut self, value: u16) -> Result<(), Status> {
+        self.add_data(&value.to_le_bytes())
+    }
+
+    /// Appends a little endian i16 to the payload, see `add_data` for the errors
+    pub fn write_i16_le(&mut self, value: i16) -> Result<(), Status> {
+        self.add_data(&value.to_le_bytes())
+    }
+
+    /// Appends a little endian u32 to the payload, see `add_data` for the errors
+    pub fn write_u32_le(&mut self, value: u32) -> Result<(), Status> {
+        self.add_data(&value.to_le_bytes())
+    }
+
+    /// Appends a little endian i32 to the payload, see `add_data` for the errors
+    pub fn write_i32_le(&mut self, value: i32) -> Result<(), Status> {
+        self.add_data(&value.to_le_bytes())
+    }
+
+    /// Appends a little endian u64 to the payload, see `add_data` for the errors
+    pub fn write_u64_le(&mut self, value: u64) -> Result<(), Status> {
+        self.add_data(&value.to_le_bytes())
+    }
+
+    /// Appends a little endian i64 to the payload, see `add_data` for the errors
+    pub fn write_i64_le(&mut self, value: i64) -> Result<(), Status> {
+        self.add_data(&value.to_le_bytes())
+    }
+
+    /// Appends a little endian f32 to the payload, see `add_data` for the errors
+    pub fn write_f32_le(&mut self, value: f32) -> Result<(), Status> {
+        self.add_data(&value.to_le_bytes())
+    }
+
+    /// Appends a little endian f64 to the payload, see `add_data` for the errors
+    pub fn write_f64_le(&mut self, value: f64) -> Result<(), Status> {
+        self.add_data(&value.to_le_bytes())
+    }
+
+    /// Appends the raw bytes of `value` to the payload, see `add_data` for the errors.
+    /// Enabled with features = ["bytemuck"].
+    ///
+    /// The bytes are in the byte order and layout of this target, so both partners must
+    /// agree on them. Use the typed writers, e.g. `write_u32_le`, on links between
+    /// different architectures.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     #[repr(C)]
+    ///     #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+    ///     struct Sample {
+    ///         raw: u32,
+    ///         volts: f32,
+    ///     }
+    ///
+    ///     let mut tx = flem::Packet::<16>::new();
+    ///     tx.add_pod(&Sample { raw: 512, volts: 1.25 }).unwrap();
+    ///     tx.pack();
+    ///
+    ///     let sample: Sample = tx.read_pod(0).unwrap();
+    ///     assert_eq!(sample.raw, 512);
+    /// }
+    /// ```
+    #[cfg(feature = "bytemuck")]
+    pub fn add_pod<P: bytemuck::Pod>(&mut self, value: &P) -> Result<(), Status> {
+        self.add_data(bytemuck::bytes_of(value))
+    }
+
+    /// Appends the UTF-8 bytes of `text` to the payload, see `add_data` for the errors. Like
+    /// `add_data`, nothing is added if all of `text` doesn't fit, a multi-byte character is
+    /// never split.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut tx = flem::Packet::<16>::new();
+    ///     tx.add_str("Température").unwrap();
+    ///     tx.pack();
+    ///
+    ///     assert_eq!(tx.payload_as_str(), Ok("Température"));
+    /// }
+    /// ```
+    pub fn add_str(&mut self, text: &str) -> Result<(), Status> {
+        self.add_data(text.as_bytes())
+    }
 
-            self.status = Status::Ok;
-            Ok(())
+    /// Appends `text` and a terminating NUL, for C partners that expect NUL terminated
+    /// strings. Nothing is added if `text` and the NUL don't both fit. Read it back with
+    /// `payload_as_str_nul`.
+    pub fn add_str_nul(&mut self, text: &str) -> Result<(), Status> {
+        let limit = T.min(self.peer_limit as usize);
+        if text.len() >= limit.saturating_sub(self.get_length() as usize) {
+            self.status = Status::PacketOverflow;
+            return Err(Status::PacketOverflow);
         }
+        self.add_data(text.as_bytes())?;
+        self.add_data(&[0])
     }
 
     /// Computes the Checksum on the packet and compares to the sent checksum. Returns true if
     /// there is a match, otherwise false.
     pub fn validate(&mut self) -> bool {
+        if self.coverage == ChecksumCoverage::Disabled {
+            return true;
+        }
         let crc = self.checksum(false);
-        return crc == self.checksum;
+        crc == self.get_checksum()
+    }
+
+    /// Returns `(computed, received)` checksums if they differ, or None if the packet is valid.
+    /// Use it to log the details of a `ChecksumError`.
+    pub fn checksum_mismatch(&mut self) -> Option<(u16, u16)> {
+        if self.coverage == ChecksumCoverage::Disabled {
+            return None;
+        }
+        let computed = self.checksum(false);
+        let received = self.get_checksum();
+        if computed == received {
+            None
+        } else {
+            Some((computed, received))
+        }
     }
 
     /// Construct a packet one byte at a time. An internal counter keeps track of where the byte should go.
@@ -341,9 +1775,16 @@ impl<const T: usize> Packet<T> {
     /// - HeaderBytesNotFound - The packet header was not found
     /// - ChecksumError - The computed checksum does not match the sent checksum
     /// - PacketOverflow - Data is being added beyond length of the packet
+    /// - InvalidDataLengthDetected - The length field is larger than the data buffer. The payload
+    ///   bytes of that packet are skipped, see `get_oversized_length()`.
+    /// - DiscardingOversized - The byte belongs to the payload of an oversized packet and was dropped
     /// - PacketBuilding - This should be the default most of the time and indicates the packet is being built without issues so far.
     /// - PacketReceived - All data bytes have been received and the checksum has been validated
     ///
+    /// After `ChecksumError`, `PacketOverflow` or `InvalidDataLengthDetected` the next byte is
+    /// treated as the start of a new packet, no reset is needed. The fields of the failed
+    /// packet are kept until then, for example for `checksum_mismatch()`.
+    ///
     /// # Arguments
     ///
     /// * `byte` - A single byte to add to a packet.
@@ -355,7 +1796,7 @@ impl<const T: usize> Packet<T> {
     ///
     ///     const PACKET_SIZE: usize = 64; // 64 byte packet
     ///
-    ///     const FLEM_EXAMPLE_REQUEST: u16 = 0xF;
+    ///     const FLEM_EXAMPLE_REQUEST: u16 = 0x10;
     ///
     ///     let mut rx = Packet::<PACKET_SIZE>::new();
     ///     let mut tx = Packet::<PACKET_SIZE>::new();
@@ -374,12 +1815,13 @@ impl<const T: usize> Packet<T> {
     ///     let tx_as_u8_array = tx.bytes();
     ///
     ///     // We are sending bytes across a hardware bus
-    ///     let mut packet_received = false;
+    ///     let mut received = None;
     ///     for byte in tx_as_u8_array {
     ///         // The received is getting bytes on the hardware bus
     ///         match rx.construct(*byte) {
     ///             Ok(_) => {
-    ///                 packet_received = true;
+    ///                 // Move the packet out, rx is reset and ready for the next byte
+    ///                 received = Some(rx.take().unwrap());
     ///             },
     ///             Err(status) => {
     ///                 /* Handle other cases here */
@@ -387,29 +1829,286 @@ impl<const T: usize> Packet<T> {
     ///         }
     ///     }
     ///
-    ///     assert!(packet_received, "Packet should have been constructed and validated.");
+    ///     let received = received.expect("Packet should have been constructed and validated.");
+    ///     assert_eq!(received.get_request(), FLEM_EXAMPLE_REQUEST);
+    ///     assert!(!rx.is_complete(), "rx should be ready for the next packet");
     ///
     /// }
     /// ```
     pub fn construct(&mut self, byte: u8) -> Result<(), Status> {
-        let local_internal_counter = self.internal_counter;
+        // Bytes consumed so far, only used when the header bytes aren't found
+        let position = self.internal_counter;
+        let result = self.construct_byte(byte);
+        if result.is_ok() {
+            // The received checksum matches the received fields
+            self.dirty = false;
+        }
 
-        match local_internal_counter {
-            0 => {
-                if byte != 0x55 {
-                    self.internal_counter = 0;
-                    self.status = Status::HeaderBytesNotFound;
-                    return Err(self.status);
-                }
-                self.header = byte as u16;
-            }
-            1 => {
-                if byte != 0x55 {
-                    self.internal_counter = 0;
-                    self.status = Status::HeaderBytesNotFound;
-                    return Err(self.status);
-                }
-                self.header |= (byte as u16) << 8;
+        let discarded = match result {
+            // A rejected second header byte can be kept as the first one
+            Err(Status::HeaderBytesNotFound) => position.saturating_add(1) - self.internal_counter,
+            Err(Status::DiscardingOversized) => 1,
+            _ => 0,
+        };
+        self.discarded = self.discarded.saturating_add(discarded);
+
+        let mut stats = self.stats;
+        stats.record(result, position.saturating_add(1));
+        self.stats = stats;
+
+        result
+    }
+
+    /// Same as `construct()` for fixed-size transports, where one transfer holds exactly one
+    /// packet. Once a packet is complete, every further byte returns `Status::PacketOverflow`
+    /// and leaves the received packet untouched, so a frame with trailing bytes is caught
+    /// instead of its extra bytes being parsed as the next packet. Call `reset_lazy()` or
+    /// `take()` at the end of the transfer to receive the next one.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut tx = flem::Packet::<16>::new();
+    ///     tx.pack_data(0x10, &[1, 2]).unwrap();
+    ///
+    ///     let mut rx = flem::Packet::<16>::new();
+    ///     let mut results = tx.bytes().iter().map(|byte| rx.construct_strict(*byte));
+    ///     assert!(results.all(|result| result != Err(flem::Status::PacketOverflow)));
+    ///
+    ///     assert_eq!(rx.construct_strict(0x00), Err(flem::Status::PacketOverflow));
+    ///     assert_eq!(rx.payload(), [1, 2]);
+    /// }
+    /// ```
+    pub fn construct_strict(&mut self, byte: u8) -> Result<(), Status> {
+        if self.status == Status::PacketReceived {
+            return Err(Status::PacketOverflow);
+        }
+        self.construct(byte)
+    }
+
+    /// Parses one complete frame from the front of `bytes`, when the whole frame is already
+    /// in a buffer. Returns the packet and the number of bytes used, the rest of `bytes`
+    /// (the next frame or anything else) is left for the caller.
+    ///
+    /// Returns `Status::PacketBuilding` if `bytes` ends before the frame is complete,
+    /// `HeaderBytesNotFound` if it doesn't start with the header, otherwise the error of
+    /// `construct()` (`ChecksumError`, `InvalidDataLengthDetected`).
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut tx = flem::Packet::<16>::new();
+    ///     tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+    ///
+    ///     let (rx, used) = flem::Packet::<16>::from_bytes(tx.bytes()).unwrap();
+    ///     assert_eq!(used, tx.length());
+    ///     assert_eq!(rx.payload(), [1, 2, 3]);
+    /// }
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Result<(Packet<T>, usize), Status> {
+        let mut packet = Packet::new();
+        for (index, byte) in bytes.iter().enumerate() {
+            match packet.construct(*byte) {
+                Ok(_) => return Ok((packet, index + 1)),
+                Err(Status::PacketBuilding) => {}
+                Err(status) => return Err(status),
+            }
+        }
+        Err(Status::PacketBuilding)
+    }
+
+    /// Same as `construct()`, errors carry the position of the byte within the frame and,
+    /// for `Status::ChecksumError`, the expected and received checksums.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut tx = flem::Packet::<32>::new();
+    ///     tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+    ///     let mut wire = tx.bytes().to_vec();
+    ///     wire[2] ^= 0xFF; // Corrupt the checksum
+    ///
+    ///     let mut rx = flem::Packet::<32>::new();
+    ///     let error = rx.construct_slice_detailed(&wire).unwrap_err();
+    ///     assert_eq!(error.status, flem::Status::ChecksumError);
+    ///     assert_eq!(error.byte_index as usize, wire.len() - 1);
+    ///     assert_eq!(error.expected_checksum, Some(tx.get_checksum()));
+    /// }
+    /// ```
+    pub fn construct_detailed(&mut self, byte: u8) -> Result<(), ConstructError> {
+        let byte_index = self.internal_counter;
+        self.construct(byte)
+            .map_err(|status| self.construct_error(status, byte_index))
+    }
+
+    /// Same as `construct_slice()` with the error context of `construct_detailed()`
+    pub fn construct_slice_detailed(&mut self, bytes: &[u8]) -> Result<usize, ConstructError> {
+        for (index, byte) in bytes.iter().enumerate() {
+            match self.construct_detailed(*byte) {
+                Ok(_) => return Ok(index + 1),
+                Err(error) => match error.status {
+                    Status::PacketBuilding
+                    | Status::HeaderBytesNotFound
+                    | Status::DiscardingOversized => {}
+                    _ => return Err(error),
+                },
+            }
+        }
+        Err(self.construct_error(Status::PacketBuilding, self.internal_counter))
+    }
+
+    fn construct_error(&mut self, status: Status, byte_index: u32) -> ConstructError {
+        let mismatch = match status {
+            Status::ChecksumError => self.checksum_mismatch(),
+            _ => None,
+        };
+        ConstructError {
+            status,
+            byte_index,
+            expected_checksum: mismatch.map(|(expected, _)| expected),
+            received_checksum: mismatch.map(|(_, received)| received),
+        }
+    }
+
+    /// Copies the wire fields (header, checksum, request, response, length and data) into a
+    /// new packet. The transmit / receive counters, status and stats of the copy are reset,
+    /// so it can be queued and sent again with `get_byte()` or `bytes()`.
+    pub fn clone_wire(&self) -> Packet<T> {
+        let mut packet = *self;
+        packet.internal_counter = 0;
+        packet.data_length_counter = 0;
+        packet.status = Status::Ok;
+        packet.stats = Stats::new();
+        packet.discarded = 0;
+        packet.discard_remaining = 0;
+        packet.oversized_length = 0;
+        packet
+    }
+
+    /// Constructs a packet from a slice of bytes, for transports that deliver several bytes
+    /// at once. Bytes before the header and the payload of oversized packets are skipped as
+    /// in `construct()`.
+    ///
+    /// Returns the number of bytes used when the packet is complete, the rest of `bytes`
+    /// belongs to the next packet. Returns `Status::PacketBuilding` if all bytes were used
+    /// and the packet isn't complete yet, or the first packet error (`ChecksumError`, ...).
+    pub fn construct_slice(&mut self, bytes: &[u8]) -> Result<usize, Status> {
+        for (index, byte) in bytes.iter().enumerate() {
+            match self.construct(*byte) {
+                Ok(_) => return Ok(index + 1),
+                Err(Status::PacketBuilding)
+                | Err(Status::HeaderBytesNotFound)
+                | Err(Status::DiscardingOversized) => {}
+                Err(status) => return Err(status),
+            }
+        }
+        Err(Status::PacketBuilding)
+    }
+
+    /// Pulls bytes from `source` into `construct()` until the source returns None or a packet
+    /// is complete, for interrupt driven receivers. Bytes left in the source after a
+    /// complete packet are not pulled. Call again when more bytes are available.
+    ///
+    /// Returns:
+    /// - PacketReceived - The packet is complete and validated
+    /// - PacketBuilding - The source ran out of bytes before the packet was complete
+    /// - The first packet error (`ChecksumError`, `InvalidDataLengthDetected`, ...)
+    pub fn read_from<F: FnMut() -> Option<u8>>(&mut self, source: &mut F) -> Status {
+        while let Some(byte) = source() {
+            match self.construct(byte) {
+                Ok(_) => return Status::PacketReceived,
+                Err(Status::PacketBuilding)
+                | Err(Status::HeaderBytesNotFound)
+                | Err(Status::DiscardingOversized) => {}
+                Err(status) => return status,
+            }
+        }
+        Status::PacketBuilding
+    }
+
+    /// Number of bytes `construct()` still needs to complete the packet, once the length
+    /// field has been received. Use it to switch to a bulk (DMA) read for the rest of the
+    /// packet. Returns `Some(0)` for a complete packet and None if the length isn't known
+    /// yet or the packet failed.
+    pub fn expected_remaining(&self) -> Option<usize> {
+        const LENGTH_END: u32 = 10;
+
+        match self.status {
+            Status::PacketReceived => Some(0),
+            Status::PacketBuilding if self.internal_counter >= LENGTH_END => Some(
+                (FLEM_HEADER_SIZE + self.get_length() as usize)
+                    .saturating_sub(self.internal_counter as usize),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Returns true once `construct()` has received all `FLEM_HEADER_SIZE` header bytes of
+    /// the current packet. A driver can receive the header a byte at a time and then read
+    /// the `expected_remaining()` payload bytes in bulk.
+    pub fn header_complete(&self) -> bool {
+        self.status == Status::PacketReceived || self.internal_counter as usize >= FLEM_HEADER_SIZE
+    }
+
+    /// Returns true if the packet was changed (request, response, data, sequence, flags or
+    /// checksum settings) after `pack()`, the stored checksum is stale until the next
+    /// `pack()`. Debug builds panic when a dirty packet is sent with `get_byte()`.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Returns true if `construct()` has received and validated a complete packet
+    pub fn is_complete(&self) -> bool {
+        self.status == Status::PacketReceived
+    }
+
+    /// Moves a packet completed by `construct()` out and resets this packet with
+    /// `reset_lazy()` so the next packet can be constructed right away.
+    ///
+    /// Returns `Status::PacketBuilding` if no packet is complete.
+    pub fn take(&mut self) -> Result<Packet<T>, Status> {
+        if !self.is_complete() {
+            return Err(Status::PacketBuilding);
+        }
+
+        let packet = *self;
+        self.reset_lazy();
+        Ok(packet)
+    }
+
+    fn construct_byte(&mut self, byte: u8) -> Result<(), Status> {
+        if self.discard_remaining > 0 {
+            // Payload of an oversized packet, drop it so it isn't mistaken for the next header
+            self.discard_remaining -= 1;
+            self.status = Status::DiscardingOversized;
+            return Err(self.status);
+        }
+
+        let local_internal_counter = self.internal_counter;
+
+        match local_internal_counter {
+            0 => {
+                if byte != self.get_sync_word().to_le_bytes()[0] {
+                    self.internal_counter = 0;
+                    self.status = Status::HeaderBytesNotFound;
+                    return Err(self.status);
+                }
+                self.header = byte as u16;
+            }
+            1 => {
+                let sync_word = self.get_sync_word().to_le_bytes();
+                if byte != sync_word[1] {
+                    // The rejected byte can still start the header of the next frame
+                    if byte == sync_word[0] {
+                        self.header = byte as u16;
+                        self.internal_counter = 1;
+                    } else {
+                        self.internal_counter = 0;
+                    }
+                    self.status = Status::HeaderBytesNotFound;
+                    return Err(self.status);
+                }
+                self.header |= (byte as u16) << 8;
             }
             2 => {
                 self.checksum = byte as u16;
@@ -431,55 +2130,100 @@ impl<const T: usize> Packet<T> {
             }
             8 => {
                 self.length = byte as u16;
+                self.initialize_to(byte as usize);
             }
             9 => {
                 self.length |= (byte as u16) << 8;
-                self.data_length_counter = 0;
-                if self.length == 0 {
-                    if self.validate() {
-                        self.status = Status::PacketReceived;
-                        return Ok(());
-                    } else {
-                        self.status = Status::ChecksumError;
-                        return Err(self.status);
-                    }
-                }
-
-                if self.length as usize > T {
-                    self.status = Status::InvalidDataLengthDetected;
-                    return Err(self.status);
-                }
+                self.initialize_to(self.get_length() as usize);
+            }
+            #[cfg(feature = "protocol-v2")]
+            10 => {
+                self.sequence = byte as u16;
+            }
+            #[cfg(feature = "protocol-v2")]
+            11 => {
+                self.sequence |= (byte as u16) << 8;
+            }
+            #[cfg(feature = "protocol-v2")]
+            12 => {
+                self.flags = byte;
+            }
+            #[cfg(feature = "protocol-v2")]
+            13 => {
+                self.channel = byte;
             }
             i if (FLEM_HEADER_SIZE as u32 <= i && i < FLEM_HEADER_SIZE as u32 + T as u32) => {
-                if self.data_length_counter < self.length as usize {
-                    self.data[self.data_length_counter] = byte;
+                // The length was checked against T at the end of the header, clamp again so
+                // the index can't leave the data buffer whatever state the packet is in
+                let limit = (self.get_length() as usize).min(T);
+                if self.data_length_counter < limit {
+                    self.data[self.data_length_counter] = MaybeUninit::new(byte);
                 } else {
-                    self.status = Status::PacketOverflow;
-                    return Err(self.status);
+                    return self.construct_failed(Status::PacketOverflow);
                 }
                 self.data_length_counter += 1;
-                if self.length as usize == self.data_length_counter {
+                if self.get_length() as usize == self.data_length_counter {
                     if self.validate() {
                         self.status = Status::PacketReceived;
                         return Ok(());
                     } else {
-                        self.status = Status::ChecksumError;
-                        return Err(self.status);
+                        return self.construct_failed(Status::ChecksumError);
                     }
                 }
             }
             _ => {
-                self.status = Status::PacketOverflow;
-                return Err(self.status);
+                return self.construct_failed(Status::PacketOverflow);
+            }
+        }
+
+        if local_internal_counter == FLEM_HEADER_SIZE as u32 - 1 {
+            // Last header byte, the length is known
+            self.data_length_counter = 0;
+            if self.get_length() == 0 {
+                if self.validate() {
+                    self.status = Status::PacketReceived;
+                    return Ok(());
+                } else {
+                    return self.construct_failed(Status::ChecksumError);
+                }
+            }
+
+            if self.get_length() as usize > T {
+                // Skip the payload and look for the next header once it has passed
+                self.oversized_length = self.get_length();
+                self.discard_remaining = self.get_length();
+                return self.construct_failed(Status::InvalidDataLengthDetected);
             }
         }
 
-        self.internal_counter += 1;
+        // Bounded by the header and data sizes, saturating so it can never wrap back to 0
+        self.internal_counter = self.internal_counter.saturating_add(1);
         self.status = Status::PacketBuilding;
 
         Err(self.status)
     }
 
+    /// Panics in debug builds if the packet is sent before `pack()` set the header, the
+    /// receiver would drop the frame with `HeaderBytesNotFound`
+    fn debug_assert_packed(&self) {
+        debug_assert!(
+            self.get_header() == self.get_sync_word(),
+            "Packet sent before pack(), call pack() or pack_data() first"
+        );
+        debug_assert!(
+            !self.dirty,
+            "Packet changed after pack(), call pack() again"
+        );
+    }
+
+    /// Ends a failed packet, the next byte is looked at as the first header byte
+    fn construct_failed(&mut self, status: Status) -> Result<(), Status> {
+        self.internal_counter = 0;
+        self.data_length_counter = 0;
+        self.status = status;
+        Err(status)
+    }
+
     /// This function treats the entire packet as a byte array and uses internal
     /// counters to determine the next byte. Keep calling this until either an
     /// error occurs or status is Status::GetByteFinished.
@@ -490,26 +2234,28 @@ impl<const T: usize> Packet<T> {
     /// The return value is a Result composed of the byte requested if everything is going
     /// well, or a Status as an Error indicating all bytes have been gotten.
     ///
+    /// Debug builds panic if the packet hasn't been packed, see `pack()`.
+    ///
     /// # Example
     /// ```
     /// pub fn main() {
-    ///    use flem::{Packet};
-    ///    use heapless;
+    ///    use flem::{buffer::ByteRing, builder::PacketBuilder, Packet};
     ///    const PACKET_SIZE: usize = 64; // 64 byte packet
-    ///    const FLEM_EXAMPLE_REQUEST: u16 = 0xF;
+    ///    const FLEM_EXAMPLE_REQUEST: u16 = 0x10;
     ///    
     ///    let mut rx = Packet::<PACKET_SIZE>::new();
-    ///    let mut tx = Packet::<PACKET_SIZE>::new();
     ///    
     ///    let mut data = [0 as u8; PACKET_SIZE];
     ///    
     ///    /* Add data as needed to the data buffer */
-    ///    tx.add_data(&data);
-    ///    tx.set_request(FLEM_EXAMPLE_REQUEST);
-    ///    tx.pack();
+    ///    let mut tx = PacketBuilder::<PACKET_SIZE>::new()
+    ///        .request(FLEM_EXAMPLE_REQUEST)
+    ///        .data(&data)
+    ///        .build()
+    ///        .unwrap();
     ///
     ///    /* Send data */
-    ///    let mut tx_fifo_queue = heapless::spsc::Queue::<u8, 8>::new();
+    ///    let mut tx_fifo_queue = ByteRing::<8>::new();
     ///    let mut keep_sending = true;
     ///    let mut packet_received = false;
     ///    let mut status = flem::Status::Ok;
@@ -518,7 +2264,7 @@ impl<const T: usize> Packet<T> {
     ///        if !tx_fifo_queue.is_full() && status != flem::Status::GetByteFinished {
     ///            match tx.get_byte() {
     ///                Ok(byte) => {
-    ///                    tx_fifo_queue.enqueue(byte).unwrap();
+    ///                    tx_fifo_queue.push(byte).unwrap();
     ///                },                
     ///                Err(x) => {
     ///                    /* Tx code should stop transmitting */
@@ -528,8 +2274,8 @@ impl<const T: usize> Packet<T> {
     ///            }
     ///        }else{
     ///            // Queue is full, Tx the data, Rx on the other end
-    ///            while !tx_fifo_queue.is_empty() {
-    ///                match rx.construct(tx_fifo_queue.dequeue().unwrap()) {
+    ///            while let Some(byte) = tx_fifo_queue.pop() {
+    ///                match rx.construct(byte) {
     ///                    Ok(_) => {
     ///                        packet_received = true;
     ///                        keep_sending = false;
@@ -545,16 +2291,11 @@ impl<const T: usize> Packet<T> {
     ///    assert!(packet_received, "Packet should have been transferred");
     ///
     ///    // This test is redundant, since the checksums passed, still nice to see
-    ///
-    ///    let rx_bytes = rx.bytes();
-    ///    let tx_bytes = tx.bytes();
-    ///
-    ///    for i in 0..rx_bytes.len() {
-    ///        assert_eq!(rx_bytes[i], tx_bytes[i], "Rx and Tx packets don't match");
-    ///    }
+    ///    assert!(rx == tx, "Rx and Tx packets don't match");
     ///}
     /// ```
     pub fn get_byte(&mut self) -> Result<u8, Status> {
+        self.debug_assert_packed();
         let bytes = self.bytes();
         let cnt = self.internal_counter;
         match cnt {
@@ -571,29 +2312,221 @@ impl<const T: usize> Packet<T> {
         }
     }
 
-    /// Sets the Flem request field
+    /// Sets the Flem request field.
+    ///
+    /// Debug builds panic if `request` is in the reserved range (`request::is_reserved`)
+    /// and isn't one of the well known requests. This is the only place the request is
+    /// checked: `pack_data()`, `pack_data_seq()`, `pack_async()`, `pack_error_code()` and
+    /// `with_payload()` accept any code, since a device packs its response with the request
+    /// code it received and shouldn't panic on what a partner sent.
     pub fn set_request(&mut self, request: u16) {
+        debug_assert_request(request);
         self.request = request;
+        self.dirty = true;
     }
 
     /// Gets the Flem request field
     pub fn get_request(&self) -> u16 {
-        self.request
+        read_field!(self, request)
     }
 
     /// Returns the stored checksum value
     pub fn get_checksum(&self) -> u16 {
-        self.checksum
+        read_field!(self, checksum)
     }
 
     /// Sets the Flem response field
     pub fn set_response(&mut self, response: u16) {
         self.response = response;
+        self.dirty = true;
     }
 
     /// Gets the Flem response field
     pub fn get_response(&self) -> u16 {
-        self.response
+        read_field!(self, response)
+    }
+
+    /// The response as a well known response, or the raw code for application defined
+    /// responses
+    pub fn response_kind(&self) -> Result<WellKnownResponse, u16> {
+        WellKnownResponse::try_from(self.get_response())
+    }
+
+    /// Returns true if the response is `response::ASYNC`, an event packed with
+    /// `pack_async()` rather than a response to a request
+    pub fn is_async(&self) -> bool {
+        self.get_response() == response::ASYNC
+    }
+
+    /// Returns true if the response is `response::SUCCESS`
+    pub fn is_success(&self) -> bool {
+        self.get_response() == response::SUCCESS
+    }
+
+    /// Sets the sequence number. The sequence number is part of the checksum, set it
+    /// before packing. It is kept by `reset_lazy()` so it can be set before `pack_data()`.
+    #[cfg(feature = "protocol-v2")]
+    pub fn set_sequence(&mut self, sequence: u16) {
+        self.sequence = sequence;
+        self.dirty = true;
+    }
+
+    /// Gets the sequence number
+    #[cfg(feature = "protocol-v2")]
+    pub fn get_sequence(&self) -> u16 {
+        read_field!(self, sequence)
+    }
+
+    /// Gets the flags byte, see `flags`
+    #[cfg(feature = "protocol-v2")]
+    pub fn get_flags(&self) -> u8 {
+        read_field!(self, flags)
+    }
+
+    /// Number of packets missing between the packet with sequence number `previous` and
+    /// this packet. Returns 0 if this packet directly follows `previous`. Sequence numbers
+    /// wrap around at u16::MAX.
+    #[cfg(feature = "protocol-v2")]
+    pub fn sequence_gap(&self, previous: u16) -> u16 {
+        self.get_sequence().wrapping_sub(previous).wrapping_sub(1)
+    }
+
+    /// Sets or clears the `flags::MORE_FRAGMENTS` bit. Set it on every packet of a payload
+    /// that is split across packets except the last one. The flags are part of the checksum,
+    /// set them before packing. They are kept by `reset_lazy()`.
+    #[cfg(feature = "protocol-v2")]
+    pub fn set_more_fragments(&mut self, more_fragments: bool) {
+        if more_fragments {
+            self.flags |= flags::MORE_FRAGMENTS;
+        } else {
+            self.flags &= !flags::MORE_FRAGMENTS;
+        }
+        self.dirty = true;
+    }
+
+    /// Sets or clears `flag`, one or more of the `flags` bits. The flags are part of the
+    /// checksum, set them before packing. They are kept by `reset_lazy()`.
+    #[cfg(feature = "protocol-v2")]
+    pub fn set_flag(&mut self, flag: u8, set: bool) {
+        if set {
+            self.flags |= flag;
+        } else {
+            self.flags &= !flag;
+        }
+        self.dirty = true;
+    }
+
+    /// Returns true if all bits of `flag` are set
+    #[cfg(feature = "protocol-v2")]
+    pub fn has_flag(&self, flag: u8) -> bool {
+        self.get_flags() & flag == flag
+    }
+
+    /// Returns true if more packets of the same payload follow this one
+    #[cfg(feature = "protocol-v2")]
+    pub fn has_more_fragments(&self) -> bool {
+        self.get_flags() & flags::MORE_FRAGMENTS != 0
+    }
+
+    /// Sets the channel, the subsystem a packet is addressed to on a shared link. Channel 0
+    /// is the default. The channel is part of the checksum, set it before packing. It is
+    /// kept by `reset_lazy()`, `reset()` sets it to 0.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut tx = flem::Packet::<16>::new();
+    ///     tx.set_channel(3);
+    ///     tx.pack_data(0x10, &[1]).unwrap();
+    ///
+    ///     let (rx, _) = flem::Packet::<16>::from_bytes(tx.bytes()).unwrap();
+    ///     assert_eq!(rx.get_channel(), 3);
+    /// }
+    /// ```
+    #[cfg(feature = "protocol-v2")]
+    pub fn set_channel(&mut self, channel: u8) {
+        self.channel = channel;
+        self.dirty = true;
+    }
+
+    /// Gets the channel
+    #[cfg(feature = "protocol-v2")]
+    pub fn get_channel(&self) -> u8 {
+        read_field!(self, channel)
+    }
+
+    /// Sets the bytes covered by the checksum in `pack()` and `validate()`. The coverage is
+    /// kept by `reset()` and `reset_lazy()`.
+    pub fn set_checksum_coverage(&mut self, coverage: ChecksumCoverage) {
+        self.coverage = coverage;
+        self.dirty = true;
+    }
+
+    /// Gets the bytes covered by the checksum
+    pub fn get_checksum_coverage(&self) -> ChecksumCoverage {
+        self.coverage
+    }
+
+    /// Sets the checksum algorithm used by `pack()` and `validate()`, CRC-16/IBM by
+    /// default. Both partners must use the same algorithm. The algorithm is kept by
+    /// `reset()` and `reset_lazy()`.
+    pub fn set_checksum_algorithm(&mut self, algorithm: ChecksumAlgorithm) {
+        self.algorithm = algorithm;
+        self.dirty = true;
+    }
+
+    /// Gets the checksum algorithm
+    pub fn get_checksum_algorithm(&self) -> ChecksumAlgorithm {
+        self.algorithm
+    }
+
+    /// Sets the header value `pack()` writes and `construct()` hunts for, `FLEM_HEADER` by
+    /// default. Logical channels sharing one wire use different sync words, a packet set
+    /// to one sync word skips the frames of the others as `HeaderBytesNotFound`. The sync
+    /// word is kept by `reset()` and `reset_lazy()`.
+    ///
+    /// A second header byte that doesn't match is looked at again as the first header byte,
+    /// so a stray byte in front of a frame doesn't hide the frame.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut motor = flem::Packet::<16>::new();
+    ///     motor.set_sync_word(0x5A5A);
+    ///     motor.pack_data(0x10, &[1]).unwrap();
+    ///
+    ///     let mut sensor = flem::Packet::<16>::new();
+    ///     sensor.set_sync_word(0xA5A5);
+    ///     assert!(sensor.construct_slice(motor.bytes()).is_err());
+    /// }
+    /// ```
+    pub fn set_sync_word(&mut self, sync_word: u16) {
+        self.sync_word = sync_word;
+        self.dirty = true;
+    }
+
+    /// Gets the sync word, see `set_sync_word`
+    pub fn get_sync_word(&self) -> u16 {
+        self.sync_word
+    }
+
+    /// Limits the payload `add_data` / `pack_data` accept to what the partner can receive,
+    /// even if T is larger. Usually the result of `DataId::negotiate` with the partner's ID.
+    /// The limit is kept by `reset()` and `reset_lazy()`, set it to `u16::MAX` to remove it.
+    pub fn set_peer_limit(&mut self, limit: u16) {
+        self.peer_limit = limit;
+    }
+
+    /// Gets the payload limit set with `set_peer_limit`, `u16::MAX` if there is none
+    pub fn get_peer_limit(&self) -> u16 {
+        self.peer_limit
+    }
+
+    /// Length field of the last packet that was too long for the data buffer, 0 if there
+    /// hasn't been one since the last `reset()`. Useful to tell the partner the packet size
+    /// this side can receive.
+    pub fn get_oversized_length(&self) -> u16 {
+        self.oversized_length
     }
 
     /// Gets the status byte from the packet
@@ -601,52 +2534,551 @@ impl<const T: usize> Packet<T> {
         self.status
     }
 
+    /// Returns a copy of the receive statistics. Stats are kept through `reset()` and
+    /// `reset_lazy()`, use `reset_stats()` to clear them.
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// Clears the receive statistics
+    pub fn reset_stats(&mut self) {
+        self.stats = Stats::new();
+    }
+
+    /// Number of received bytes `construct()` threw away: bytes consumed while searching
+    /// for the header bytes (also counted in `Stats::resync_bytes`) and the payload bytes
+    /// of oversized packets (`Status::DiscardingOversized`). Kept through `reset_lazy()`,
+    /// cleared by `reset()` and `clear_discarded()`. Saturates at u32::MAX.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut tx = flem::Packet::<16>::new();
+    ///     tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+    ///
+    ///     let mut rx = flem::Packet::<16>::new();
+    ///     for byte in [0x00, 0xFF, 0x13] {
+    ///         let _ = rx.construct(byte);
+    ///     }
+    ///     assert_eq!(rx.construct_slice(tx.bytes()), Ok(tx.length()));
+    ///     assert_eq!(rx.discarded_bytes(), 3);
+    /// }
+    /// ```
+    pub fn discarded_bytes(&self) -> u32 {
+        self.discarded
+    }
+
+    /// Sets `discarded_bytes()` back to 0, for example after reporting it
+    pub fn clear_discarded(&mut self) {
+        self.discarded = 0;
+    }
+
     /// Get the header byte as u16
     pub fn get_header(&self) -> u16 {
-        self.header
+        read_field!(self, header)
+    }
+
+    /// Gets the length field, the number of payload bytes. Unlike `length()` this is the
+    /// raw field and isn't clamped to T.
+    pub fn get_length(&self) -> u16 {
+        read_field!(self, length)
     }
 
     pub fn get_data_length(&self) -> usize {
         self.data_length_counter
     }
 
-    /// Returns the _entire_ packet as a u8 byte array
-    pub fn bytes(&self) -> &[u8] {
-        let stream: &[u8] = unsafe {
+    /// Number of wire bytes already sent with `get_byte()`, `get_bytes_into()` or
+    /// `write_to()`, the index of the next byte to send. `length()` once the whole packet
+    /// has been sent. The transmit and receive cursors share a counter, only the one of the
+    /// direction the packet is used for is meaningful.
+    pub fn tx_cursor(&self) -> usize {
+        self.internal_counter as usize
+    }
+
+    /// Number of wire bytes of the current frame received with `construct()`, header
+    /// included, `length()` once the packet is complete. 0 before a header byte arrived and
+    /// after a failed frame. See `get_data_length()` for the payload bytes only.
+    pub fn rx_cursor(&self) -> usize {
+        if self.status == Status::PacketReceived {
+            self.length()
+        } else {
+            self.internal_counter as usize
+        }
+    }
+
+    /// Copies up to `out.len()` bytes from the transmit cursor into `out` and advances the
+    /// cursor, the multi-byte version of `get_byte()` for FIFOs and DMA. Returns the number
+    /// of bytes written, 0 once the whole packet has been sent.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut tx = flem::Packet::<32>::new();
+    ///     tx.pack_data(0x10, &[0x55; 20]).unwrap();
+    ///
+    ///     let mut fifo = [0u8; 16];
+    ///     let mut sent = Vec::new();
+    ///     loop {
+    ///         let count = tx.get_bytes_into(&mut fifo);
+    ///         if count == 0 {
+    ///             break;
+    ///         }
+    ///         sent.extend_from_slice(&fifo[..count]);
+    ///     }
+    ///
+    ///     assert_eq!(sent, tx.bytes());
+    /// }
+    /// ```
+    pub fn get_bytes_into(&mut self, out: &mut [u8]) -> usize {
+        self.debug_assert_packed();
+        let start = (self.internal_counter as usize).min(self.length());
+        let count = out.len().min(self.length() - start);
+        if start == self.length() {
+            self.status = Status::GetByteFinished;
+            return 0;
+        }
+        out[..count].copy_from_slice(&self.bytes()[start..start + count]);
+        self.internal_counter += count as u32;
+        self.status = Status::Ok;
+        count
+    }
+
+    /// Number of bytes the packet takes on the wire, header and payload. Same as `length()`
+    /// and the length of `bytes()`.
+    pub fn wire_len(&self) -> usize {
+        self.length()
+    }
+
+    /// Splits the wire bytes into chunks of at most `chunk` bytes, for links that take a
+    /// fixed number of bytes per write (FIFOs, BLE MTUs). The last chunk is shorter if the
+    /// packet doesn't divide evenly, it isn't padded. Only `wire_len()` bytes are covered.
+    ///
+    /// Panics if `chunk` is 0.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut tx = flem::Packet::<64>::new();
+    ///     tx.pack_data(0x10, &[0x55; 30]).unwrap();
+    ///
+    ///     let mut rx = flem::Packet::<64>::new();
+    ///     for chunk in tx.bytes_chunks(20) {
+    ///         assert!(chunk.len() <= 20);
+    ///         let _ = rx.construct_slice(chunk);
+    ///     }
+    ///     assert_eq!(rx, tx);
+    /// }
+    /// ```
+    pub fn bytes_chunks(&self, chunk: usize) -> core::slice::Chunks<'_, u8> {
+        self.bytes().chunks(chunk)
+    }
+
+    /// Pushes the bytes of a packed packet into `sink` until the sink returns false (FIFO
+    /// full) or the whole packet has been sent, for interrupt driven transmitters. A byte
+    /// the sink refused is offered again on the next call, so no bytes are lost or repeated.
+    ///
+    /// Returns `Status::GetByteFinished` once the whole packet has been sent, otherwise
+    /// `Status::Ok` and the transmit can be resumed with another call.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut tx = flem::Packet::<32>::new();
+    ///     tx.pack_data(0x10, &[0x55; 20]).unwrap();
+    ///
+    ///     let mut fifo = Vec::new();
+    ///     let mut sent = Vec::new();
+    ///     while tx.write_to(&mut |byte| {
+    ///         if fifo.len() == 8 {
+    ///             return false; // FIFO full
+    ///         }
+    ///         fifo.push(byte);
+    ///         true
+    ///     }) != flem::Status::GetByteFinished
+    ///     {
+    ///         sent.append(&mut fifo); // FIFO drained by the hardware
+    ///     }
+    ///     sent.append(&mut fifo);
+    ///
+    ///     assert_eq!(sent, tx.bytes());
+    /// }
+    /// ```
+    pub fn write_to<F: FnMut(u8) -> bool>(&mut self, sink: &mut F) -> Status {
+        loop {
+            let position = self.internal_counter;
+            match self.get_byte() {
+                Ok(byte) => {
+                    if !sink(byte) {
+                        // Offer the same byte again next time
+                        self.internal_counter = position;
+                        return Status::Ok;
+                    }
+                }
+                Err(status) => return status,
+            }
+        }
+    }
+
+    /// Returns the header bytes and the payload separately, for transmitting with two DMA
+    /// descriptors instead of one contiguous buffer. The header is built field by field in
+    /// little endian order, the payload is the valid part of the data buffer.
+    ///
+    /// The packet must be packed first, otherwise `Status::HeaderBytesNotFound` is returned.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut tx = flem::Packet::<16>::new();
+    ///     tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+    ///
+    ///     let (header, payload) = tx.as_parts().unwrap();
+    ///     assert_eq!(header.len(), flem::FLEM_HEADER_SIZE);
+    ///     assert_eq!(payload, &[1, 2, 3]);
+    /// }
+    /// ```
+    pub fn as_parts(&self) -> Result<([u8; FLEM_HEADER_SIZE], &[u8]), Status> {
+        if self.get_header() != self.get_sync_word() {
+            return Err(Status::HeaderBytesNotFound);
+        }
+
+        Ok((self.header_bytes(), self.payload()))
+    }
+
+    /// Header fields in wire order, little endian
+    fn header_bytes(&self) -> [u8; FLEM_HEADER_SIZE] {
+        let mut header = [0u8; FLEM_HEADER_SIZE];
+        let fields = [
+            self.get_header(),
+            self.get_checksum(),
+            self.get_request(),
+            self.get_response(),
+            self.get_length(),
+            #[cfg(feature = "protocol-v2")]
+            self.get_sequence(),
+        ];
+        for (chunk, field) in header.chunks_exact_mut(2).zip(fields.iter()) {
+            chunk.copy_from_slice(&field.to_le_bytes());
+        }
+        #[cfg(feature = "protocol-v2")]
+        {
+            header[12] = self.get_flags();
+            header[13] = self.get_channel();
+        }
+        header
+    }
+
+    /// The valid part of the data buffer, `length` bytes, without copying it
+    pub fn payload(&self) -> &[u8] {
+        let length = (self.get_length() as usize).min(T);
+        &self.initialized_data()[..length]
+    }
+
+    /// The initialized part of the data buffer, `payload()` is always inside it
+    fn initialized_data(&self) -> &[u8] {
+        let initialized = (self.initialized as usize).min(T);
+        // SAFETY: [MaybeUninit<u8>; T] has an alignment of 1, so a pointer to it is aligned
+        // even in the packed struct, and `data[..initialized]` has been written.
+        unsafe {
+            ::core::slice::from_raw_parts(core::ptr::addr_of!(self.data) as *const u8, initialized)
+        }
+    }
+
+    /// Zeroes the data buffer up to `end` if it isn't initialized that far yet, so
+    /// `payload()` can cover it
+    fn initialize_to(&mut self, end: usize) {
+        let end = end.min(T);
+        for i in self.initialized as usize..end {
+            self.data[i] = MaybeUninit::new(0);
+        }
+        self.initialized = self.initialized.max(end as u16);
+    }
+
+    /// `len` payload bytes starting at `offset`. Returns `Status::PayloadUnderflow` if they
+    /// run past the payload, the bytes after `length` in the data buffer are never returned.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut tx = flem::Packet::<16>::new();
+    ///     tx.pack_data(0x10, &[0x34, 0x12, 0x78, 0x56]).unwrap();
+    ///
+    ///     assert_eq!(tx.read_u16_le(0), Ok(0x1234));
+    ///     assert_eq!(tx.read_u32_le(0), Ok(0x5678_1234));
+    ///     assert_eq!(tx.read_u32_le(2), Err(flem::Status::PayloadUnderflow));
+    /// }
+    /// ```
+    pub fn read_bytes(&self, offset: usize, len: usize) -> Result<&[u8], Status> {
+        let end = offset.checked_add(len).ok_or(Status::PayloadUnderflow)?;
+        self.payload()
+            .get(offset..end)
+            .ok_or(Status::PayloadUnderflow)
+    }
+
+    /// Byte at `offset` of the payload, see `read_bytes`
+    pub fn read_u8(&self, offset: usize) -> Result<u8, Status> {
+        self.read_array(offset).map(u8::from_le_bytes)
+    }
+
+    /// Byte at `offset` of the payload as an i8, see `read_bytes`
+    pub fn read_i8(&self, offset: usize) -> Result<i8, Status> {
+        self.read_array(offset).map(i8::from_le_bytes)
+    }
+
+    /// Little endian u16 at `offset` of the payload, see `read_bytes`
+    pub fn read_u16_le(&self, offset: usize) -> Result<u16, Status> {
+        self.read_array(offset).map(u16::from_le_bytes)
+    }
+
+    /// Little endian i16 at `offset` of the payload, see `read_bytes`
+    pub fn read_i16_le(&self, offset: usize) -> Result<i16, Status> {
+        self.read_array(offset).map(i16::from_le_bytes)
+    }
+
+    /// Little endian u32 at `offset` of the payload, see `read_bytes`
+    pub fn read_u32_le(&self, offset: usize) -> Result<u32, Status> {
+        self.read_array(offset).map(u32::from_le_bytes)
+    }
+
+    /// Little endian i32 at `offset` of the payload, see `read_bytes`
+    pub fn read_i32_le(&self, offset: usize) -> Result<i32, Status> {
+        self.read_array(offset).map(i32::from_le_bytes)
+    }
+
+    /// Little endian u64 at `offset` of the payload, see `read_bytes`
+    pub fn read_u64_le(&self, offset: usize) -> Result<u64, Status> {
+        self.read_array(offset).map(u64::from_le_bytes)
+    }
+
+    /// Little endian i64 at `offset` of the payload, see `read_bytes`
+    pub fn read_i64_le(&self, offset: usize) -> Result<i64, Status> {
+        self.read_array(offset).map(i64::from_le_bytes)
+    }
+
+    /// Little endian f32 at `offset` of the payload, see `read_bytes`
+    pub fn read_f32_le(&self, offset: usize) -> Result<f32, Status> {
+        self.read_array(offset).map(f32::from_le_bytes)
+    }
+
+    /// Little endian f64 at `offset` of the payload, see `read_bytes`
+    pub fn read_f64_le(&self, offset: usize) -> Result<f64, Status> {
+        self.read_array(offset).map(f64::from_le_bytes)
+    }
+
+    /// A `P` copied from the raw bytes at `offset` of the payload, so the payload doesn't
+    /// have to be aligned for `P`. Returns `Status::PayloadUnderflow` if the payload ends
+    /// before `offset + size_of::<P>()`. The bytes are in the byte order of this target,
+    /// see `add_pod`. Enabled with features = ["bytemuck"].
+    #[cfg(feature = "bytemuck")]
+    pub fn read_pod<P: bytemuck::Pod>(&self, offset: usize) -> Result<P, Status> {
+        self.read_bytes(offset, core::mem::size_of::<P>())
+            .map(bytemuck::pod_read_unaligned)
+    }
+
+    /// The payload as a string. Returns `Status::InvalidUtf8` if it isn't valid UTF-8.
+    pub fn payload_as_str(&self) -> Result<&str, Status> {
+        core::str::from_utf8(self.payload()).map_err(|_| Status::InvalidUtf8)
+    }
+
+    /// The payload up to the first NUL as a string, all of it if there is no NUL. For C
+    /// partners that send NUL terminated strings shorter than the payload. Returns
+    /// `Status::InvalidUtf8` if the bytes before the NUL aren't valid UTF-8.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut tx = flem::Packet::<16>::new();
+    ///     tx.pack_data(0x10, b"Pump\0\x01\x02").unwrap();
+    ///
+    ///     assert_eq!(tx.payload_as_str_nul(), Ok("Pump"));
+    ///     assert!(tx.payload_as_str().is_ok(), "NUL and control bytes are valid UTF-8");
+    /// }
+    /// ```
+    pub fn payload_as_str_nul(&self) -> Result<&str, Status> {
+        let payload = self.payload();
+        let end = payload
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(payload.len());
+        core::str::from_utf8(&payload[..end]).map_err(|_| Status::InvalidUtf8)
+    }
+
+    fn read_array<const N: usize>(&self, offset: usize) -> Result<[u8; N], Status> {
+        let mut bytes = [0; N];
+        bytes.copy_from_slice(self.read_bytes(offset, N)?);
+        Ok(bytes)
+    }
+
+    /// Encodes the packed packet into `out` as a 0x00 terminated COBS frame, so the header
+    /// bytes can't be confused with payload bytes on unframed links. Receive the frames
+    /// with `cobs::CobsReceiver`. Same as `cobs::encode_into(self, out)`.
+    ///
+    /// Returns the number of bytes written, or `Status::PacketOverflow` if `out` is shorter
+    /// than `cobs::max_encoded_len(self.length())`.
+    #[cfg(feature = "cobs")]
+    pub fn cobs_encode(&self, out: &mut [u8]) -> Result<usize, Status> {
+        cobs::encode_into(self, out)
+    }
+
+    /// The whole wire region of the packet, `FLEM_HEADER_SIZE + T` bytes, for DMA engines
+    /// that want the maximally sized buffer. Only the first `length()` bytes are valid, tell
+    /// the engine to send `length()` bytes.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut tx = flem::Packet::<64>::new();
+    ///     tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+    ///
+    ///     let region = tx.as_full_bytes();
+    ///     assert_eq!(region.len(), flem::FLEM_HEADER_SIZE + 64);
+    ///     assert_eq!(&region[..tx.length()], tx.bytes());
+    /// }
+    /// ```
+    pub fn as_full_bytes(&self) -> &[u8] {
+        // SAFETY: The wire fields and the data are in front of the other fields of the
+        // repr(C) struct without padding (see the FLEM_HEADER_SIZE assert). The wire fields
+        // and `data[..initialized]` are initialized.
+        unsafe {
             ::core::slice::from_raw_parts(
                 (self as *const Packet<T>) as *const u8,
-                self.length() as usize,
+                FLEM_HEADER_SIZE + self.initialized_len().min(T),
             )
+        }
+    }
+
+    /// Returns the _entire_ packet as a u8 byte array
+    pub fn bytes(&self) -> &[u8] {
+        let stream: &[u8] = unsafe {
+            ::core::slice::from_raw_parts((self as *const Packet<T>) as *const u8, self.length())
         };
 
-        return stream;
+        stream
     }
 
-    /// Computes a CRC16 IBM style checksum on the packet, except the header
-    /// and checksum bytes
-    pub fn checksum(&mut self, store: bool) -> u16 {
-        let mut crc: u16 = 0;
-        let bytes: &[u8] = self.bytes();
-        let psize: u16 = bytes.len() as u16;
-
-        //Skip the first 4 bytes, 2 header and 2 checksum
-        for i in 4..psize {
-            let ptr = bytes[i as usize] as u16;
-            let lut_index = (crc ^ ptr) as u8;
-            let mut tmp_crc = CRC16_TAB[lut_index as usize];
-            tmp_crc ^= crc >> 8;
-            crc = tmp_crc;
+    /// Writes the wire bytes, `bytes()`, as space separated upper case hex pairs, for
+    /// logging the exact frame. Works without `std`, e.g. into a `heapless::String`.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     use core::fmt::Write;
+    ///
+    ///     let mut tx = flem::Packet::<16>::new();
+    ///     tx.pack_data(0x10, &[0xAB]).unwrap();
+    ///
+    ///     let mut hex = heapless::String::<64>::new();
+    ///     tx.write_hex(&mut hex).unwrap();
+    ///     assert!(hex.starts_with("55 "));
+    ///     assert!(hex.ends_with(" AB"));
+    /// }
+    /// ```
+    pub fn write_hex(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        for (index, byte) in self.bytes().iter().enumerate() {
+            if index > 0 {
+                f.write_char(' ')?;
+            }
+            write!(f, "{:02X}", byte)?;
         }
+        Ok(())
+    }
 
+    /// Computes a checksum on the packet, except the header and checksum bytes, with the
+    /// algorithm set by `set_checksum_algorithm` (CRC16 IBM by default). With `ChecksumCoverage::IncludeHeader` the header bytes
+    /// are included, the checksum bytes are always skipped. With
+    /// `ChecksumCoverage::Disabled` the checksum is 0.
+    pub fn checksum(&mut self, store: bool) -> u16 {
+        let crc = self.compute_checksum();
         if store {
             self.checksum = crc;
         }
+        crc
+    }
 
-        return crc;
+    /// Stored and computed checksum, the state of the parser and the header fields in one
+    /// `Copy` struct, for logging a packet that failed. Works on packed packets and on
+    /// received packets in any state, also halfway through `construct()`.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut tx = flem::Packet::<16>::new();
+    ///     tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+    ///
+    ///     let diagnostics = tx.explain();
+    ///     assert!(diagnostics.checksum_matches());
+    ///     assert_eq!(diagnostics.payload_received, 3);
+    /// }
+    /// ```
+    pub fn explain(&self) -> PacketDiagnostics {
+        let building = self.status == Status::PacketBuilding;
+        PacketDiagnostics {
+            header: self.get_header(),
+            request: self.get_request(),
+            response: self.get_response(),
+            length: self.get_length(),
+            payload_received: if building {
+                self.data_length_counter
+            } else {
+                self.payload().len()
+            },
+            stored_checksum: self.get_checksum(),
+            computed_checksum: (!building).then(|| self.compute_checksum()),
+            status: self.status,
+            byte_index: self.internal_counter,
+        }
+    }
+
+    fn compute_checksum(&self) -> u16 {
+        let bytes: &[u8] = self.bytes();
+
+        let covered = match self.coverage {
+            ChecksumCoverage::PayloadOnly => &bytes[..0],
+            ChecksumCoverage::IncludeHeader => &bytes[..2],
+            ChecksumCoverage::Disabled => return 0,
+        };
+
+        //Skip the first 4 bytes, 2 header and 2 checksum
+        let algorithm = self.algorithm;
+        algorithm.compute(&[covered, &bytes[4..]])
+    }
+
+    /// Resets only the receive state: the header, the length and the receive counters, and
+    /// sets the status to Ok. The checksum, request and response fields are kept, so a
+    /// receive-only packet can recover after a bad frame without disturbing them.
+    ///
+    /// Unlike `reset_lazy()`, it also stops skipping the payload of an oversized packet,
+    /// the next byte is looked at as the first header byte. `get_oversized_length()` is
+    /// kept.
+    ///
+    /// # Example
+    /// ```
+    /// pub fn main() {
+    ///     let mut rx = flem::Packet::<16>::new();
+    ///     assert!(rx.construct_slice(&[0x55, 0x55, 0x00]).is_err());
+    ///
+    ///     rx.reset_rx();
+    ///     assert_eq!(rx.get_header(), 0);
+    ///     assert_eq!(rx.get_status(), flem::Status::Ok);
+    /// }
+    /// ```
+    pub fn reset_rx(&mut self) {
+        self.header = 0;
+        self.length = 0;
+        self.internal_counter = 0;
+        self.data_length_counter = 0;
+        self.status = Status::Ok;
+        self.stop_discarding();
     }
 
     /// Resets the packet to all 0's, but does not clear the data array. Much faster than
     /// zeroing out the packet's data buffer. **Packets should be cleared before reusing, both Rx and Tx.**
+    ///
+    /// If `construct()` is skipping the payload of an oversized packet it keeps doing so,
+    /// use `reset()` to stop.
+    ///
+    /// With protocol-v2 the sequence number, flags and channel are kept, except
+    /// `flags::TRANSACTION` which goes with the payload.
     pub fn reset_lazy(&mut self) {
         self.checksum = 0;
         self.request = 0;
@@ -655,6 +3087,11 @@ impl<const T: usize> Packet<T> {
         self.internal_counter = 0;
         self.status = Status::Ok;
         self.data_length_counter = 0;
+        #[cfg(feature = "protocol-v2")]
+        {
+            self.flags &= !flags::TRANSACTION;
+        }
+        self.dirty = false;
     }
 
     /// Resets the packet. The data array is cleared only if clear_data is true. **Packets should be
@@ -665,13 +3102,52 @@ impl<const T: usize> Packet<T> {
     /// * `clear_data` - Zero out the data array.
     pub fn reset(&mut self) {
         self.reset_lazy();
+        #[cfg(feature = "protocol-v2")]
+        {
+            self.sequence = 0;
+            self.flags = 0;
+            self.channel = 0;
+        }
         for i in 0..T {
-            self.data[i] = 0;
+            self.data[i] = MaybeUninit::new(0);
         }
+        self.initialized = T as u16;
         self.data_length_counter = 0;
+        self.stop_discarding();
+        self.oversized_length = 0;
+        self.discarded = 0;
     }
 
-    /// Length of the packet, **including the header and data.**
+    /// Puts back everything `new()` sets except the data buffer: the header fields, the
+    /// receive state, the stats and the settings. Used for packets returned to a
+    /// `buffer::PacketPool`, so the next user doesn't inherit them.
+    pub(crate) fn reset_for_reuse(&mut self) {
+        self.reset_lazy();
+        self.header = 0;
+        #[cfg(feature = "protocol-v2")]
+        {
+            self.sequence = 0;
+            self.flags = 0;
+            self.channel = 0;
+        }
+        self.stop_discarding();
+        self.oversized_length = 0;
+        self.discarded = 0;
+        self.stats = Stats::new();
+        self.peer_limit = u16::MAX;
+        self.coverage = ChecksumCoverage::PayloadOnly;
+        self.algorithm = ChecksumAlgorithm::Crc16Ibm;
+        self.sync_word = FLEM_HEADER;
+    }
+
+    /// Stops skipping the payload of an oversized packet, for framings that already know
+    /// where the next packet starts.
+    pub(crate) fn stop_discarding(&mut self) {
+        self.discard_remaining = 0;
+    }
+
+    /// Length of the packet, **including the header and data.** The length is clamped to
+    /// `FLEM_HEADER_SIZE + T`, even if the length field is larger after a failed `construct()`.
     ///
     /// # Example
     /// ```
@@ -691,27 +3167,127 @@ impl<const T: usize> Packet<T> {
     /// }
     /// ```
     pub fn length(&self) -> usize {
-        let mut x: usize = FLEM_HEADER_SIZE as usize;
-        x += self.length as usize;
-        return x;
+        // A packet that failed construct() can hold any value in the length field, never
+        // report more than the packet can hold so bytes() stays inside the packet.
+        let length = (self.get_length() as usize).min(T);
+        FLEM_HEADER_SIZE + length
+    }
+}
+
+impl<const T: usize> Default for Packet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Appends to the payload, see `add_iter`. Bytes past the end of the packet are dropped and
+/// the status is set to `PacketOverflow`.
+impl<const T: usize> Extend<u8> for Packet<T> {
+    fn extend<I: IntoIterator<Item = u8>>(&mut self, iter: I) {
+        let _ = self.add_iter(iter);
+    }
+}
+
+/// Appends formatted text to the payload with `write!`. The text is added with `add_data`,
+/// text that doesn't fit isn't added, the status is set to `PacketOverflow` and `fmt::Error`
+/// is returned. Text from earlier writes is kept.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use core::fmt::Write;
+///
+///     let mut packet = flem::Packet::<16>::new();
+///     let temp = 21;
+///     write!(packet, "T={} C", temp).unwrap();
+///     packet.pack();
+///
+///     assert_eq!(&packet.bytes()[flem::FLEM_HEADER_SIZE..], b"T=21 C");
+/// }
+/// ```
+impl<const T: usize> fmt::Write for Packet<T> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.add_data(s.as_bytes()).map_err(|_| Error)
+    }
+}
+
+/// Packets are equal if their wire bytes are equal: header, checksum, request, response,
+/// length (plus sequence and flags for protocol version 2) and the valid part of the data.
+/// The internal counters, status and stats are ignored, so a transmitted packet equals the
+/// packet received from it.
+///
+/// There is deliberately no `Hash`, packets are buffers rather than keys. Hash `bytes()` to
+/// deduplicate frames.
+impl<const T: usize> PartialEq for Packet<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes() == other.bytes()
+    }
+}
+
+impl<const T: usize> Eq for Packet<T> {}
+
+/// Parses a buffer holding exactly one frame, see `Packet::from_bytes`. Bytes after the
+/// frame return `Status::PacketOverflow`.
+impl<const T: usize> TryFrom<&[u8]> for Packet<T> {
+    type Error = Status;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let (packet, used) = Packet::from_bytes(bytes)?;
+        if used != bytes.len() {
+            return Err(Status::PacketOverflow);
+        }
+        Ok(packet)
+    }
+}
+
+/// The payload, `payload()`
+impl<const T: usize> AsRef<[u8]> for Packet<T> {
+    fn as_ref(&self) -> &[u8] {
+        self.payload()
+    }
+}
+
+/// Indexes the payload like a slice, `packet[0]` or `packet[1..3]`. Indexes are checked
+/// against the length field, not T, and panic past the end of the payload.
+impl<const T: usize, I: SliceIndex<[u8]>> Index<I> for Packet<T> {
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &Self::Output {
+        &self.payload()[index]
+    }
+}
+
+/// Changes payload bytes in place, see `Index`. The payload can't grow this way, use
+/// `add_data()`. The packet has to be packed again before it is sent.
+impl<const T: usize, I: SliceIndex<[u8]>> IndexMut<I> for Packet<T> {
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        let length = (self.get_length() as usize).min(T);
+        self.dirty = true;
+        // SAFETY: [MaybeUninit<u8>; T] has an alignment of 1 and the payload is initialized,
+        // see `initialized_data()`
+        let data: &mut [u8] = unsafe {
+            ::core::slice::from_raw_parts_mut(core::ptr::addr_of_mut!(self.data) as *mut u8, length)
+        };
+        &mut data[index]
     }
 }
 
 impl<const T: usize> Debug for Packet<T> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-        let header = self.header;
-        let checksum = self.checksum;
-        let request = self.request;
-        let response = self.response;
-        let length = self.length;
-
-        f.debug_struct("Packet")
-            .field("header", &header)
-            .field("checksum", &checksum)
-            .field("request", &request)
-            .field("response", &response)
-            .field("length", &length)
-            .field("status", &self.status)
-            .finish()
+        let mut debug = f.debug_struct("Packet");
+        debug
+            .field("header", &self.get_header())
+            .field("checksum", &self.get_checksum())
+            .field("request", &self.get_request())
+            .field("response", &self.get_response())
+            .field("length", &self.get_length());
+        #[cfg(feature = "protocol-v2")]
+        {
+            debug
+                .field("sequence", &self.get_sequence())
+                .field("flags", &self.get_flags())
+                .field("channel", &self.get_channel());
+        }
+        debug.field("status", &self.status).finish()
     }
 }
@@ -0,0 +1,112 @@
+//! Non-blocking send / receive for HAL serial drivers with an `nb` API (stm32, nrf, ...).
+//! Enabled with features = ["nb"].
+//!
+//! Both functions return `nb::Error::WouldBlock` when the serial driver does, keeping their
+//! progress in the packet. Calling them again resumes where they left off, so they can be
+//! polled from a main loop or wrapped in `nb::block!`.
+
+use core::fmt::{self, Display, Formatter};
+
+use crate::{Packet, Status};
+
+/// Error returned by `send` and `recv`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlemNbError<E> {
+    /// The serial driver failed
+    Serial(E),
+    /// The bytes received didn't form a valid packet
+    Flem(Status),
+}
+
+impl<E: fmt::Debug> Display for FlemNbError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FlemNbError::Serial(error) => write!(f, "Serial error: {:?}", error),
+            FlemNbError::Flem(status) => write!(f, "Invalid packet: {:?}", status),
+        }
+    }
+}
+
+impl<E> From<Status> for FlemNbError<E> {
+    fn from(status: Status) -> Self {
+        FlemNbError::Flem(status)
+    }
+}
+
+/// Writes a packed packet a byte at a time with `serial`, usually a closure around the
+/// driver's `write()`. A byte that returned `WouldBlock` is written again on the next call.
+/// Returns `Ok(())` once the whole packet has been written.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     let mut tx = flem::Packet::<16>::new();
+///     tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+///
+///     let mut wire = Vec::new();
+///     let mut ready = false;
+///     let mut write = |byte: u8| -> nb::Result<(), ()> {
+///         // The transmit register is only ready every other call
+///         ready = !ready;
+///         if !ready {
+///             return Err(nb::Error::WouldBlock);
+///         }
+///         wire.push(byte);
+///         Ok(())
+///     };
+///
+///     nb::block!(flem::nb::send(&mut write, &mut tx)).unwrap();
+///     assert_eq!(wire, tx.bytes());
+/// }
+/// ```
+pub fn send<E, const T: usize>(
+    serial: &mut impl FnMut(u8) -> ::nb::Result<(), E>,
+    packet: &mut Packet<T>,
+) -> ::nb::Result<(), FlemNbError<E>> {
+    loop {
+        let position = packet.internal_counter;
+        let byte = match packet.get_byte() {
+            Ok(byte) => byte,
+            Err(_) => return Ok(()),
+        };
+
+        if let Err(error) = serial(byte) {
+            // Write the same byte again next time
+            packet.internal_counter = position;
+            return match error {
+                ::nb::Error::WouldBlock => Err(::nb::Error::WouldBlock),
+                ::nb::Error::Other(error) => Err(::nb::Error::Other(FlemNbError::Serial(error))),
+            };
+        }
+    }
+}
+
+/// Reads bytes with `serial`, usually a closure around the driver's `read()`, into `packet`
+/// until it holds a complete, validated packet. Bytes before the header are skipped. Returns
+/// `Ok(())` when the packet is complete, the next call starts a new packet.
+///
+/// A packet error (`ChecksumError`, `InvalidDataLengthDetected`, ...) is returned as
+/// `FlemNbError::Flem` and the packet is reset for the next one.
+pub fn recv<E, const T: usize>(
+    serial: &mut impl FnMut() -> ::nb::Result<u8, E>,
+    packet: &mut Packet<T>,
+) -> ::nb::Result<(), FlemNbError<E>> {
+    if packet.is_complete() {
+        packet.reset_lazy();
+    }
+
+    loop {
+        let byte = serial().map_err(|error| error.map(FlemNbError::Serial))?;
+
+        match packet.construct(byte) {
+            Ok(_) => return Ok(()),
+            Err(Status::PacketBuilding)
+            | Err(Status::HeaderBytesNotFound)
+            | Err(Status::DiscardingOversized) => {}
+            Err(status) => {
+                packet.reset_lazy();
+                return Err(::nb::Error::Other(FlemNbError::Flem(status)));
+            }
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! Packets with a 32 bit checksum (CRC-32/ISO-HDLC) for large payloads on noisy links,
+//! where a 16 bit CRC misses too many errors.
+//!
+//! The checksum field is 4 bytes, so the header is `FLEM32_HEADER_SIZE` bytes and starts
+//! with `FLEM32_HEADER`. A `Packet` receiving a `Packet32` frame (or the other way around)
+//! fails on the second header byte with `Status::HeaderBytesNotFound`, both partners have
+//! to use `Packet32`.
+//!
+//! Wire format, little endian: header (2 bytes), checksum (4), request (2), response (2),
+//! length (2), with protocol-v2 sequence (2), flags (1) and channel (1), then the data. The checksum
+//! covers everything after the checksum bytes.
+
+use crate::frame::{Frame, Layout};
+use crate::{Status, FLEM_HEADER_SIZE};
+
+#[cfg(not(feature = "protocol-v2"))]
+pub const FLEM32_HEADER: u16 = 0x3355;
+#[cfg(feature = "protocol-v2")]
+pub const FLEM32_HEADER: u16 = 0xCC55;
+
+/// Header size of a `Packet32`, the FLEM header with 2 more checksum bytes
+pub const FLEM32_HEADER_SIZE: usize = FLEM_HEADER_SIZE + 2;
+
+#[derive(Debug, Clone, Copy)]
+struct Crc32Layout;
+
+impl Layout for Crc32Layout {
+    const HEADER: u16 = FLEM32_HEADER;
+    const CHECKSUM_SIZE: usize = 4;
+    const BIG_ENDIAN: bool = false;
+
+    fn checksum(fields: &[u8], payload: &[u8]) -> u32 {
+        crc32(&[fields, payload])
+    }
+}
+
+/// CRC-32/ISO-HDLC (the Ethernet and zip CRC) of `parts`, in order
+pub fn crc32(parts: &[&[u8]]) -> u32 {
+    let mut crc = u32::MAX;
+    for byte in parts.iter().flat_map(|part| part.iter()) {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// A FLEM packet with a 32 bit checksum, the API follows `Packet`.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use flem::packet32::{Packet32, FLEM32_HEADER_SIZE};
+///
+///     let mut tx = Packet32::<64>::new();
+///     tx.pack_data(0x10, &[0x55; 40]).unwrap();
+///
+///     let mut wire = [0u8; FLEM32_HEADER_SIZE + 64];
+///     let length = tx.write_wire(&mut wire).unwrap();
+///
+///     let mut rx = Packet32::<64>::new();
+///     assert_eq!(rx.construct_slice(&wire[..length]), Ok(length));
+///     assert_eq!(rx.payload(), &[0x55; 40]);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Packet32<const T: usize>(Frame<Crc32Layout, FLEM32_HEADER_SIZE, T>);
+
+impl<const T: usize> Packet32<T> {
+    pub fn new() -> Self {
+        Self(Frame::new())
+    }
+
+    /// Sets the request, debug builds panic on reserved codes as `Packet::set_request`
+    pub fn set_request(&mut self, request: u16) {
+        crate::debug_assert_request(request);
+        self.0.set_request(request);
+    }
+
+    pub fn get_request(&self) -> u16 {
+        self.0.get_request()
+    }
+
+    pub fn set_response(&mut self, response: u16) {
+        self.0.set_response(response);
+    }
+
+    pub fn get_response(&self) -> u16 {
+        self.0.get_response()
+    }
+
+    /// Gets the header field, `FLEM32_HEADER` once packed or received
+    pub fn get_header(&self) -> u16 {
+        self.0.get_header()
+    }
+
+    pub fn get_checksum(&self) -> u32 {
+        self.0.get_checksum()
+    }
+
+    /// Gets the length field, the number of payload bytes
+    pub fn get_length(&self) -> u16 {
+        self.0.get_length()
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    pub fn set_sequence(&mut self, sequence: u16) {
+        self.0.set_sequence(sequence);
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    pub fn get_sequence(&self) -> u16 {
+        self.0.get_sequence()
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    pub fn set_flags(&mut self, flags: u8) {
+        self.0.set_flags(flags);
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    pub fn get_flags(&self) -> u8 {
+        self.0.get_flags()
+    }
+
+    /// Sets the channel, see `Packet::set_channel`
+    #[cfg(feature = "protocol-v2")]
+    pub fn set_channel(&mut self, channel: u8) {
+        self.0.set_channel(channel);
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    pub fn get_channel(&self) -> u8 {
+        self.0.get_channel()
+    }
+
+    pub fn get_status(&self) -> Status {
+        self.0.get_status()
+    }
+
+    /// Appends `data` to the payload. Returns `Status::PacketOverflow` and adds nothing if
+    /// it doesn't fit.
+    pub fn add_data(&mut self, data: &[u8]) -> Result<(), Status> {
+        self.0.add_data(data)
+    }
+
+    /// Resets the packet, adds `data` and packs it with a response of SUCCESS. Any request
+    /// code is accepted as with `Packet::pack_data`.
+    pub fn pack_data(&mut self, request: u16, data: &[u8]) -> Result<(), Status> {
+        self.0.pack_data(request, data)
+    }
+
+    /// Sets the header and the 32 bit checksum, call it after all fields and data are set.
+    /// The transmit cursor of `get_byte()` starts over.
+    pub fn pack(&mut self) {
+        self.0.pack();
+    }
+
+    /// Computes the 32 bit checksum of the packet
+    pub fn checksum(&self) -> u32 {
+        self.0.checksum()
+    }
+
+    /// Returns true if the stored checksum matches the packet
+    pub fn validate(&self) -> bool {
+        self.0.validate()
+    }
+
+    /// The valid part of the data buffer, `length` bytes
+    pub fn payload(&self) -> &[u8] {
+        self.0.payload()
+    }
+
+    /// Length of the packet on the wire, including the header
+    pub fn length(&self) -> usize {
+        self.0.length()
+    }
+
+    /// Returns the next byte of the packed packet to send, or `Status::GetByteFinished`
+    /// once all bytes have been sent, see `Packet::get_byte`
+    pub fn get_byte(&mut self) -> Result<u8, Status> {
+        self.0.get_byte()
+    }
+
+    /// Writes the frame into `out`. Returns the number of bytes written, or
+    /// `Status::BufferTooSmall` if `out` is shorter than `length()`.
+    pub fn write_wire(&self, out: &mut [u8]) -> Result<usize, Status> {
+        self.0.write_wire(out)
+    }
+
+    /// Receives a packet a byte at a time, see `Packet::construct`. Returns `Ok(())` once
+    /// the packet is complete and its checksum is valid, otherwise:
+    /// - PacketBuilding - The packet isn't complete yet
+    /// - HeaderBytesNotFound - The byte isn't part of a `FLEM32_HEADER`, for example a
+    ///   frame of a 16 bit `Packet`
+    /// - InvalidDataLengthDetected - The length field is larger than T
+    /// - ChecksumError - The computed checksum does not match the sent checksum
+    ///
+    /// After an error or a complete packet, the next byte is looked at as the first
+    /// header byte. A second header byte that doesn't match is looked at again as the first.
+    pub fn construct(&mut self, byte: u8) -> Result<(), Status> {
+        self.0.construct(byte)
+    }
+
+    /// Feeds `bytes` to `construct()`, see `Packet::construct_slice`
+    pub fn construct_slice(&mut self, bytes: &[u8]) -> Result<usize, Status> {
+        self.0.construct_slice(bytes)
+    }
+
+    /// Clears the header fields and the transmit / receive state, the data buffer isn't
+    /// cleared
+    pub fn reset(&mut self) {
+        self.0.reset();
+    }
+}
+
+impl<const T: usize> Default for Packet32<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
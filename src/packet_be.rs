@@ -0,0 +1,232 @@
+//! Packets with big endian 16 bit fields, for partners whose framing is big endian.
+//!
+//! The layout, checksum (CRC-16/IBM) and payload are those of `Packet`, only the byte order
+//! of the header, checksum, request, response, length (and with protocol-v2 sequence)
+//! fields is swapped. The header value is `FLEM_HEADER` as for `Packet`, see
+//! `PacketBe::set_sync_word`.
+//!
+//! Both partners have to use `PacketBe`. A `Packet` receiving a `PacketBe` frame (or the
+//! other way around) rejects it in most cases:
+//! - With protocol-v2 the header bytes differ, `Status::HeaderBytesNotFound`. The protocol
+//!   v1 header 0x5555 reads the same in both byte orders.
+//! - The length field is read swapped, `Status::InvalidDataLengthDetected` if that is
+//!   larger than T.
+//! - Both compute the checksum over the same wire bytes, but read the stored checksum
+//!   swapped, `Status::ChecksumError`.
+//!
+//! The checksum doesn't catch a frame whose two checksum bytes are equal, 1 in 256 frames,
+//! if its length also reads the same both ways, for example an empty payload. Such a frame
+//! is received with swapped request and response fields.
+
+use crate::frame::{Frame, Layout};
+use crate::{Status, FLEM_HEADER, FLEM_HEADER_SIZE};
+
+#[derive(Debug, Clone, Copy)]
+struct BigEndianLayout;
+
+impl Layout for BigEndianLayout {
+    const HEADER: u16 = FLEM_HEADER;
+    const CHECKSUM_SIZE: usize = 2;
+    const BIG_ENDIAN: bool = true;
+
+    fn checksum(fields: &[u8], payload: &[u8]) -> u32 {
+        let crc = crate::crc16_update(0, fields.iter());
+        crate::crc16_update(crc, payload.iter()) as u32
+    }
+}
+
+/// A FLEM packet with big endian fields, the API follows `Packet`.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use flem::packet_be::PacketBe;
+///
+///     const FLEM_EXAMPLE_REQUEST: u16 = 0x10;
+///
+///     let mut tx = PacketBe::<64>::new();
+///     tx.pack_data(FLEM_EXAMPLE_REQUEST, &[1, 2, 3]).unwrap();
+///     assert_eq!(tx.get_header(), flem::FLEM_HEADER);
+///
+///     // We are sending bytes across a hardware bus
+///     let mut rx = PacketBe::<64>::new();
+///     let mut received = false;
+///     while let Ok(byte) = tx.get_byte() {
+///         if rx.construct(byte).is_ok() {
+///             received = true;
+///         }
+///     }
+///
+///     assert!(received, "Packet should have been constructed and validated.");
+///     assert_eq!(rx.get_request(), FLEM_EXAMPLE_REQUEST);
+///     assert_eq!(rx.payload(), &[1, 2, 3]);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct PacketBe<const T: usize>(Frame<BigEndianLayout, FLEM_HEADER_SIZE, T>);
+
+impl<const T: usize> PacketBe<T> {
+    pub fn new() -> Self {
+        Self(Frame::new())
+    }
+
+    /// Sets the request, debug builds panic on reserved codes as `Packet::set_request`
+    pub fn set_request(&mut self, request: u16) {
+        crate::debug_assert_request(request);
+        self.0.set_request(request);
+    }
+
+    pub fn get_request(&self) -> u16 {
+        self.0.get_request()
+    }
+
+    pub fn set_response(&mut self, response: u16) {
+        self.0.set_response(response);
+    }
+
+    pub fn get_response(&self) -> u16 {
+        self.0.get_response()
+    }
+
+    /// Gets the header field, the sync word once packed or received
+    pub fn get_header(&self) -> u16 {
+        self.0.get_header()
+    }
+
+    /// Sets the header value `pack()` writes and `construct()` hunts for, `FLEM_HEADER` by
+    /// default, see `Packet::set_sync_word`. It is kept by `reset()`.
+    pub fn set_sync_word(&mut self, sync_word: u16) {
+        self.0.set_sync_word(sync_word);
+    }
+
+    /// Gets the sync word, see `set_sync_word`
+    pub fn get_sync_word(&self) -> u16 {
+        self.0.get_sync_word()
+    }
+
+    pub fn get_checksum(&self) -> u16 {
+        self.0.get_checksum() as u16
+    }
+
+    /// Gets the length field, the number of payload bytes
+    pub fn get_length(&self) -> u16 {
+        self.0.get_length()
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    pub fn set_sequence(&mut self, sequence: u16) {
+        self.0.set_sequence(sequence);
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    pub fn get_sequence(&self) -> u16 {
+        self.0.get_sequence()
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    pub fn set_flags(&mut self, flags: u8) {
+        self.0.set_flags(flags);
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    pub fn get_flags(&self) -> u8 {
+        self.0.get_flags()
+    }
+
+    /// Sets the channel, see `Packet::set_channel`
+    #[cfg(feature = "protocol-v2")]
+    pub fn set_channel(&mut self, channel: u8) {
+        self.0.set_channel(channel);
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    pub fn get_channel(&self) -> u8 {
+        self.0.get_channel()
+    }
+
+    pub fn get_status(&self) -> Status {
+        self.0.get_status()
+    }
+
+    /// Appends `data` to the payload. Returns `Status::PacketOverflow` and adds nothing if
+    /// it doesn't fit.
+    pub fn add_data(&mut self, data: &[u8]) -> Result<(), Status> {
+        self.0.add_data(data)
+    }
+
+    /// Resets the packet, adds `data` and packs it with a response of SUCCESS. Any request
+    /// code is accepted as with `Packet::pack_data`.
+    pub fn pack_data(&mut self, request: u16, data: &[u8]) -> Result<(), Status> {
+        self.0.pack_data(request, data)
+    }
+
+    /// Sets the header and the checksum, call it after all fields and data are set. The
+    /// transmit cursor of `get_byte()` starts over.
+    pub fn pack(&mut self) {
+        self.0.pack();
+    }
+
+    /// Computes the checksum of the packet, CRC-16/IBM over the bytes after the checksum
+    /// field as they are on the wire
+    pub fn checksum(&self) -> u16 {
+        self.0.checksum() as u16
+    }
+
+    /// Returns true if the stored checksum matches the packet
+    pub fn validate(&self) -> bool {
+        self.0.validate()
+    }
+
+    /// The valid part of the data buffer, `length` bytes
+    pub fn payload(&self) -> &[u8] {
+        self.0.payload()
+    }
+
+    /// Length of the packet on the wire, including the header
+    pub fn length(&self) -> usize {
+        self.0.length()
+    }
+
+    /// Returns the next byte of the packed packet to send, or `Status::GetByteFinished`
+    /// once all bytes have been sent, see `Packet::get_byte`
+    pub fn get_byte(&mut self) -> Result<u8, Status> {
+        self.0.get_byte()
+    }
+
+    /// Writes the frame into `out`. Returns the number of bytes written, or
+    /// `Status::BufferTooSmall` if `out` is shorter than `length()`.
+    pub fn write_wire(&self, out: &mut [u8]) -> Result<usize, Status> {
+        self.0.write_wire(out)
+    }
+
+    /// Receives a packet a byte at a time, see `Packet::construct`. Returns `Ok(())` once
+    /// the packet is complete and its checksum is valid, otherwise:
+    /// - PacketBuilding - The packet isn't complete yet
+    /// - HeaderBytesNotFound - The byte isn't part of the sync word
+    /// - InvalidDataLengthDetected - The length field is larger than T
+    /// - ChecksumError - The computed checksum does not match the sent checksum, also the
+    ///   usual result for a frame of a little endian `Packet`, see the module docs
+    ///
+    /// After an error or a complete packet, the next byte is looked at as the first
+    /// header byte. A second header byte that doesn't match is looked at again as the first.
+    pub fn construct(&mut self, byte: u8) -> Result<(), Status> {
+        self.0.construct(byte)
+    }
+
+    /// Feeds `bytes` to `construct()`, see `Packet::construct_slice`
+    pub fn construct_slice(&mut self, bytes: &[u8]) -> Result<usize, Status> {
+        self.0.construct_slice(bytes)
+    }
+
+    /// Clears the header fields and the transmit / receive state, the data buffer isn't
+    /// cleared
+    pub fn reset(&mut self) {
+        self.0.reset();
+    }
+}
+
+impl<const T: usize> Default for PacketBe<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,107 @@
+//! Rebuilds payloads that were split across several packets. Requires
+//! features = ["std", "protocol-v2"].
+//!
+//! The sender splits the payload into packets with consecutive sequence numbers and
+//! sets `flags::MORE_FRAGMENTS` on every packet except the last one. The receiver
+//! pushes every validated packet into a `Reassembler`, which returns the payload once
+//! the last packet arrives.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use crate::{Packet, Status, FLEM_HEADER_SIZE};
+
+/// Collects the payloads of consecutive packets until a packet without
+/// `flags::MORE_FRAGMENTS` is received.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use flem::{reassembly::Reassembler, Packet};
+///
+///     let payload = [0xA5_u8; 40];
+///     let mut reassembler = Reassembler::<16>::new();
+///     let mut received = None;
+///
+///     for (sequence, chunk) in payload.chunks(16).enumerate() {
+///         let mut packet = Packet::<16>::new();
+///         packet.set_sequence(sequence as u16);
+///         packet.set_more_fragments((sequence + 1) * 16 < payload.len());
+///         packet.pack_data(0x10, chunk).unwrap();
+///
+///         received = reassembler.push(&packet).unwrap();
+///     }
+///
+///     assert_eq!(received.unwrap(), payload);
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Reassembler<const T: usize> {
+    payload: Vec<u8>,
+    next_sequence: Option<u16>,
+    discarding: bool,
+}
+
+impl<const T: usize> Reassembler<T> {
+    pub fn new() -> Self {
+        Self {
+            payload: Vec::new(),
+            next_sequence: None,
+            discarding: false,
+        }
+    }
+
+    /// Adds the payload of a validated packet. Returns the complete payload when the
+    /// packet is the last one (`has_more_fragments()` is false), otherwise `Ok(None)`.
+    ///
+    /// If the sequence number doesn't follow the previous packet, the partial payload
+    /// is dropped and `Status::SequenceGap` is returned. The remaining packets of the
+    /// broken payload are ignored, the next payload is collected as normal.
+    pub fn push(&mut self, packet: &Packet<T>) -> Result<Option<Vec<u8>>, Status> {
+        let sequence = packet.get_sequence();
+
+        if self.discarding {
+            if !packet.has_more_fragments() {
+                self.discarding = false;
+            }
+            return Ok(None);
+        }
+
+        if let Some(expected) = self.next_sequence {
+            if sequence != expected {
+                self.reset();
+                self.discarding = packet.has_more_fragments();
+                return Err(Status::SequenceGap);
+            }
+        }
+
+        self.payload
+            .extend_from_slice(&packet.bytes()[FLEM_HEADER_SIZE..]);
+
+        if packet.has_more_fragments() {
+            self.next_sequence = Some(sequence.wrapping_add(1));
+            Ok(None)
+        } else {
+            self.next_sequence = None;
+            Ok(Some(core::mem::take(&mut self.payload)))
+        }
+    }
+
+    /// Number of payload bytes collected for the payload in progress
+    pub fn len(&self) -> usize {
+        self.payload.len()
+    }
+
+    /// Returns true if no payload is in progress
+    pub fn is_empty(&self) -> bool {
+        self.payload.is_empty()
+    }
+
+    /// Drops the payload in progress
+    pub fn reset(&mut self) {
+        self.payload.clear();
+        self.next_sequence = None;
+        self.discarding = false;
+    }
+}
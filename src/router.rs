@@ -0,0 +1,110 @@
+//! Forwards packets between buses by request code, for bridges and gateways.
+//!
+//! A forwarded packet is sent byte for byte as it was received, its checksum still holds so
+//! nothing is packed again.
+
+use core::ops::RangeInclusive;
+
+use crate::dispatch::{unknown_request, Handler};
+use crate::host::ByteChannel;
+use crate::{Packet, Status};
+
+/// Maps up to `N` request ranges to output channels. Requests that match no rule go to the
+/// default handler, which by default responds with `response::UNKNOWN_REQUEST`, so the
+/// bridge answers them itself.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use flem::router::Router;
+///     use flem::Packet;
+///
+///     let mut router = Router::<16, 4>::new();
+///     router.add_rule(0x10..=0x1F, 0).unwrap();
+///     router.add_rule(0x20..=0x2F, 1).unwrap();
+///
+///     let rx = Packet::<16>::with_payload(0x24, &[1]).unwrap();
+///     assert_eq!(router.route(&rx), Some(1));
+///
+///     let rx = Packet::<16>::with_payload(0x30, &[1]).unwrap();
+///     assert_eq!(router.route(&rx), None);
+/// }
+/// ```
+#[derive(Clone, Copy)]
+pub struct Router<const T: usize, const N: usize> {
+    rules: [Option<(u16, u16, usize)>; N],
+    default: Handler<T>,
+}
+
+impl<const T: usize, const N: usize> Router<T, N> {
+    pub const fn new() -> Self {
+        Self {
+            rules: [None; N],
+            default: unknown_request::<T>,
+        }
+    }
+
+    /// Sends requests in `requests` to output `output`. Rules are matched in the order they
+    /// were added, the first match wins. Returns `Status::PacketOverflow` if all N rules are
+    /// taken.
+    pub fn add_rule(&mut self, requests: RangeInclusive<u16>, output: usize) -> Result<(), Status> {
+        match self.rules.iter_mut().find(|rule| rule.is_none()) {
+            Some(rule) => {
+                *rule = Some((*requests.start(), *requests.end(), output));
+                Ok(())
+            }
+            None => Err(Status::PacketOverflow),
+        }
+    }
+
+    /// Removes all rules
+    pub fn clear(&mut self) {
+        self.rules = [None; N];
+    }
+
+    /// Replaces the handler for requests that match no rule
+    pub fn set_default(&mut self, handler: Handler<T>) {
+        self.default = handler;
+    }
+
+    /// The output of the first rule matching the request of `packet`, None if no rule matches
+    pub fn route(&self, packet: &Packet<T>) -> Option<usize> {
+        let request = packet.get_request();
+        self.rules
+            .iter()
+            .flatten()
+            .find(|(start, end, _)| (*start..=*end).contains(&request))
+            .map(|(_, _, output)| *output)
+    }
+
+    /// Sends `rx` unchanged to the output it is routed to and returns the output. If no rule
+    /// matches, the default handler packs the answer into `tx` and None is returned, send
+    /// `tx` back on the incoming bus.
+    ///
+    /// Returns the errors of the output channel or the default handler, or
+    /// `Status::UnspecifiedError` if a rule names an output that isn't in `outputs`.
+    pub fn forward(
+        &self,
+        rx: &Packet<T>,
+        tx: &mut Packet<T>,
+        outputs: &mut [&mut dyn ByteChannel],
+    ) -> Result<Option<usize>, Status> {
+        match self.route(rx) {
+            Some(output) => {
+                let channel = outputs.get_mut(output).ok_or(Status::UnspecifiedError)?;
+                channel.write(rx.bytes())?;
+                Ok(Some(output))
+            }
+            None => {
+                (self.default)(rx, tx)?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl<const T: usize, const N: usize> Default for Router<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
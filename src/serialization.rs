@@ -0,0 +1,345 @@
+//! Serde support for `DataId` and `Packet`. Enabled with features = ["serde"].
+//!
+//! `Packet` is `#[repr(C, packed)]` so the fields can't be handed to a derive
+//! directly. Only the wire fields are serialized (header, checksum, request,
+//! response, length, the protocol-v2 sequence, flags and channel, and the valid
+//! part of the data buffer), the internal counters and status are left out. A deserialized
+//! packet starts out as `Packet::new()` with only the wire fields filled in.
+
+use core::fmt::{self, Display, Formatter};
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{DataId, Packet, FLEM_HEADER_SIZE};
+
+#[cfg(not(feature = "protocol-v2"))]
+const PACKET_FIELDS: &[&str] = &[
+    "header", "checksum", "request", "response", "length", "data",
+];
+#[cfg(feature = "protocol-v2")]
+const PACKET_FIELDS: &[&str] = &[
+    "header", "checksum", "request", "response", "length", "sequence", "flags", "channel", "data",
+];
+const DATA_ID_FIELDS: &[&str] = &["major", "minor", "patch", "max_packet_size", "name"];
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "snake_case")]
+enum PacketField {
+    Header,
+    Checksum,
+    Request,
+    Response,
+    Length,
+    #[cfg(feature = "protocol-v2")]
+    Sequence,
+    #[cfg(feature = "protocol-v2")]
+    Flags,
+    #[cfg(feature = "protocol-v2")]
+    Channel,
+    Data,
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "snake_case")]
+enum DataIdField {
+    Major,
+    Minor,
+    Patch,
+    MaxPacketSize,
+    Name,
+}
+
+/// Writes the name of a `DataId` up to the first NUL character
+struct IdName<'a>(&'a [char]);
+
+impl Display for IdName<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for c in self.0.iter().take_while(|c| **c != '\0') {
+            fmt::Write::write_char(f, *c)?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for IdName<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<const N: usize> Serialize for DataId<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("DataId", DATA_ID_FIELDS.len())?;
+        state.serialize_field("major", &self.major)?;
+        state.serialize_field("minor", &self.minor)?;
+        state.serialize_field("patch", &self.patch)?;
+        state.serialize_field("max_packet_size", &self.get_max_packet_size())?;
+        state.serialize_field("name", &IdName(&self.name))?;
+        state.end()
+    }
+}
+
+/// Reads a name string into the fixed size `DataId` name buffer
+struct IdNameSeed<const N: usize>;
+
+impl<'de, const N: usize> Visitor<'de> for IdNameSeed<N> {
+    type Value = [char; N];
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "a string of at most {} characters", N)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        let mut name = ['\0'; N];
+        for (index, c) in v.chars().enumerate() {
+            if index >= N {
+                return Err(E::invalid_length(v.chars().count(), &self));
+            }
+            name[index] = c;
+        }
+        Ok(name)
+    }
+}
+
+impl<'de, const N: usize> DeserializeSeed<'de> for IdNameSeed<N> {
+    type Value = [char; N];
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_str(self)
+    }
+}
+
+struct DataIdVisitor<const N: usize>;
+
+impl<'de, const N: usize> Visitor<'de> for DataIdVisitor<N> {
+    type Value = DataId<N>;
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str("struct DataId")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<DataId<N>, A::Error> {
+        let mut major = None;
+        let mut minor = None;
+        let mut patch = None;
+        let mut max_packet_size = None;
+        let mut name = None;
+
+        while let Some(key) = map.next_key()? {
+            match key {
+                DataIdField::Major => major = Some(map.next_value()?),
+                DataIdField::Minor => minor = Some(map.next_value()?),
+                DataIdField::Patch => patch = Some(map.next_value()?),
+                DataIdField::MaxPacketSize => max_packet_size = Some(map.next_value()?),
+                DataIdField::Name => name = Some(map.next_value_seed(IdNameSeed::<N>)?),
+            }
+        }
+
+        Ok(DataId {
+            major: major.ok_or_else(|| de::Error::missing_field("major"))?,
+            minor: minor.ok_or_else(|| de::Error::missing_field("minor"))?,
+            patch: patch.ok_or_else(|| de::Error::missing_field("patch"))?,
+            max_packet_size: max_packet_size
+                .ok_or_else(|| de::Error::missing_field("max_packet_size"))?,
+            name: name.ok_or_else(|| de::Error::missing_field("name"))?,
+        })
+    }
+
+    /// Non-self-describing formats (postcard, bincode) hand the fields over in order
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<DataId<N>, A::Error> {
+        let length = |index| de::Error::invalid_length(index, &"struct DataId with 5 fields");
+        Ok(DataId {
+            major: seq.next_element()?.ok_or_else(|| length(0))?,
+            minor: seq.next_element()?.ok_or_else(|| length(1))?,
+            patch: seq.next_element()?.ok_or_else(|| length(2))?,
+            max_packet_size: seq.next_element()?.ok_or_else(|| length(3))?,
+            name: seq
+                .next_element_seed(IdNameSeed::<N>)?
+                .ok_or_else(|| length(4))?,
+        })
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for DataId<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("DataId", DATA_ID_FIELDS, DataIdVisitor::<N>)
+    }
+}
+
+impl<const T: usize> Serialize for Packet<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Packet", PACKET_FIELDS.len())?;
+        state.serialize_field("header", &self.get_header())?;
+        state.serialize_field("checksum", &self.get_checksum())?;
+        state.serialize_field("request", &self.get_request())?;
+        state.serialize_field("response", &self.get_response())?;
+        state.serialize_field("length", &self.get_length())?;
+        #[cfg(feature = "protocol-v2")]
+        {
+            state.serialize_field("sequence", &self.get_sequence())?;
+            state.serialize_field("flags", &self.get_flags())?;
+            state.serialize_field("channel", &self.get_channel())?;
+        }
+        state.serialize_field("data", &self.bytes()[FLEM_HEADER_SIZE..])?;
+        state.end()
+    }
+}
+
+/// Reads a sequence of bytes into a `[u8; T]` buffer, returning the buffer and the number
+/// of bytes read.
+struct PayloadSeed<const T: usize>;
+
+impl<'de, const T: usize> Visitor<'de> for PayloadSeed<T> {
+    type Value = ([u8; T], usize);
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "a sequence of at most {} bytes", T)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut data = [0u8; T];
+        let mut count = 0;
+        while let Some(byte) = seq.next_element::<u8>()? {
+            if count >= T {
+                return Err(de::Error::invalid_length(count + 1, &self));
+            }
+            data[count] = byte;
+            count += 1;
+        }
+        Ok((data, count))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        if v.len() > T {
+            return Err(E::invalid_length(v.len(), &self));
+        }
+        let mut data = [0u8; T];
+        data[..v.len()].copy_from_slice(v);
+        Ok((data, v.len()))
+    }
+}
+
+impl<'de, const T: usize> DeserializeSeed<'de> for PayloadSeed<T> {
+    type Value = ([u8; T], usize);
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+struct PacketVisitor<const T: usize> {
+    marker: PhantomData<Packet<T>>,
+}
+
+impl<'de, const T: usize> Visitor<'de> for PacketVisitor<T> {
+    type Value = Packet<T>;
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str("struct Packet")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Packet<T>, A::Error> {
+        let mut header = None;
+        let mut checksum = None;
+        let mut request = None;
+        let mut response = None;
+        let mut length = None;
+        #[cfg(feature = "protocol-v2")]
+        let mut sequence = None;
+        #[cfg(feature = "protocol-v2")]
+        let mut flags = None;
+        #[cfg(feature = "protocol-v2")]
+        let mut channel = None;
+        let mut data = None;
+
+        while let Some(key) = map.next_key()? {
+            match key {
+                PacketField::Header => header = Some(map.next_value()?),
+                PacketField::Checksum => checksum = Some(map.next_value()?),
+                PacketField::Request => request = Some(map.next_value()?),
+                PacketField::Response => response = Some(map.next_value()?),
+                PacketField::Length => length = Some(map.next_value()?),
+                #[cfg(feature = "protocol-v2")]
+                PacketField::Sequence => sequence = Some(map.next_value()?),
+                #[cfg(feature = "protocol-v2")]
+                PacketField::Flags => flags = Some(map.next_value()?),
+                #[cfg(feature = "protocol-v2")]
+                PacketField::Channel => channel = Some(map.next_value()?),
+                PacketField::Data => data = Some(map.next_value_seed(PayloadSeed::<T>)?),
+            }
+        }
+
+        let mut packet = Packet::<T>::new();
+        packet.header = header.ok_or_else(|| de::Error::missing_field("header"))?;
+        packet.checksum = checksum.ok_or_else(|| de::Error::missing_field("checksum"))?;
+        packet.request = request.ok_or_else(|| de::Error::missing_field("request"))?;
+        packet.response = response.ok_or_else(|| de::Error::missing_field("response"))?;
+        packet.length = length.ok_or_else(|| de::Error::missing_field("length"))?;
+        #[cfg(feature = "protocol-v2")]
+        {
+            packet.sequence = sequence.ok_or_else(|| de::Error::missing_field("sequence"))?;
+            packet.flags = flags.ok_or_else(|| de::Error::missing_field("flags"))?;
+            packet.channel = channel.ok_or_else(|| de::Error::missing_field("channel"))?;
+        }
+        let data = data.ok_or_else(|| de::Error::missing_field("data"))?;
+        set_data(packet, data)
+    }
+
+    /// Non-self-describing formats (postcard, bincode) hand the fields over in order
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Packet<T>, A::Error> {
+        let mut packet = Packet::<T>::new();
+        packet.header = next_field(&mut seq, 0)?;
+        packet.checksum = next_field(&mut seq, 1)?;
+        packet.request = next_field(&mut seq, 2)?;
+        packet.response = next_field(&mut seq, 3)?;
+        packet.length = next_field(&mut seq, 4)?;
+        #[cfg(feature = "protocol-v2")]
+        {
+            packet.sequence = next_field(&mut seq, 5)?;
+            packet.flags = next_field(&mut seq, 6)?;
+            packet.channel = next_field(&mut seq, 7)?;
+        }
+        let data = seq
+            .next_element_seed(PayloadSeed::<T>)?
+            .ok_or_else(|| de::Error::invalid_length(PACKET_FIELDS.len() - 1, &"struct Packet"))?;
+        set_data(packet, data)
+    }
+}
+
+/// Reads field `index` of a `Packet` from a sequence
+fn next_field<'de, A: SeqAccess<'de>, V: Deserialize<'de>>(
+    seq: &mut A,
+    index: usize,
+) -> Result<V, A::Error> {
+    seq.next_element()?
+        .ok_or_else(|| de::Error::invalid_length(index, &"struct Packet"))
+}
+
+/// Copies the deserialized data into `packet`, the number of bytes must match the length
+/// field
+fn set_data<E: de::Error, const T: usize>(
+    mut packet: Packet<T>,
+    (data, count): ([u8; T], usize),
+) -> Result<Packet<T>, E> {
+    if packet.get_length() as usize != count {
+        return Err(E::invalid_length(count, &"data matching the length field"));
+    }
+    packet.data = data.map(MaybeUninit::new);
+    Ok(packet)
+}
+
+impl<'de, const T: usize> Deserialize<'de> for Packet<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct(
+            "Packet",
+            PACKET_FIELDS,
+            PacketVisitor {
+                marker: PhantomData,
+            },
+        )
+    }
+}
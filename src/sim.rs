@@ -0,0 +1,137 @@
+//! Loopback link with fault injection, to test application protocols and their error
+//! handling on a PC without hardware. Enabled with features = ["sim"].
+
+extern crate std;
+
+use std::vec::Vec;
+
+use crate::{Packet, Status};
+
+/// Connects a packed TX packet to an RX packet, byte by byte, like a hardware bus would.
+/// Faults can be injected into the bytes on the way. The faults stay configured for every
+/// `run()` until `clear_faults()` is called.
+///
+/// The RX packet is used as is, as on real hardware take the received packet out with
+/// `take()` (or call `reset_lazy()`) before receiving the next one.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use flem::sim::LoopbackLink;
+///     use flem::{Packet, Status};
+///
+///     let mut tx = Packet::<16>::new();
+///     tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+///     let mut rx = Packet::<16>::new();
+///
+///     let mut link = LoopbackLink::new(&tx, &mut rx).corrupt_byte_at(flem::FLEM_HEADER_SIZE);
+///     assert_eq!(link.run().last(), Some(&Status::ChecksumError));
+///
+///     // Retry without the fault
+///     link.clear_faults();
+///     assert_eq!(link.run().last(), Some(&Status::PacketReceived));
+///     assert_eq!(rx, tx);
+/// }
+/// ```
+pub struct LoopbackLink<'a, const T: usize> {
+    tx: &'a Packet<T>,
+    rx: &'a mut Packet<T>,
+    drop_every: Option<usize>,
+    corrupt_at: Option<usize>,
+    garbage_before: Vec<u8>,
+    garbage_after: Vec<u8>,
+}
+
+impl<'a, const T: usize> LoopbackLink<'a, T> {
+    pub fn new(tx: &'a Packet<T>, rx: &'a mut Packet<T>) -> Self {
+        Self {
+            tx,
+            rx,
+            drop_every: None,
+            corrupt_at: None,
+            garbage_before: Vec::new(),
+            garbage_after: Vec::new(),
+        }
+    }
+
+    /// Drops every `n`th byte of the frame (the `n`th, `2n`th, ...). 0 drops nothing.
+    pub fn drop_every_nth(mut self, n: usize) -> Self {
+        self.drop_every = if n == 0 { None } else { Some(n) };
+        self
+    }
+
+    /// Inverts the bits of the frame byte at `index`. Ignored if the frame is shorter.
+    pub fn corrupt_byte_at(mut self, index: usize) -> Self {
+        self.corrupt_at = Some(index);
+        self
+    }
+
+    /// Sends `garbage` before the frame, appended to earlier garbage
+    pub fn inject_garbage(mut self, garbage: &[u8]) -> Self {
+        self.garbage_before.extend_from_slice(garbage);
+        self
+    }
+
+    /// Sends `garbage` after the frame, appended to earlier garbage. The receiver only sees
+    /// it if the frame didn't complete or fail.
+    pub fn inject_garbage_after(mut self, garbage: &[u8]) -> Self {
+        self.garbage_after.extend_from_slice(garbage);
+        self
+    }
+
+    /// Removes all faults, the next `run()` sends the frame as is
+    pub fn clear_faults(&mut self) {
+        self.drop_every = None;
+        self.corrupt_at = None;
+        self.garbage_before.clear();
+        self.garbage_after.clear();
+    }
+
+    /// The bytes the receiver gets from the next `run()`, with the faults applied
+    pub fn wire_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.garbage_before.clone();
+        for (index, byte) in self.tx.bytes().iter().enumerate() {
+            if let Some(n) = self.drop_every {
+                if (index + 1) % n == 0 {
+                    continue;
+                }
+            }
+            if self.corrupt_at == Some(index) {
+                bytes.push(!*byte);
+            } else {
+                bytes.push(*byte);
+            }
+        }
+        bytes.extend_from_slice(&self.garbage_after);
+        bytes
+    }
+
+    /// Sends the frame to the RX packet with `construct()`, until the packet is received,
+    /// fails (`ChecksumError`, `PacketOverflow` or `InvalidDataLengthDetected`) or the
+    /// bytes run out. Returns the status of every byte, `PacketReceived` for a complete
+    /// packet.
+    pub fn run(&mut self) -> Vec<Status> {
+        let mut statuses = Vec::new();
+        for byte in self.wire_bytes() {
+            let status = match self.rx.construct(byte) {
+                Ok(_) => Status::PacketReceived,
+                Err(status) => status,
+            };
+            statuses.push(status);
+
+            match status {
+                Status::PacketReceived
+                | Status::ChecksumError
+                | Status::PacketOverflow
+                | Status::InvalidDataLengthDetected => break,
+                _ => {}
+            }
+        }
+        statuses
+    }
+
+    /// The RX packet
+    pub fn rx(&self) -> &Packet<T> {
+        self.rx
+    }
+}
@@ -0,0 +1,111 @@
+//! Sends and receives packets over `std::io` streams, for example a serial port or a
+//! TCP socket on the host. Requires features = ["std"].
+
+extern crate std;
+
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, ErrorKind, Read, Write};
+
+use crate::{Packet, Status};
+
+/// Error returned by `read_packet`
+#[derive(Debug)]
+pub enum ReadPacketError {
+    /// Reading from the stream failed, `UnexpectedEof` if the stream ended mid packet
+    Io(io::Error),
+    /// The bytes read didn't form a valid packet
+    Flem(Status),
+}
+
+impl Display for ReadPacketError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadPacketError::Io(error) => write!(f, "IO error while reading a packet: {}", error),
+            ReadPacketError::Flem(status) => write!(f, "Invalid packet: {:?}", status),
+        }
+    }
+}
+
+impl std::error::Error for ReadPacketError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReadPacketError::Io(error) => Some(error),
+            ReadPacketError::Flem(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for ReadPacketError {
+    fn from(error: io::Error) -> Self {
+        ReadPacketError::Io(error)
+    }
+}
+
+impl From<Status> for ReadPacketError {
+    fn from(status: Status) -> Self {
+        ReadPacketError::Flem(status)
+    }
+}
+
+/// Writes the bytes of a packed packet to `writer`
+pub fn write_packet<W: Write, const T: usize>(
+    writer: &mut W,
+    packet: &Packet<T>,
+) -> io::Result<()> {
+    writer.write_all(packet.bytes())?;
+    writer.flush()
+}
+
+/// Reads bytes from `reader` until `packet` holds a complete, validated packet. Bytes before
+/// the header are skipped, so the stream can start with garbage or a partial packet.
+///
+/// The stream is read a byte at a time so no bytes of the next packet are consumed. Wrap
+/// unbuffered readers (serial ports, sockets) in a `std::io::BufReader`.
+///
+/// Returns `ReadPacketError::Flem` for a packet that failed the checksum or was too long
+/// for `packet`. The next call continues with the bytes after it.
+///
+/// # Example
+/// ```
+/// pub fn main() {
+///     use flem::{stdio, Packet};
+///     use std::io::Cursor;
+///
+///     let mut tx = Packet::<32>::new();
+///     tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+///
+///     let mut stream = Vec::new();
+///     stdio::write_packet(&mut stream, &tx).unwrap();
+///
+///     let mut rx = Packet::<32>::new();
+///     stdio::read_packet(&mut Cursor::new(stream), &mut rx).unwrap();
+///     assert_eq!(rx, tx);
+/// }
+/// ```
+pub fn read_packet<R: Read, const T: usize>(
+    reader: &mut R,
+    packet: &mut Packet<T>,
+) -> Result<(), ReadPacketError> {
+    packet.reset_lazy();
+
+    let mut byte = [0u8; 1];
+    loop {
+        match reader.read(&mut byte) {
+            Ok(0) => return Err(io::Error::from(ErrorKind::UnexpectedEof).into()),
+            Ok(_) => {}
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error.into()),
+        }
+
+        match packet.construct(byte[0]) {
+            Ok(_) => return Ok(()),
+            Err(Status::PacketBuilding)
+            | Err(Status::HeaderBytesNotFound)
+            | Err(Status::DiscardingOversized) => {}
+            Err(status) => {
+                packet.reset_lazy();
+                return Err(status.into());
+            }
+        }
+    }
+}
@@ -28,6 +28,7 @@ pub trait Channel<const T: usize>: Sized {
     type Error;
 
     fn list_devices(&self) -> Vec<String>;
+    #[allow(clippy::ptr_arg)]
     fn connect(&mut self, device: &String) -> Result<(), Self::Error>;
     fn disconnect(&mut self) -> Result<(), Self::Error>;
     fn listen(&mut self, rx_sleep_time_ms: u64, tx_sleep_time_ms: u64,) -> (Sender<Packet<T>>, Receiver<Packet<T>>);
@@ -5,19 +5,66 @@ mod tests {
         f32_to_le_buffer, i16_to_le_buffer, i32_to_le_buffer, le_buffer_to_f32, le_buffer_to_i16,
         le_buffer_to_i32, le_buffer_to_u16, le_buffer_to_u32, u16_to_le_buffer, u32_to_le_buffer,
     };
+    use std::collections::VecDeque;
 
     const FLEM_PACKET_SIZE: usize = 108;
 
+    /// Runs a client engine whenever the host waits for a byte. With `corrupt` set, the
+    /// first payload byte of the next response is flipped.
+    struct Loopback<const N: usize> {
+        engine: flem::client::Engine<FLEM_PACKET_SIZE, N>,
+        to_client: VecDeque<u8>,
+        to_host: VecDeque<u8>,
+        corrupt: bool,
+    }
+
+    impl<const N: usize> Loopback<N> {
+        fn new(dispatcher: flem::dispatch::Dispatcher<FLEM_PACKET_SIZE, N>) -> Self {
+            Self {
+                engine: flem::client::Engine::new(dispatcher),
+                to_client: VecDeque::new(),
+                to_host: VecDeque::new(),
+                corrupt: false,
+            }
+        }
+    }
+
+    impl<const N: usize> flem::host::ByteChannel for Loopback<N> {
+        fn write(&mut self, bytes: &[u8]) -> Result<(), flem::Status> {
+            self.to_client.extend(bytes);
+            Ok(())
+        }
+
+        fn read(&mut self) -> Option<u8> {
+            if self.to_host.is_empty() {
+                let to_client = &mut self.to_client;
+                let to_host = &mut self.to_host;
+                self.engine.poll(
+                    || to_client.pop_front(),
+                    |byte| {
+                        to_host.push_back(byte);
+                        true
+                    },
+                );
+                if self.corrupt && to_host.len() > flem::FLEM_HEADER_SIZE {
+                    to_host[flem::FLEM_HEADER_SIZE] ^= 0xFF;
+                    self.corrupt = false;
+                }
+            }
+            self.to_host.pop_front()
+        }
+    }
+
     #[test]
     fn sending() {
-        const CUSTOM_REQUEST: u16 = 0xF;
+        const CUSTOM_REQUEST: u16 = 0x10;
 
         let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
         let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
 
-        let mut payload = [0 as u8; FLEM_PACKET_SIZE];
-        for i in 0..FLEM_PACKET_SIZE {
-            payload[i] = i as u8;
+        let mut payload = [0_u8; FLEM_PACKET_SIZE];
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte = i as u8;
         }
 
         tx.set_request(CUSTOM_REQUEST);
@@ -33,7 +80,7 @@ mod tests {
 
         assert_eq!(
             tx.length(),
-            flem::FLEM_HEADER_SIZE as usize + payload.len(),
+            flem::FLEM_HEADER_SIZE + payload.len(),
             "Packet length incorrect"
         );
 
@@ -50,7 +97,7 @@ mod tests {
                     if status == flem::Status::PacketBuilding {
                         byte_counter += 1;
                     } else {
-                        assert!(true, "Should not be hit");
+                        panic!("Should not be hit");
                     }
                 }
             }
@@ -58,7 +105,7 @@ mod tests {
         assert!(packet_received, "Packet not detected as received");
         assert_eq!(
             byte_counter,
-            flem::FLEM_HEADER_SIZE as usize + payload.len(),
+            flem::FLEM_HEADER_SIZE + payload.len(),
             "Not all bytes were sent"
         );
 
@@ -89,44 +136,48 @@ mod tests {
     fn size_check() {
         let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
 
-        assert_eq!(rx.length(), 10, "Size should be 10 (i.e. just the header)");
+        assert_eq!(
+            rx.length(),
+            flem::FLEM_HEADER_SIZE,
+            "Size should be 10 (i.e. just the header)"
+        );
 
-        let payload = [10 as u8; FLEM_PACKET_SIZE + 1];
+        let payload = [10_u8; FLEM_PACKET_SIZE + 1];
         assert!(
             rx.add_data(&payload).is_err(),
             "Payload is larger than allocated packet, this SHOULD cause an error"
         );
         assert_eq!(
             rx.length(),
-            flem::FLEM_HEADER_SIZE as usize,
+            flem::FLEM_HEADER_SIZE,
             "Size should be 10 (i.e. just the header)"
         );
 
-        let smaller_payload = [10 as u8; 60];
+        let smaller_payload = [10_u8; 60];
         assert!(
             rx.add_data(&smaller_payload).is_ok(),
             "Payload is smaller than allocated packet, this SHOULD NOT cause an error"
         );
         assert_eq!(
             rx.length(),
-            flem::FLEM_HEADER_SIZE as usize + smaller_payload.len(),
+            flem::FLEM_HEADER_SIZE + smaller_payload.len(),
             "Size should be 68 (i.e. header + smaller_payload.len)"
         );
 
         rx.reset_lazy();
         assert_eq!(
             rx.length(),
-            flem::FLEM_HEADER_SIZE as usize,
+            flem::FLEM_HEADER_SIZE,
             "Size should be 14 (i.e. just the header)"
         );
-        let just_right_payload = [10 as u8; FLEM_PACKET_SIZE];
+        let just_right_payload = [10_u8; FLEM_PACKET_SIZE];
         assert!(
             rx.add_data(&just_right_payload).is_ok(),
             "Payload is exactly the size of the allocated packet, this SHOULD NOT cause an error"
         );
         assert_eq!(
             rx.length(),
-            flem::FLEM_HEADER_SIZE as usize + just_right_payload.len(),
+            flem::FLEM_HEADER_SIZE + just_right_payload.len(),
             "Size should be 116 (i.e. header + just_right_payload.len)"
         );
     }
@@ -136,12 +187,12 @@ mod tests {
         use flem::Packet;
         use heapless;
         const PACKET_SIZE: usize = 64; // 64 byte packet
-        const FLEM_EXAMPLE_REQUEST: u16 = 0xF;
+        const FLEM_EXAMPLE_REQUEST: u16 = 0x10;
 
         let mut rx = Packet::<PACKET_SIZE>::new();
         let mut tx = Packet::<PACKET_SIZE>::new();
 
-        let data = [0 as u8; PACKET_SIZE];
+        let data = [0_u8; PACKET_SIZE];
 
         /* Add data as needed to the data buffer */
         tx.add_data(&data).unwrap();
@@ -195,7 +246,7 @@ mod tests {
     #[test]
     fn test_f32() {
         let test_data = [0.0, 1.0, 2.0, 3.0];
-        let mut buffer = [0 as u8; 16];
+        let mut buffer = [0_u8; 16];
 
         let mut offset = 0;
         for num in test_data {
@@ -218,8 +269,8 @@ mod tests {
 
     #[test]
     fn test_i32() {
-        let test_data = [0 as i32, 1, 2, 3];
-        let mut buffer = [0 as u8; 16];
+        let test_data = [0_i32, 1, 2, 3];
+        let mut buffer = [0_u8; 16];
 
         let mut offset = 0;
         for num in test_data {
@@ -227,7 +278,7 @@ mod tests {
             i32_to_le_buffer(num, &mut buffer, &mut offset).unwrap();
         }
 
-        let mut results = [0 as i32; 4];
+        let mut results = [0_i32; 4];
         offset = 0;
         results[0] = le_buffer_to_i32(&buffer, &mut offset).unwrap();
         results[1] = le_buffer_to_i32(&buffer, &mut offset).unwrap();
@@ -242,8 +293,8 @@ mod tests {
 
     #[test]
     fn test_u32() {
-        let test_data = [0 as u32, 1, 2, 3];
-        let mut buffer = [0 as u8; 16];
+        let test_data = [0_u32, 1, 2, 3];
+        let mut buffer = [0_u8; 16];
 
         let mut offset = 0;
         for num in test_data {
@@ -251,7 +302,7 @@ mod tests {
             u32_to_le_buffer(num, &mut buffer, &mut offset).unwrap();
         }
 
-        let mut results = [0 as u32; 4];
+        let mut results = [0_u32; 4];
         offset = 0;
         results[0] = le_buffer_to_u32(&buffer, &mut offset).unwrap();
         results[1] = le_buffer_to_u32(&buffer, &mut offset).unwrap();
@@ -266,8 +317,8 @@ mod tests {
 
     #[test]
     fn test_u16() {
-        let test_data = [0 as u16, 1, 2, 3];
-        let mut buffer = [0 as u8; 8];
+        let test_data = [0_u16, 1, 2, 3];
+        let mut buffer = [0_u8; 8];
 
         let mut offset = 0;
         for num in test_data {
@@ -275,7 +326,7 @@ mod tests {
             u16_to_le_buffer(num, &mut buffer, &mut offset).unwrap();
         }
 
-        let mut results = [0 as u16; 4];
+        let mut results = [0_u16; 4];
         offset = 0;
         results[0] = le_buffer_to_u16(&buffer, &mut offset).unwrap();
         results[1] = le_buffer_to_u16(&buffer, &mut offset).unwrap();
@@ -290,8 +341,8 @@ mod tests {
 
     #[test]
     fn test_i16() {
-        let test_data = [0 as i16, 1, 2, 3];
-        let mut buffer = [0 as u8; 8];
+        let test_data = [0_i16, 1, 2, 3];
+        let mut buffer = [0_u8; 8];
 
         let mut offset = 0;
         for num in test_data {
@@ -299,7 +350,7 @@ mod tests {
             i16_to_le_buffer(num, &mut buffer, &mut offset).unwrap();
         }
 
-        let mut results = [0 as i16; 4];
+        let mut results = [0_i16; 4];
         offset = 0;
         results[0] = le_buffer_to_i16(&buffer, &mut offset).unwrap();
         results[1] = le_buffer_to_i16(&buffer, &mut offset).unwrap();
@@ -311,4 +362,5465 @@ mod tests {
         assert_eq!(results[2], test_data[2], "Error in i16 buffer module");
         assert_eq!(results[3], test_data[3], "Error in i16 buffer module");
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_packet_round_trip() {
+        const CUSTOM_REQUEST: u16 = 0x10;
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(CUSTOM_REQUEST, &[1, 2, 3, 4, 5]).unwrap();
+
+        let json = serde_json::to_string(&tx).unwrap();
+        let mut rx: flem::Packet<FLEM_PACKET_SIZE> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(rx.get_header(), flem::FLEM_HEADER, "Header not restored");
+        assert_eq!(rx.get_request(), CUSTOM_REQUEST, "Request not restored");
+        assert_eq!(
+            rx.get_response(),
+            flem::response::SUCCESS,
+            "Response not restored"
+        );
+        assert_eq!(rx.length(), tx.length(), "Length not restored");
+        assert_eq!(
+            rx.get_checksum(),
+            tx.get_checksum(),
+            "Checksum not restored"
+        );
+        assert!(
+            rx.validate(),
+            "Checksum should still validate after a round trip"
+        );
+        assert_eq!(rx.bytes(), tx.bytes(), "Wire bytes should match");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_packet_rejects_oversized_data() {
+        let json = r#"{"header":21845,"checksum":0,"request":1,"response":1,"length":5,"data":[1,2,3,4,5]}"#;
+        assert!(
+            serde_json::from_str::<flem::Packet<4>>(json).is_err(),
+            "Data longer than the packet should not deserialize"
+        );
+
+        let json =
+            r#"{"header":21845,"checksum":0,"request":1,"response":1,"length":4,"data":[1,2,3]}"#;
+        assert!(
+            serde_json::from_str::<flem::Packet<4>>(json).is_err(),
+            "Data not matching the length field should not deserialize"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_data_id_round_trip() {
+        let id = flem::DataId::new("Serde Target", 1, 2, 3, FLEM_PACKET_SIZE);
+
+        let json = serde_json::to_string(&id).unwrap();
+        assert!(
+            json.contains("\"name\":\"Serde Target\""),
+            "Name should serialize as a string"
+        );
+
+        let decoded: flem::DataId = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.get_name(), id.get_name(), "Name not restored");
+        assert_eq!(decoded.get_major(), 1, "Major not restored");
+        assert_eq!(decoded.get_minor(), 2, "Minor not restored");
+        assert_eq!(decoded.get_patch(), 3, "Patch not restored");
+        assert_eq!(
+            decoded.get_max_packet_size() as usize,
+            FLEM_PACKET_SIZE,
+            "Max packet size not restored"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_status_round_trip() {
+        let json = serde_json::to_string(&flem::Status::ChecksumError).unwrap();
+        let status: flem::Status = serde_json::from_str(&json).unwrap();
+        assert_eq!(status, flem::Status::ChecksumError, "Status not restored");
+    }
+
+    #[cfg(feature = "cobs")]
+    fn cobs_round_trip(payload: &[u8]) {
+        use flem::cobs;
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0xF, payload).unwrap();
+
+        let mut frame = [0_u8; cobs::max_encoded_len(flem::FLEM_HEADER_SIZE + FLEM_PACKET_SIZE)];
+        let frame_length = cobs::encode_into(&tx, &mut frame).unwrap();
+
+        assert_eq!(
+            frame[frame_length - 1],
+            0x00,
+            "Frame should end with the delimiter"
+        );
+        assert!(
+            !frame[..frame_length - 1].contains(&0x00),
+            "Frame should not contain 0x00 before the delimiter"
+        );
+
+        let mut receiver = cobs::CobsReceiver::<FLEM_PACKET_SIZE>::new();
+        let mut received = 0;
+        for byte in &frame[..frame_length] {
+            if receiver.push(*byte).is_ok() {
+                received += 1;
+            }
+        }
+
+        assert_eq!(received, 1, "Exactly one packet should have been received");
+        assert_eq!(
+            receiver.packet().bytes(),
+            tx.bytes(),
+            "Rx and Tx packets don't match"
+        );
+    }
+
+    #[cfg(feature = "cobs")]
+    #[test]
+    fn cobs_zero_payload() {
+        cobs_round_trip(&[0x00; FLEM_PACKET_SIZE]);
+    }
+
+    #[cfg(feature = "cobs")]
+    #[test]
+    fn cobs_header_payload() {
+        cobs_round_trip(&[0x55; FLEM_PACKET_SIZE]);
+    }
+
+    #[cfg(feature = "cobs")]
+    #[test]
+    fn cobs_mixed_payloads() {
+        cobs_round_trip(&[]);
+        cobs_round_trip(&[0x00, 0x55, 0x55, 0x00, 0x00, 0x55]);
+
+        // Long runs of non-zero bytes need the 0xFF code byte
+        let mut payload = [0_u8; FLEM_PACKET_SIZE];
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte = (i as u8) | 0x01;
+        }
+        cobs_round_trip(&payload);
+    }
+
+    #[cfg(feature = "cobs")]
+    #[test]
+    fn cobs_long_non_zero_block() {
+        use flem::cobs;
+
+        const SIZE: usize = 600;
+        let mut tx = flem::Packet::<SIZE>::new();
+        tx.pack_data(0x0102, &[0xAA; SIZE]).unwrap();
+
+        let mut frame = [0_u8; cobs::max_encoded_len(flem::FLEM_HEADER_SIZE + SIZE)];
+        let frame_length = cobs::encode_into(&tx, &mut frame).unwrap();
+
+        let mut receiver = cobs::CobsReceiver::<SIZE>::new();
+        let mut received = false;
+        for byte in &frame[..frame_length] {
+            if receiver.push(*byte).is_ok() {
+                received = true;
+            }
+        }
+
+        assert!(received, "Packet should have been received");
+        assert_eq!(
+            receiver.packet().bytes(),
+            tx.bytes(),
+            "Rx and Tx packets don't match"
+        );
+    }
+
+    #[cfg(feature = "cobs")]
+    #[test]
+    fn cobs_output_too_small() {
+        use flem::cobs;
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0xF, &[0x00; 20]).unwrap();
+
+        let mut frame = [0_u8; 20];
+        assert_eq!(
+            cobs::encode_into(&tx, &mut frame),
+            Err(flem::Status::PacketOverflow),
+            "Destination is too small, this SHOULD cause an error"
+        );
+
+        let mut frame = [0_u8; cobs::max_encoded_len(flem::FLEM_HEADER_SIZE + 20)];
+        assert!(
+            cobs::encode_into(&tx, &mut frame).is_ok(),
+            "Destination is exactly the worst case size, this SHOULD NOT cause an error"
+        );
+    }
+
+    #[cfg(feature = "cobs")]
+    #[test]
+    fn cobs_recovers_after_bad_frame() {
+        use flem::cobs;
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0xF, &[0x55, 0x00, 0x55]).unwrap();
+
+        let mut frame = [0_u8; cobs::max_encoded_len(flem::FLEM_HEADER_SIZE + FLEM_PACKET_SIZE)];
+        let frame_length = cobs::encode_into(&tx, &mut frame).unwrap();
+
+        let mut receiver = cobs::CobsReceiver::<FLEM_PACKET_SIZE>::new();
+
+        // Truncated frame, the receiver sees the delimiter before the packet completes
+        let mut last = Ok(());
+        for byte in &frame[..frame_length / 2] {
+            last = receiver.push(*byte);
+        }
+        assert_eq!(last, Err(flem::Status::PacketBuilding));
+        assert_eq!(
+            receiver.push(0x00),
+            Err(flem::Status::InvalidDataLengthDetected),
+            "Truncated frame should be reported"
+        );
+
+        // Garbage frame
+        for byte in [0x03, 0x12, 0x34] {
+            let _ = receiver.push(byte);
+        }
+        assert!(
+            receiver.push(0x00).is_err(),
+            "Garbage frame should be rejected"
+        );
+
+        let mut received = false;
+        for byte in &frame[..frame_length] {
+            if receiver.push(*byte).is_ok() {
+                received = true;
+            }
+        }
+        assert!(received, "Receiver should recover on the next frame");
+        assert_eq!(
+            receiver.packet().bytes(),
+            tx.bytes(),
+            "Rx and Tx packets don't match"
+        );
+    }
+
+    #[test]
+    fn data_id_long_name() {
+        const NAME: &str = "A device name that is 40 characters long";
+        let id = flem::DataId::<40>::with_name(NAME, 1, 2, 3, FLEM_PACKET_SIZE);
+
+        assert_eq!(
+            flem::DataId::<40>::SIZE,
+            45,
+            "Wire size should be 40 + 5 bytes"
+        );
+        assert_eq!(
+            flem::DataId::<25>::SIZE,
+            30,
+            "Default wire size should be 30 bytes"
+        );
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_id(&id, true).unwrap();
+        assert_eq!(
+            tx.length(),
+            flem::FLEM_HEADER_SIZE + flem::DataId::<40>::SIZE,
+            "ID payload should grow with the name size"
+        );
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let mut received = false;
+        for byte in tx.bytes() {
+            if rx.construct(*byte).is_ok() {
+                received = true;
+            }
+        }
+        assert!(received, "ID packet not received");
+
+        let decoded = flem::DataId::<40>::parse(&rx.get_data()).unwrap();
+        let name: String = decoded.get_name().iter().collect();
+        assert_eq!(name, NAME, "Name not restored");
+        assert_eq!(decoded.get_major(), 1, "Major not restored");
+        assert_eq!(decoded.get_minor(), 2, "Minor not restored");
+        assert_eq!(decoded.get_patch(), 3, "Patch not restored");
+        assert_eq!(
+            decoded.get_max_packet_size() as usize,
+            FLEM_PACKET_SIZE,
+            "Max packet size not restored"
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn data_id_name_too_long() {
+        flem::DataId::<4>::with_name("Too long", 0, 0, 0, FLEM_PACKET_SIZE);
+    }
+
+    #[test]
+    fn stats_counters() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+
+        tx.pack_data(0xF, &[1, 2, 3, 4]).unwrap();
+
+        // Garbage before the header, 0x55 followed by a non header byte counts twice
+        for byte in [0x01, 0x02, 0x55, 0x03] {
+            assert!(rx.construct(byte).is_err());
+        }
+
+        // Valid packet
+        for byte in tx.bytes() {
+            let _ = rx.construct(*byte);
+        }
+        rx.reset_lazy();
+
+        // Corrupted payload byte
+        let mut corrupted = [0_u8; flem::FLEM_HEADER_SIZE + 4];
+        corrupted.copy_from_slice(tx.bytes());
+        corrupted[flem::FLEM_HEADER_SIZE] ^= 0xFF;
+        let mut result = Ok(());
+        for byte in corrupted {
+            result = rx.construct(byte);
+        }
+        assert_eq!(result, Err(flem::Status::ChecksumError));
+        rx.reset_lazy();
+
+        // Length longer than the packet
+        let mut too_long = [0_u8; flem::FLEM_HEADER_SIZE];
+        too_long.copy_from_slice(&tx.bytes()[..flem::FLEM_HEADER_SIZE]);
+        too_long[8..10].copy_from_slice(&(FLEM_PACKET_SIZE as u16 + 1).to_le_bytes());
+        for byte in too_long {
+            result = rx.construct(byte);
+        }
+        assert_eq!(result, Err(flem::Status::InvalidDataLengthDetected));
+        rx.reset_lazy();
+
+        let stats = rx.stats();
+        assert_eq!(
+            stats.packets_received, 1,
+            "One packet should have been received"
+        );
+        assert_eq!(stats.checksum_errors, 1, "One checksum error expected");
+        assert_eq!(stats.overflows, 1, "One overflow expected");
+        assert_eq!(stats.header_errors, 3, "Three header errors expected");
+        assert_eq!(
+            stats.resync_bytes, 4,
+            "Four bytes should have been discarded"
+        );
+
+        rx.reset_stats();
+        assert_eq!(
+            rx.stats(),
+            flem::Stats::default(),
+            "Stats should be cleared"
+        );
+    }
+
+    #[test]
+    fn stats_to_bytes() {
+        let stats = flem::Stats {
+            packets_received: 1,
+            checksum_errors: 2,
+            overflows: 3,
+            header_errors: 4,
+            resync_bytes: 0x01020304,
+        };
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0xF, &stats.to_bytes()).unwrap();
+        assert_eq!(tx.length(), flem::FLEM_HEADER_SIZE + flem::Stats::SIZE);
+
+        let decoded = flem::Stats::from_bytes(&tx.get_data()).unwrap();
+        assert_eq!(decoded, stats, "Stats not restored");
+
+        assert!(
+            flem::Stats::from_bytes(&[0; flem::Stats::SIZE - 1]).is_none(),
+            "Too few bytes, this SHOULD fail"
+        );
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    #[test]
+    fn sequence_detects_missing_frame() {
+        const CUSTOM_REQUEST: u16 = 0x10;
+
+        let mut frames = [flem::Packet::<FLEM_PACKET_SIZE>::new(); 3];
+        for (sequence, frame) in frames.iter_mut().enumerate() {
+            frame.set_sequence(sequence as u16);
+            frame
+                .pack_data(CUSTOM_REQUEST, &[sequence as u8; 8])
+                .unwrap();
+            assert_eq!(
+                frame.get_sequence(),
+                sequence as u16,
+                "Sequence should survive packing"
+            );
+        }
+
+        // Drop the middle frame
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let mut received = [0_u16; 2];
+        let mut received_count = 0;
+        for frame in [&frames[0], &frames[2]] {
+            for byte in frame.bytes() {
+                if rx.construct(*byte).is_ok() {
+                    received[received_count] = rx.get_sequence();
+                    received_count += 1;
+                    rx.reset_lazy();
+                }
+            }
+        }
+
+        assert_eq!(received_count, 2, "Both frames should have been received");
+        assert_eq!(received, [0, 2], "Sequence numbers not received");
+
+        rx.set_sequence(received[1]);
+        assert_eq!(
+            rx.sequence_gap(received[0]),
+            1,
+            "One frame should be missing"
+        );
+        rx.set_sequence(received[0].wrapping_add(1));
+        assert_eq!(
+            rx.sequence_gap(received[0]),
+            0,
+            "No frames should be missing"
+        );
+        rx.set_sequence(0);
+        assert_eq!(rx.sequence_gap(u16::MAX), 0, "Sequence should wrap");
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    #[test]
+    fn sequence_is_checksummed() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.set_sequence(7);
+        tx.pack_data(0xF, &[1, 2, 3]).unwrap();
+
+        let mut frame = [0_u8; flem::FLEM_HEADER_SIZE + 3];
+        frame.copy_from_slice(tx.bytes());
+        frame[10] ^= 0x01; // Sequence low byte
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let mut result = Ok(());
+        for byte in frame {
+            result = rx.construct(byte);
+        }
+        assert_eq!(
+            result,
+            Err(flem::Status::ChecksumError),
+            "Sequence should be checksummed"
+        );
+    }
+
+    #[test]
+    fn protocol_versions_reject_each_other() {
+        // A version 1 frame: header, checksum, request, response, length, 1 byte of data
+        let v1_frame = [
+            0x55, 0x55, 0x00, 0x00, 0x0F, 0x00, 0x01, 0x00, 0x01, 0x00, 0xAB,
+        ];
+        // A version 2 frame: as above, plus a 2 byte sequence number
+        let v2_frame = [
+            0x55, 0xAA, 0x00, 0x00, 0x0F, 0x00, 0x01, 0x00, 0x01, 0x00, 0x03, 0x00, 0xAB,
+        ];
+
+        let foreign_frame: &[u8] = if flem::FLEM_PROTOCOL_VERSION == 2 {
+            &v1_frame
+        } else {
+            &v2_frame
+        };
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert!(rx.construct(foreign_frame[0]).is_err());
+        assert_eq!(
+            rx.construct(foreign_frame[1]),
+            Err(flem::Status::HeaderBytesNotFound),
+            "A packet from the other protocol version should be rejected at the header"
+        );
+    }
+
+    #[test]
+    fn add_data_rejects_accumulated_overflow() {
+        let mut packet = Box::new(flem::Packet::<65000>::new());
+        let chunk = vec![0xA5u8; 40_000];
+
+        assert_eq!(packet.add_data(&chunk), Ok(()));
+        assert_eq!(packet.length(), flem::FLEM_HEADER_SIZE + 40_000);
+
+        assert_eq!(
+            packet.add_data(&chunk),
+            Err(flem::Status::PacketOverflow),
+            "Second 40 KB chunk should not fit"
+        );
+        assert_eq!(
+            packet.length(),
+            flem::FLEM_HEADER_SIZE + 40_000,
+            "Failed add_data should not change the length"
+        );
+        assert_eq!(packet.get_status(), flem::Status::PacketOverflow);
+    }
+
+    #[test]
+    fn add_data_rejects_slices_longer_than_u16() {
+        let mut packet = Box::new(flem::Packet::<65000>::new());
+        let chunk = vec![0u8; u16::MAX as usize + 1];
+
+        assert_eq!(packet.add_data(&chunk), Err(flem::Status::PacketOverflow));
+        assert_eq!(packet.length(), flem::FLEM_HEADER_SIZE);
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    #[test]
+    fn more_fragments_flag() {
+        const CUSTOM_REQUEST: u16 = 0x10;
+
+        let payload: Vec<u8> = (0..(FLEM_PACKET_SIZE * 3) as u32)
+            .map(|i| i as u8)
+            .collect();
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let mut reassembled = Vec::new();
+        let mut flags = Vec::new();
+
+        let chunks: Vec<&[u8]> = payload.chunks(FLEM_PACKET_SIZE).collect();
+        assert_eq!(chunks.len(), 3);
+
+        for (index, chunk) in chunks.iter().enumerate() {
+            tx.set_sequence(index as u16);
+            tx.set_more_fragments(index + 1 < chunks.len());
+            tx.pack_data(CUSTOM_REQUEST, chunk).unwrap();
+
+            for byte in tx.bytes() {
+                if rx.construct(*byte).is_ok() {
+                    reassembled
+                        .extend_from_slice(&rx.get_data()[..rx.length() - flem::FLEM_HEADER_SIZE]);
+                    flags.push(rx.has_more_fragments());
+                    rx.reset_lazy();
+                }
+            }
+        }
+
+        assert_eq!(
+            flags,
+            [true, true, false],
+            "Only the last fragment should clear the flag"
+        );
+        assert_eq!(reassembled, payload, "Payload should be reassembled");
+
+        tx.reset();
+        assert!(!tx.has_more_fragments(), "Reset should clear the flags");
+    }
+
+    #[test]
+    fn bytes_clamped_after_bad_length() {
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+
+        // Header, checksum, request and response, then a length of 0xFFFF
+        let mut stream = Vec::from(flem::FLEM_HEADER.to_le_bytes());
+        stream.extend_from_slice(&[0x12, 0x34, 0x0F, 0x00, 0x01, 0x00, 0xFF, 0xFF]);
+
+        for byte in stream {
+            let _ = rx.construct(byte);
+        }
+
+        assert_eq!(
+            rx.length(),
+            flem::FLEM_HEADER_SIZE + FLEM_PACKET_SIZE,
+            "Length should be clamped to the packet size"
+        );
+        assert_eq!(rx.bytes().len(), rx.length());
+        assert_eq!(&rx.bytes()[..2], &flem::FLEM_HEADER.to_le_bytes());
+
+        // Checksum walks bytes(), it must stay inside the packet as well
+        let _ = rx.checksum(false);
+    }
+
+    #[cfg(all(feature = "std", feature = "protocol-v2"))]
+    fn fragments(payload: &[u8], first_sequence: u16) -> Vec<flem::Packet<FLEM_PACKET_SIZE>> {
+        let count = payload.chunks(FLEM_PACKET_SIZE).len();
+        payload
+            .chunks(FLEM_PACKET_SIZE)
+            .enumerate()
+            .map(|(index, chunk)| {
+                let mut packet = flem::Packet::<FLEM_PACKET_SIZE>::new();
+                packet.set_sequence(first_sequence.wrapping_add(index as u16));
+                packet.set_more_fragments(index + 1 < count);
+                packet.pack_data(0xF, chunk).unwrap();
+                packet
+            })
+            .collect()
+    }
+
+    #[cfg(all(feature = "std", feature = "protocol-v2"))]
+    #[test]
+    fn reassembler_in_order() {
+        let payload: Vec<u8> = (0..300_u32).map(|i| i as u8).collect();
+        let mut reassembler = flem::reassembly::Reassembler::<FLEM_PACKET_SIZE>::new();
+
+        // Sequence numbers wrap in the middle of the payload
+        let packets = fragments(&payload, u16::MAX - 1);
+        assert_eq!(packets.len(), 3);
+
+        assert_eq!(reassembler.push(&packets[0]), Ok(None));
+        assert_eq!(reassembler.push(&packets[1]), Ok(None));
+        assert_eq!(reassembler.len(), 2 * FLEM_PACKET_SIZE);
+        assert_eq!(reassembler.push(&packets[2]), Ok(Some(payload)));
+        assert!(
+            reassembler.is_empty(),
+            "Reassembler should be ready for the next payload"
+        );
+    }
+
+    #[cfg(all(feature = "std", feature = "protocol-v2"))]
+    #[test]
+    fn reassembler_dropped_fragment() {
+        let first: Vec<u8> = (0..300_u32).map(|i| i as u8).collect();
+        let second = vec![0x55_u8; 250];
+        let mut reassembler = flem::reassembly::Reassembler::<FLEM_PACKET_SIZE>::new();
+
+        let packets = fragments(&first, 0);
+        assert_eq!(reassembler.push(&packets[0]), Ok(None));
+        // packets[1] is lost
+        assert_eq!(
+            reassembler.push(&packets[2]),
+            Err(flem::Status::SequenceGap),
+            "Missing fragment should be reported"
+        );
+        assert!(reassembler.is_empty(), "Partial payload should be dropped");
+
+        let packets = fragments(&second, 3);
+        let mut received = None;
+        for packet in &packets {
+            received = reassembler.push(packet).unwrap();
+        }
+        assert_eq!(received, Some(second), "Next payload should be reassembled");
+    }
+
+    #[test]
+    fn construct_discards_oversized() {
+        const SMALL_PACKET_SIZE: usize = 64;
+        const CUSTOM_REQUEST: u16 = 0x10;
+
+        let mut oversized = flem::Packet::<300>::new();
+        oversized.pack_data(CUSTOM_REQUEST, &[0x55; 300]).unwrap();
+
+        let mut valid = flem::Packet::<SMALL_PACKET_SIZE>::new();
+        valid
+            .pack_data(CUSTOM_REQUEST, &[0xA5; SMALL_PACKET_SIZE])
+            .unwrap();
+
+        let mut rx = flem::Packet::<SMALL_PACKET_SIZE>::new();
+        let mut discarded = 0;
+        let mut received = 0;
+        for byte in oversized.bytes().iter().chain(valid.bytes()) {
+            match rx.construct(*byte) {
+                Ok(_) => {
+                    received += 1;
+                    assert_eq!(rx.bytes(), valid.bytes(), "Rx and Tx packets don't match");
+                }
+                Err(flem::Status::DiscardingOversized) => discarded += 1,
+                Err(flem::Status::InvalidDataLengthDetected) => {
+                    // The usual receive loop resets on errors, the payload is still skipped
+                    rx.reset_lazy();
+                }
+                Err(flem::Status::PacketBuilding) => {}
+                Err(error) => panic!("Unexpected error {:?}", error),
+            }
+        }
+
+        assert_eq!(
+            received, 1,
+            "Valid packet after the oversized one should be received"
+        );
+        assert_eq!(
+            discarded, 300,
+            "Whole oversized payload should be discarded"
+        );
+        assert_eq!(rx.get_oversized_length(), 300);
+
+        rx.reset();
+        assert_eq!(rx.get_oversized_length(), 0);
+    }
+
+    #[test]
+    fn reassemble_into_scratch_buffer() {
+        const CUSTOM_REQUEST: u16 = 0x10;
+
+        let payload: Vec<u8> = (0..250_u32).map(|i| i as u8).collect();
+        let mut scratch = [0_u8; 256];
+        let mut offset = 0;
+
+        for chunk in payload.chunks(FLEM_PACKET_SIZE) {
+            let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+            tx.pack_data(CUSTOM_REQUEST, chunk).unwrap();
+
+            let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+            for byte in tx.bytes() {
+                if rx.construct(*byte).is_ok() {
+                    offset = rx.reassemble_into(&mut scratch, offset).unwrap();
+                }
+            }
+        }
+
+        assert_eq!(
+            offset,
+            payload.len(),
+            "Offset should be the total payload length"
+        );
+        assert_eq!(
+            &scratch[..offset],
+            payload.as_slice(),
+            "Payload not reassembled"
+        );
+
+        // A full packet no longer fits in the rest of the scratch buffer
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(CUSTOM_REQUEST, &[0xFF; FLEM_PACKET_SIZE])
+            .unwrap();
+        assert_eq!(
+            tx.reassemble_into(&mut scratch, offset),
+            Err(flem::Status::PacketOverflow)
+        );
+        assert_eq!(
+            &scratch[..offset],
+            payload.as_slice(),
+            "Scratch should be untouched"
+        );
+        assert_eq!(
+            tx.reassemble_into(&mut scratch, usize::MAX),
+            Err(flem::Status::PacketOverflow),
+            "Offset overflow should be caught"
+        );
+    }
+
+    #[test]
+    fn take_resets_receiver() {
+        const CUSTOM_REQUEST: u16 = 0x10;
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(
+            rx.take().unwrap_err(),
+            flem::Status::PacketBuilding,
+            "Nothing to take yet"
+        );
+
+        let mut first = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        first.pack_data(CUSTOM_REQUEST, &[1, 2, 3]).unwrap();
+        let mut second = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        second.pack_data(CUSTOM_REQUEST + 1, &[4, 5]).unwrap();
+
+        let mut received = Vec::new();
+        for byte in first.bytes().iter().chain(second.bytes()) {
+            if rx.construct(*byte).is_ok() {
+                assert!(rx.is_complete());
+                received.push(rx.take().unwrap());
+                assert!(!rx.is_complete(), "take() should reset the receiver");
+            }
+        }
+
+        assert_eq!(
+            received.len(),
+            2,
+            "Both packets should be received back to back"
+        );
+        assert_eq!(received[0].bytes(), first.bytes());
+        assert_eq!(received[1].bytes(), second.bytes());
+        assert_eq!(
+            rx.stats().packets_received,
+            2,
+            "Stats should survive take()"
+        );
+    }
+
+    #[test]
+    fn packet_equality() {
+        const CUSTOM_REQUEST: u16 = 0x10;
+
+        let mut first = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        first.pack_data(CUSTOM_REQUEST, &[1, 2, 3]).unwrap();
+
+        // Built differently, and with garbage past the payload
+        let mut second = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        second.add_data(&[0xFF; 8]).unwrap();
+        second.reset_lazy();
+        second.set_request(CUSTOM_REQUEST);
+        second.set_response(flem::response::SUCCESS);
+        second.add_data(&[1, 2]).unwrap();
+        second.add_data(&[3]).unwrap();
+        second.pack();
+
+        assert_eq!(first, second, "Identical wire contents should be equal");
+
+        // Received packets have different counters and status, but the same wire bytes
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        for byte in first.bytes() {
+            let _ = rx.construct(*byte);
+        }
+        assert_eq!(rx, first, "Received packet should equal the sent packet");
+
+        let mut different = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        different.pack_data(CUSTOM_REQUEST, &[1, 2, 4]).unwrap();
+        assert_ne!(first, different, "Different payloads should not be equal");
+
+        different.pack_data(CUSTOM_REQUEST, &[1, 2, 3, 0]).unwrap();
+        assert_ne!(first, different, "Different lengths should not be equal");
+    }
+
+    #[test]
+    fn parse_id_round_trip() {
+        const NAME: &str = "Round Trip Target";
+
+        let id = flem::DataId::new(NAME, 4, 5, 6, FLEM_PACKET_SIZE);
+
+        for ascii in [true, false] {
+            let mut host_tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+            host_tx.set_request(flem::request::ID);
+            host_tx.pack();
+
+            let mut client_rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+            for byte in host_tx.bytes() {
+                let _ = client_rx.construct(*byte);
+            }
+            assert!(
+                client_rx.is_complete(),
+                "Request not received on the client"
+            );
+            assert_eq!(client_rx.get_request(), flem::request::ID);
+
+            let mut client_tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+            client_tx.pack_id(&id, ascii).unwrap();
+
+            let mut host_rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+            for byte in client_tx.bytes() {
+                let _ = host_rx.construct(*byte);
+            }
+            assert!(host_rx.is_complete(), "Response not received on the host");
+
+            let decoded = host_rx.parse_id().unwrap();
+            let name: String = decoded
+                .get_name()
+                .iter()
+                .take_while(|c| **c != '\0')
+                .collect();
+            assert_eq!(name, NAME, "Name not restored, ascii = {}", ascii);
+            assert_eq!(decoded.get_major(), 4, "Major not restored");
+            assert_eq!(decoded.get_minor(), 5, "Minor not restored");
+            assert_eq!(decoded.get_patch(), 6, "Patch not restored");
+            assert_eq!(
+                decoded.get_max_packet_size() as usize,
+                FLEM_PACKET_SIZE,
+                "Max packet size not restored, ascii = {}",
+                ascii
+            );
+        }
+    }
+
+    #[test]
+    fn parse_id_errors() {
+        let mut packet = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        packet.pack_data(0xF, &[0; 40]).unwrap();
+        assert_eq!(
+            packet.parse_id().err(),
+            Some(flem::Status::UnrecognizedRequest),
+            "Only ID responses should parse"
+        );
+
+        packet.pack_data(flem::request::ID, &[1, 2, 3, 4]).unwrap();
+        assert_eq!(
+            packet.parse_id().err(),
+            Some(flem::Status::InvalidDataLengthDetected),
+            "Short payloads should not parse"
+        );
+
+        assert_eq!(
+            flem::DataId::from_bytes(&[0; 4]).err(),
+            Some(flem::Status::InvalidDataLengthDetected)
+        );
+        let mut id = [0; flem::DataId::<25>::SIZE];
+        id[3] = 1; // Max packet size
+        assert!(flem::DataId::from_bytes(&id).is_ok());
+    }
+
+    #[test]
+    fn clone_wire_resets_counters() {
+        const CUSTOM_REQUEST: u16 = 0x10;
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(CUSTOM_REQUEST, &[1, 2, 3, 4]).unwrap();
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let _ = rx.construct(0x00); // Bad header byte, counted in the stats
+        for byte in tx.bytes() {
+            let _ = rx.construct(*byte);
+        }
+        assert_eq!(rx.get_status(), flem::Status::PacketReceived);
+        assert_eq!(rx.get_data_length(), 4);
+
+        let mut clone = rx.clone_wire();
+        assert_eq!(clone, rx, "Wire contents should match");
+        assert_eq!(clone.get_data()[..4], [1, 2, 3, 4], "Data should match");
+        assert_eq!(
+            clone.get_status(),
+            flem::Status::Ok,
+            "Status should be reset"
+        );
+        assert_eq!(clone.get_data_length(), 0, "Data counter should be reset");
+        assert_eq!(clone.stats(), flem::Stats::new(), "Stats should be reset");
+
+        // Partially transmitted packets are sent from the start again
+        let _ = tx.get_byte();
+        let _ = tx.get_byte();
+        let mut retransmit = tx.clone_wire();
+        let mut sent = Vec::new();
+        while let Ok(byte) = retransmit.get_byte() {
+            sent.push(byte);
+        }
+        assert_eq!(sent, tx.bytes(), "Clone should transmit the whole packet");
+    }
+
+    #[test]
+    fn peer_limit() {
+        const CUSTOM_REQUEST: u16 = 0x10;
+
+        let client = flem::DataId::new("Client", 0, 1, 0, 50);
+        assert_eq!(client.negotiate(FLEM_PACKET_SIZE), 50, "Client is smaller");
+        assert_eq!(client.negotiate(20), 20, "Local side is smaller");
+        assert_eq!(client.negotiate(100_000), 50, "Local max larger than u16");
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(tx.get_peer_limit(), u16::MAX, "No limit by default");
+
+        // Peer limit smaller than T
+        tx.set_peer_limit(client.negotiate(FLEM_PACKET_SIZE));
+        assert_eq!(tx.pack_data(CUSTOM_REQUEST, &[0; 50]), Ok(()));
+        assert_eq!(
+            tx.pack_data(CUSTOM_REQUEST, &[0; 51]),
+            Err(flem::Status::PacketOverflow),
+            "Payload larger than the peer limit should be rejected"
+        );
+        tx.reset();
+        tx.add_data(&[0; 30]).unwrap();
+        assert_eq!(tx.add_data(&[0; 21]), Err(flem::Status::PacketOverflow));
+        assert_eq!(tx.length(), flem::FLEM_HEADER_SIZE + 30);
+
+        // Peer limit equal to T
+        tx.set_peer_limit(FLEM_PACKET_SIZE as u16);
+        assert_eq!(tx.pack_data(CUSTOM_REQUEST, &[0; FLEM_PACKET_SIZE]), Ok(()));
+
+        // Peer limit larger than T, T still applies
+        tx.set_peer_limit(FLEM_PACKET_SIZE as u16 * 2);
+        assert_eq!(tx.pack_data(CUSTOM_REQUEST, &[0; FLEM_PACKET_SIZE]), Ok(()));
+        assert_eq!(
+            tx.pack_data(CUSTOM_REQUEST, &[0; FLEM_PACKET_SIZE + 1]),
+            Err(flem::Status::PacketOverflow)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_packet_preserves_payload_length() {
+        const CUSTOM_REQUEST: u16 = 0x10;
+
+        let mut full = [0xA5_u8; FLEM_PACKET_SIZE];
+        full[FLEM_PACKET_SIZE - 1] = 0;
+        let payloads: [&[u8]; 5] = [&[], &[0], &[7], &[1, 2, 0, 0, 0], &full];
+
+        for payload in payloads {
+            let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+            tx.pack_data(CUSTOM_REQUEST, payload).unwrap();
+
+            let json = serde_json::to_string(&tx).unwrap();
+            let rx: flem::Packet<FLEM_PACKET_SIZE> = serde_json::from_str(&json).unwrap();
+
+            assert_eq!(
+                rx.length(),
+                flem::FLEM_HEADER_SIZE + payload.len(),
+                "Payload length should be preserved exactly, trailing zeros included"
+            );
+            assert_eq!(&rx.bytes()[flem::FLEM_HEADER_SIZE..], payload);
+            assert_eq!(rx, tx, "Wire bytes should match");
+            assert!(
+                rx.get_data()[payload.len()..].iter().all(|byte| *byte == 0),
+                "Data past the payload should be cleared"
+            );
+            assert_eq!(rx.get_data_length(), 0, "Counters should be reset");
+        }
+    }
+
+    #[test]
+    fn data_id_from_validates_input() {
+        assert!(
+            flem::DataId::from(&[]).is_none(),
+            "Empty slice should be rejected"
+        );
+
+        let id = flem::DataId::new("Valid", 1, 2, 3, FLEM_PACKET_SIZE);
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_id(&id, true).unwrap();
+        let bytes = &tx.bytes()[flem::FLEM_HEADER_SIZE..];
+
+        assert!(
+            flem::DataId::from(&bytes[..flem::DataId::<25>::SIZE - 1]).is_none(),
+            "Truncated slice should be rejected"
+        );
+
+        let decoded = flem::DataId::from(bytes).expect("Valid slice should decode");
+        let name: String = decoded
+            .get_name()
+            .iter()
+            .take_while(|c| **c != '\0')
+            .collect();
+        assert_eq!(name, "Valid");
+        assert_eq!(decoded.get_major(), 1);
+        assert_eq!(decoded.get_minor(), 2);
+        assert_eq!(decoded.get_patch(), 3);
+        assert_eq!(decoded.get_max_packet_size() as usize, FLEM_PACKET_SIZE);
+
+        let mut zero_size = [0_u8; flem::DataId::<25>::SIZE];
+        zero_size.copy_from_slice(bytes);
+        zero_size[3] = 0;
+        zero_size[4] = 0;
+        assert!(
+            flem::DataId::from(&zero_size).is_none(),
+            "Max packet size of 0 should be rejected"
+        );
+    }
+
+    #[test]
+    fn dispatcher_routes_requests() {
+        use flem::dispatch::Dispatcher;
+
+        const GET_DATA: u16 = 0x10;
+        const SET_DATA: u16 = 0x11;
+        const UNHANDLED: u16 = 0x12;
+
+        fn get_data(
+            rx: &flem::Packet<FLEM_PACKET_SIZE>,
+            tx: &mut flem::Packet<FLEM_PACKET_SIZE>,
+        ) -> Result<(), flem::Status> {
+            tx.pack_data(rx.get_request(), &[0xAA, 0xBB])
+        }
+
+        fn set_data(
+            rx: &flem::Packet<FLEM_PACKET_SIZE>,
+            tx: &mut flem::Packet<FLEM_PACKET_SIZE>,
+        ) -> Result<(), flem::Status> {
+            tx.pack_data(rx.get_request(), &[])
+        }
+
+        let mut dispatcher = Dispatcher::<FLEM_PACKET_SIZE, 2>::new();
+        dispatcher.register(GET_DATA, set_data).unwrap();
+        dispatcher.register(GET_DATA, get_data).unwrap(); // Replaces set_data
+        dispatcher.register(SET_DATA, set_data).unwrap();
+        assert_eq!(
+            dispatcher.register(UNHANDLED, set_data),
+            Err(flem::Status::PacketOverflow),
+            "Dispatcher should be full"
+        );
+        assert!(dispatcher.handles(GET_DATA));
+        assert!(!dispatcher.handles(UNHANDLED));
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+
+        rx.set_request(GET_DATA);
+        rx.pack();
+        dispatcher.dispatch(&rx, &mut tx).unwrap();
+        assert_eq!(tx.get_request(), GET_DATA);
+        assert_eq!(tx.get_response(), flem::response::SUCCESS);
+        assert_eq!(
+            &tx.bytes()[flem::FLEM_HEADER_SIZE..],
+            &[0xAA, 0xBB],
+            "Wrong handler ran"
+        );
+
+        rx.set_request(UNHANDLED);
+        rx.pack();
+        dispatcher.dispatch(&rx, &mut tx).unwrap();
+        assert_eq!(
+            tx.get_request(),
+            UNHANDLED,
+            "Error response should echo the request"
+        );
+        assert_eq!(tx.get_response(), flem::response::UNKNOWN_REQUEST);
+        assert_eq!(tx.length(), flem::FLEM_HEADER_SIZE);
+        assert!(tx.validate(), "Error response should be packed");
+
+        assert!(dispatcher.unregister(SET_DATA));
+        assert!(!dispatcher.unregister(SET_DATA));
+        dispatcher.register(UNHANDLED, set_data).unwrap();
+    }
+
+    #[cfg(feature = "defmt")]
+    #[test]
+    fn defmt_format_implemented() {
+        fn assert_format<F: defmt::Format>() {}
+
+        assert_format::<flem::Status>();
+        assert_format::<flem::DataId>();
+        assert_format::<flem::DataId<40>>();
+        assert_format::<flem::Packet<FLEM_PACKET_SIZE>>();
+    }
+
+    #[test]
+    fn response_classification() {
+        use flem::response;
+
+        for error in [
+            response::BUSY,
+            response::NOT_READY,
+            response::INVALID_ARGUMENT,
+            response::OUT_OF_RANGE,
+            response::UNKNOWN_REQUEST,
+            response::CHECKSUM_ERROR,
+            response::ERROR_START,
+        ] {
+            assert!(
+                response::is_error(error),
+                "{:#06x} should be an error",
+                error
+            );
+        }
+
+        for ok in [
+            response::ASYNC,
+            response::SUCCESS,
+            0x1234,
+            response::ERROR_START - 1,
+        ] {
+            assert!(
+                !response::is_error(ok),
+                "{:#06x} should not be an error",
+                ok
+            );
+        }
+
+        let mut packet = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert!(!packet.is_success(), "New packets are ASYNC");
+        packet.pack_data(0xF, &[1]).unwrap();
+        assert!(packet.is_success());
+        packet.pack_error_code(0xF, response::BUSY).unwrap();
+        assert!(!packet.is_success());
+        assert!(response::is_error(packet.get_response()));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn stdio_cursor() {
+        use flem::stdio::{read_packet, write_packet, ReadPacketError};
+        use std::io::{Cursor, ErrorKind};
+
+        let mut first = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        first.pack_data(0xF, &[1, 2, 3]).unwrap();
+        let mut second = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        second.pack_data(0x10, &[4, 5]).unwrap();
+        let mut corrupt = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        corrupt.pack_data(0x11, &[6]).unwrap();
+
+        let mut stream = vec![0x00, 0x55, 0x12]; // Garbage before the first header
+        write_packet(&mut stream, &first).unwrap();
+        write_packet(&mut stream, &second).unwrap();
+        let corrupt_start = stream.len();
+        write_packet(&mut stream, &corrupt).unwrap();
+        let last = stream.len() - 1;
+        stream[last] ^= 0xFF;
+        stream.extend_from_slice(&first.bytes()[..5]); // Stream ends mid packet
+
+        let mut reader = Cursor::new(stream);
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+
+        read_packet(&mut reader, &mut rx).unwrap();
+        assert_eq!(rx, first, "First packet not received");
+        read_packet(&mut reader, &mut rx).unwrap();
+        assert_eq!(rx, second, "Second packet should follow directly");
+        assert_eq!(
+            reader.position() as usize,
+            corrupt_start,
+            "No bytes of the next packet read"
+        );
+
+        match read_packet(&mut reader, &mut rx) {
+            Err(ReadPacketError::Flem(flem::Status::ChecksumError)) => {}
+            other => panic!("Expected a checksum error, got {:?}", other),
+        }
+        match read_packet(&mut reader, &mut rx) {
+            Err(ReadPacketError::Io(error)) => assert_eq!(error.kind(), ErrorKind::UnexpectedEof),
+            other => panic!("Expected end of stream, got {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn stdio_tcp() {
+        use flem::stdio::{read_packet, write_packet};
+        use std::io::{BufReader, Write};
+        use std::net::{TcpListener, TcpStream};
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let client = std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(address).unwrap();
+            let mut request = flem::Packet::<FLEM_PACKET_SIZE>::new();
+            request.set_request(flem::request::ID);
+            request.pack();
+
+            // Send the request in two writes to force short reads on the other side
+            stream.write_all(&request.bytes()[..3]).unwrap();
+            stream.flush().unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            stream.write_all(&request.bytes()[3..]).unwrap();
+
+            let mut response = flem::Packet::<FLEM_PACKET_SIZE>::new();
+            read_packet(&mut BufReader::new(&stream), &mut response).unwrap();
+            response
+        });
+
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(&stream);
+        let mut request = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        read_packet(&mut reader, &mut request).unwrap();
+        assert_eq!(request.get_request(), flem::request::ID);
+
+        let id = flem::DataId::new("TCP Target", 1, 0, 0, FLEM_PACKET_SIZE);
+        let mut response = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        response.pack_id(&id, true).unwrap();
+        write_packet(&mut &stream, &response).unwrap();
+
+        let received = client.join().unwrap();
+        assert_eq!(received, response, "Response not received over TCP");
+        assert_eq!(
+            received.parse_id().unwrap().get_max_packet_size() as usize,
+            FLEM_PACKET_SIZE
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn async_link_pipe() {
+        use flem::async_io::{AsyncLink, AsyncPacketRx, AsyncPacketTx};
+        use std::cell::RefCell;
+        use std::collections::VecDeque;
+        use std::rc::Rc;
+
+        /// In-memory pipe that hands out at most 3 bytes per read
+        #[derive(Clone, Default)]
+        struct Pipe(Rc<RefCell<VecDeque<u8>>>);
+
+        impl embedded_io_async::ErrorType for Pipe {
+            type Error = core::convert::Infallible;
+        }
+
+        impl embedded_io_async::Write for Pipe {
+            async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+                self.0.borrow_mut().extend(buf);
+                Ok(buf.len())
+            }
+        }
+
+        impl embedded_io_async::Read for Pipe {
+            async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+                let mut queue = self.0.borrow_mut();
+                let count = buf.len().min(queue.len()).min(3);
+                for byte in buf.iter_mut().take(count) {
+                    *byte = queue.pop_front().unwrap();
+                }
+                Ok(count)
+            }
+        }
+
+        let pipe = Pipe::default();
+        let mut host = AsyncLink::<_, FLEM_PACKET_SIZE>::new(pipe.clone());
+        let mut client = AsyncLink::<_, FLEM_PACKET_SIZE>::new(pipe);
+
+        let mut first = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        first.pack_data(0xF, &[0xAA; 40]).unwrap();
+        let mut second = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        second.pack_data(0x10, &[1, 2, 3]).unwrap();
+
+        pollster::block_on(async {
+            host.send(&mut first).await.unwrap();
+            host.send(&mut second).await.unwrap();
+
+            client.recv().await.unwrap();
+            assert_eq!(*client.packet(), first, "First packet not received");
+            assert_eq!(client.take().unwrap(), first);
+
+            client.recv().await.unwrap();
+            assert_eq!(*client.packet(), second, "Second packet not received");
+
+            assert_eq!(
+                client.recv().await,
+                Err(flem::Status::IoError),
+                "Empty pipe should end the stream"
+            );
+        });
+    }
+
+    #[test]
+    fn as_parts_matches_bytes() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.add_data(&[0x12; 7]).unwrap();
+        assert_eq!(
+            tx.as_parts().err(),
+            Some(flem::Status::HeaderBytesNotFound),
+            "Unpacked packets have no header"
+        );
+
+        for payload in [&[][..], &[1, 2, 3][..], &[0xA5; FLEM_PACKET_SIZE][..]] {
+            tx.pack_data(0xF, payload).unwrap();
+
+            let (header, data) = tx.as_parts().unwrap();
+            assert_eq!(data, payload, "Payload part doesn't match");
+
+            let mut wire = Vec::from(header);
+            wire.extend_from_slice(data);
+            assert_eq!(wire, tx.bytes(), "Parts should match bytes()");
+
+            let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+            for byte in wire {
+                let _ = rx.construct(byte);
+            }
+            assert!(rx.is_complete(), "Parts should construct a valid packet");
+            assert_eq!(rx, tx);
+        }
+    }
+
+    #[cfg(feature = "embedded-io")]
+    #[test]
+    fn embedded_io_blocking() {
+        /// Accepts at most 5 bytes per write
+        #[derive(Default)]
+        struct MockWriter(Vec<u8>);
+
+        impl embedded_io::ErrorType for MockWriter {
+            type Error = core::convert::Infallible;
+        }
+
+        impl embedded_io::Write for MockWriter {
+            fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+                let count = buf.len().min(5);
+                self.0.extend_from_slice(&buf[..count]);
+                Ok(count)
+            }
+
+            fn flush(&mut self) -> Result<(), Self::Error> {
+                Ok(())
+            }
+        }
+
+        /// Interrupts every other read
+        struct MockReader {
+            bytes: Vec<u8>,
+            position: usize,
+            interrupt: bool,
+        }
+
+        impl embedded_io::ErrorType for MockReader {
+            type Error = embedded_io::ErrorKind;
+        }
+
+        impl embedded_io::Read for MockReader {
+            fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+                self.interrupt = !self.interrupt;
+                if self.interrupt {
+                    return Err(embedded_io::ErrorKind::Interrupted);
+                }
+                let count = buf.len().min(self.bytes.len() - self.position);
+                buf[..count].copy_from_slice(&self.bytes[self.position..self.position + count]);
+                self.position += count;
+                Ok(count)
+            }
+        }
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0xF, &[0x5A; 70]).unwrap();
+
+        let mut writer = MockWriter::default();
+        writer.0.extend_from_slice(&[0x00, 0x12]); // Garbage before the packet
+        tx.send_blocking(&mut writer).unwrap();
+        assert_eq!(
+            &writer.0[2..],
+            tx.bytes(),
+            "Partial writes should be completed"
+        );
+
+        let mut reader = MockReader {
+            bytes: writer.0,
+            position: 0,
+            interrupt: false,
+        };
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        rx.recv_blocking(&mut reader).unwrap();
+        assert_eq!(rx, tx, "Packet not received");
+
+        assert_eq!(
+            rx.recv_blocking(&mut reader),
+            Err(flem::Status::IoError),
+            "End of the data should be an error"
+        );
+    }
+
+    #[test]
+    fn bytes_chunks_round_trip() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0xF, &[0xC3; 33]).unwrap();
+        let wire_len = tx.wire_len();
+        assert_eq!(wire_len, flem::FLEM_HEADER_SIZE + 33);
+        assert_eq!(wire_len, tx.bytes().len());
+
+        for chunk_size in [1, 7, wire_len + 10] {
+            let chunks: Vec<&[u8]> = tx.bytes_chunks(chunk_size).collect();
+            assert_eq!(chunks.len(), wire_len.div_ceil(chunk_size));
+            assert!(chunks.iter().all(|chunk| chunk.len() <= chunk_size));
+            assert_eq!(
+                chunks.iter().map(|chunk| chunk.len()).sum::<usize>(),
+                wire_len
+            );
+
+            let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+            let mut result = Err(flem::Status::PacketBuilding);
+            for chunk in chunks {
+                result = rx.construct_slice(chunk);
+            }
+            assert!(
+                result.is_ok(),
+                "Chunk size {} should round trip",
+                chunk_size
+            );
+            assert_eq!(rx, tx, "Chunk size {} should round trip", chunk_size);
+        }
+    }
+
+    #[test]
+    fn construct_slice_leaves_next_packet() {
+        let mut first = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        first.pack_data(0xF, &[1, 2, 3]).unwrap();
+        let mut second = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        second.pack_data(0x10, &[4]).unwrap();
+
+        let mut stream = vec![0xFF, 0x00]; // Garbage
+        stream.extend_from_slice(first.bytes());
+        stream.extend_from_slice(second.bytes());
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let used = rx.construct_slice(&stream).unwrap();
+        assert_eq!(used, 2 + first.wire_len());
+        assert_eq!(rx.take().unwrap(), first);
+
+        assert_eq!(rx.construct_slice(&stream[used..]), Ok(second.wire_len()));
+        assert_eq!(rx, second);
+
+        rx.reset_lazy();
+        assert_eq!(
+            rx.construct_slice(&first.bytes()[..5]),
+            Err(flem::Status::PacketBuilding),
+            "Partial packet needs more bytes"
+        );
+    }
+
+    #[test]
+    fn checksum_coverage() {
+        use flem::ChecksumCoverage;
+
+        let mut payload_only = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(
+            payload_only.get_checksum_coverage(),
+            ChecksumCoverage::PayloadOnly
+        );
+        payload_only.pack_data(0xF, &[1, 2, 3]).unwrap();
+
+        let mut include_header = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        include_header.set_checksum_coverage(ChecksumCoverage::IncludeHeader);
+        include_header.pack_data(0xF, &[1, 2, 3]).unwrap();
+        assert_eq!(
+            include_header.get_checksum_coverage(),
+            ChecksumCoverage::IncludeHeader,
+            "Coverage should survive pack_data"
+        );
+
+        assert_ne!(
+            payload_only.get_checksum(),
+            include_header.get_checksum(),
+            "Coverage modes should produce different checksums"
+        );
+
+        for (tx, coverage, valid) in [
+            (&payload_only, ChecksumCoverage::PayloadOnly, true),
+            (&payload_only, ChecksumCoverage::IncludeHeader, false),
+            (&include_header, ChecksumCoverage::IncludeHeader, true),
+            (&include_header, ChecksumCoverage::PayloadOnly, false),
+        ] {
+            let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+            rx.set_checksum_coverage(coverage);
+            let result = rx.construct_slice(tx.bytes());
+            assert_eq!(
+                result.is_ok(),
+                valid,
+                "Validation with {:?} should be {}",
+                coverage,
+                valid
+            );
+            if !valid {
+                assert_eq!(result, Err(flem::Status::ChecksumError));
+            }
+        }
+    }
+
+    #[test]
+    fn write_to_resumes() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let payload: Vec<u8> = (0..50).collect();
+        tx.pack_data(0xF, &payload).unwrap();
+
+        let mut wire = Vec::new();
+        let mut calls = 0;
+        loop {
+            // FIFO takes 16 bytes, then reports full
+            let mut room = 16;
+            let status = tx.write_to(&mut |byte| {
+                if room == 0 {
+                    return false;
+                }
+                room -= 1;
+                wire.push(byte);
+                true
+            });
+            calls += 1;
+            if status == flem::Status::GetByteFinished {
+                break;
+            }
+            assert_eq!(status, flem::Status::Ok, "Transmit should be resumable");
+        }
+
+        assert_eq!(wire, tx.bytes(), "No bytes should be lost or repeated");
+        assert_eq!(calls, tx.wire_len().div_ceil(16));
+        assert_eq!(
+            tx.write_to(&mut |_| true),
+            flem::Status::GetByteFinished,
+            "Nothing left to send"
+        );
+    }
+
+    #[test]
+    fn read_from_resumes() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0xF, &[9; 25]).unwrap();
+        let mut next = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        next.pack_data(0x10, &[]).unwrap();
+
+        let mut stream = vec![0x00]; // Garbage
+        stream.extend_from_slice(tx.bytes());
+        stream.extend_from_slice(next.bytes());
+        let mut stream = stream.into_iter();
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+
+        // Interrupts deliver 10 bytes at a time
+        let mut status = flem::Status::PacketBuilding;
+        let mut burst = Vec::new().into_iter();
+        while status == flem::Status::PacketBuilding {
+            burst = stream.by_ref().take(10).collect::<Vec<u8>>().into_iter();
+            status = rx.read_from(&mut || burst.next());
+        }
+        assert_eq!(status, flem::Status::PacketReceived);
+        assert_eq!(rx.take().unwrap(), tx);
+
+        // The rest of the burst is still in the source
+        let mut rest = burst.chain(stream);
+        assert_eq!(
+            rx.read_from(&mut || rest.next()),
+            flem::Status::PacketReceived
+        );
+        assert_eq!(rx, next, "Bytes after the first packet should not be lost");
+        assert_eq!(rx.read_from(&mut || None), flem::Status::PacketBuilding);
+    }
+
+    #[test]
+    fn get_bytes_into_chunks() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let payload: Vec<u8> = (0..77).collect();
+        tx.pack_data(0xF, &payload).unwrap();
+
+        for chunk in [1, 3, 7, 16, 64, 500] {
+            let mut tx = tx.clone_wire();
+            let mut out = vec![0u8; chunk];
+            let mut wire = Vec::new();
+            loop {
+                let count = tx.get_bytes_into(&mut out);
+                if count == 0 {
+                    break;
+                }
+                assert!(count <= chunk);
+                wire.extend_from_slice(&out[..count]);
+            }
+            assert_eq!(
+                wire,
+                tx.bytes(),
+                "Chunks of {} should rebuild the packet",
+                chunk
+            );
+            assert_eq!(tx.get_bytes_into(&mut out), 0, "Finished stays finished");
+            assert_eq!(tx.get_byte(), Err(flem::Status::GetByteFinished));
+        }
+
+        let mut tx = tx.clone_wire();
+
+        // Mixing with get_byte continues from the same cursor
+        let first = tx.get_byte().unwrap();
+        let mut rest = vec![0u8; tx.wire_len()];
+        let count = tx.get_bytes_into(&mut rest);
+        assert_eq!(count, tx.wire_len() - 1);
+        assert_eq!(first, tx.bytes()[0]);
+        assert_eq!(&rest[..count], &tx.bytes()[1..]);
+
+        // Empty output slice writes nothing
+        let mut tx = tx.clone_wire();
+        assert_eq!(tx.get_bytes_into(&mut []), 0);
+        assert_eq!(tx.get_byte(), Ok(tx.bytes()[0]));
+    }
+
+    #[cfg(feature = "nb")]
+    #[test]
+    fn nb_send_recv_resume_after_would_block() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let payload: Vec<u8> = (0..40).collect();
+        tx.pack_data(0xF, &payload).unwrap();
+
+        // Mock serial, every other write blocks
+        let mut wire = Vec::new();
+        let mut busy = false;
+        let mut write = |byte: u8| -> nb::Result<(), ()> {
+            busy = !busy;
+            if busy {
+                return Err(nb::Error::WouldBlock);
+            }
+            wire.push(byte);
+            Ok(())
+        };
+
+        let mut blocked = 0;
+        loop {
+            match flem::nb::send(&mut write, &mut tx) {
+                Ok(()) => break,
+                Err(nb::Error::WouldBlock) => blocked += 1,
+                Err(nb::Error::Other(error)) => panic!("Unexpected error {:?}", error),
+            }
+        }
+        assert_eq!(blocked, tx.wire_len());
+        assert_eq!(wire, tx.bytes(), "No bytes should be lost or repeated");
+
+        // Mock serial, every other read blocks
+        let mut incoming = vec![0x12, 0x34]; // Garbage
+        incoming.extend_from_slice(&wire);
+        incoming.extend_from_slice(&wire);
+        let mut incoming = incoming.into_iter();
+        let mut busy = false;
+        let mut read = || -> nb::Result<u8, ()> {
+            busy = !busy;
+            if busy {
+                return Err(nb::Error::WouldBlock);
+            }
+            incoming.next().ok_or(nb::Error::Other(()))
+        };
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        for _ in 0..2 {
+            let mut blocked = 0;
+            loop {
+                match flem::nb::recv(&mut read, &mut rx) {
+                    Ok(()) => break,
+                    Err(nb::Error::WouldBlock) => blocked += 1,
+                    Err(nb::Error::Other(error)) => panic!("Unexpected error {:?}", error),
+                }
+            }
+            assert!(blocked > 0);
+            assert_eq!(rx, tx);
+        }
+
+        // Serial errors are passed through
+        assert_eq!(
+            nb::block!(flem::nb::recv(&mut read, &mut rx)),
+            Err(flem::nb::FlemNbError::Serial(()))
+        );
+    }
+
+    #[cfg(feature = "nb")]
+    #[test]
+    fn nb_recv_checksum_error() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0xF, &[1, 2, 3]).unwrap();
+        let mut wire = tx.bytes().to_vec();
+        let last = wire.len() - 1;
+        wire[last] ^= 0xFF;
+        wire.extend_from_slice(tx.bytes());
+        let mut incoming = wire.into_iter();
+        let mut read = || incoming.next().ok_or(nb::Error::<()>::WouldBlock);
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(
+            flem::nb::recv(&mut read, &mut rx),
+            Err(nb::Error::Other(flem::nb::FlemNbError::Flem(
+                flem::Status::ChecksumError
+            )))
+        );
+        assert_eq!(flem::nb::recv(&mut read, &mut rx), Ok(()));
+        assert_eq!(rx, tx);
+    }
+
+    #[test]
+    fn header_size_matches_fields() {
+        // The size itself is checked at compile time, this checks where the fields land
+        let mut packet = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        packet.set_request(0x1234);
+        packet.set_response(0x5678);
+        packet.pack();
+
+        let bytes = packet.bytes();
+        assert_eq!(bytes.len(), flem::FLEM_HEADER_SIZE);
+        assert_eq!(&bytes[0..2], &flem::FLEM_HEADER.to_le_bytes());
+        assert_eq!(&bytes[2..4], &packet.get_checksum().to_le_bytes());
+        assert_eq!(&bytes[4..6], &0x1234u16.to_le_bytes());
+        assert_eq!(&bytes[6..8], &0x5678u16.to_le_bytes());
+        assert_eq!(&bytes[8..10], &0u16.to_le_bytes());
+
+        packet.pack_data(0x1234, &[0xEE]).unwrap();
+        assert_eq!(
+            packet.bytes()[flem::FLEM_HEADER_SIZE],
+            0xEE,
+            "Data follows the header"
+        );
+    }
+
+    #[test]
+    fn with_payload_and_builder() {
+        let payload: Vec<u8> = (0..20).collect();
+
+        let mut expected = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        expected.pack_data(0x42, &payload).unwrap();
+        let tx = flem::Packet::<FLEM_PACKET_SIZE>::with_payload(0x42, &payload).unwrap();
+        assert_eq!(tx, expected);
+        assert_eq!(
+            flem::Packet::<8>::with_payload(0x42, &payload).err(),
+            Some(flem::Status::PacketOverflow)
+        );
+
+        let built = flem::builder::PacketBuilder::<FLEM_PACKET_SIZE>::new()
+            .request(0x42)
+            .response(flem::response::SUCCESS)
+            .data(&payload[..5])
+            .data(&payload[5..])
+            .build()
+            .unwrap();
+        assert_eq!(built, expected, "Appending should match one add_data call");
+
+        // The built packet is packed and passes construct
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(rx.construct_slice(built.bytes()), Ok(built.wire_len()));
+        assert_eq!(rx, built);
+
+        // Empty packet still gets a header and checksum
+        let mut empty = flem::builder::PacketBuilder::<FLEM_PACKET_SIZE>::new()
+            .request(0x17)
+            .build()
+            .unwrap();
+        assert_eq!(empty.get_header(), flem::FLEM_HEADER);
+        assert_eq!(empty.get_checksum(), empty.checksum(false));
+
+        // Overflow is caught by data() and reported by build(), later data is ignored
+        let overflow = flem::builder::PacketBuilder::<8>::new()
+            .data(&[1; 6])
+            .data(&[2; 3])
+            .data(&[3; 2])
+            .build();
+        assert_eq!(overflow.err(), Some(flem::Status::PacketOverflow));
+    }
+
+    #[test]
+    fn expected_remaining() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0xF, &[7; 30]).unwrap();
+        let wire = tx.bytes();
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(rx.expected_remaining(), None, "Nothing received yet");
+
+        // Header up to and including the length field
+        for byte in &wire[..10] {
+            assert_eq!(rx.expected_remaining(), None, "Length isn't known yet");
+            assert_eq!(rx.construct(*byte), Err(flem::Status::PacketBuilding));
+        }
+
+        for (index, byte) in wire.iter().enumerate().skip(10) {
+            assert_eq!(rx.expected_remaining(), Some(wire.len() - index));
+            let _ = rx.construct(*byte);
+        }
+        assert!(rx.is_complete());
+        assert_eq!(rx.expected_remaining(), Some(0));
+
+        // Bulk read the rest once the length is known
+        rx.reset_lazy();
+        assert_eq!(
+            rx.construct_slice(&wire[..12]).err(),
+            Some(flem::Status::PacketBuilding)
+        );
+        let remaining = rx.expected_remaining().unwrap();
+        assert_eq!(remaining, wire.len() - 12);
+        assert_eq!(rx.construct_slice(&wire[12..12 + remaining]), Ok(remaining));
+
+        // Failed packets have no expected length
+        let mut bad = wire.to_vec();
+        let last = bad.len() - 1;
+        bad[last] ^= 0xFF;
+        rx.reset_lazy();
+        assert_eq!(
+            rx.construct_slice(&bad).err(),
+            Some(flem::Status::ChecksumError)
+        );
+        assert_eq!(rx.expected_remaining(), None);
+    }
+
+    #[test]
+    fn fmt_write_into_payload() {
+        use core::fmt::Write;
+
+        let mut packet = flem::Packet::<12>::new();
+        packet.add_data(b"ab").unwrap();
+
+        // Exactly fills the remaining 10 bytes
+        write!(packet, "T={} C={}", 123, 45).unwrap();
+        assert_eq!(packet.get_data(), *b"abT=123 C=45");
+        assert_eq!(packet.length(), flem::FLEM_HEADER_SIZE + 12);
+
+        // Full, nothing else fits
+        assert!(write!(packet, "{}", 1).is_err());
+        assert_eq!(packet.get_status(), flem::Status::PacketOverflow);
+        assert_eq!(packet.length(), flem::FLEM_HEADER_SIZE + 12);
+
+        // Text written before the overflow is kept
+        let mut packet = flem::Packet::<8>::new();
+        write!(packet, "T={}", 21).unwrap();
+        assert!(write!(packet, " degrees").is_err());
+        assert_eq!(packet.get_status(), flem::Status::PacketOverflow);
+        assert_eq!(&packet.get_data()[..4], b"T=21");
+        assert_eq!(packet.length(), flem::FLEM_HEADER_SIZE + 4);
+
+        packet.pack();
+        let mut rx = flem::Packet::<8>::new();
+        assert!(rx.construct_slice(packet.bytes()).is_ok());
+        assert_eq!(&rx.bytes()[flem::FLEM_HEADER_SIZE..], b"T=21");
+    }
+
+    #[test]
+    fn add_iter_and_extend() {
+        let mut packet = flem::Packet::<10>::new();
+        packet.add_iter((0..4).map(|i| i * 2)).unwrap();
+        packet.extend([8u8, 10]);
+        assert_eq!(packet.get_status(), flem::Status::Ok);
+        assert_eq!(&packet.get_data()[..6], &[0, 2, 4, 6, 8, 10]);
+
+        // Exactly fills
+        packet.add_iter([1u8; 4]).unwrap();
+        assert_eq!(packet.length(), flem::FLEM_HEADER_SIZE + 10);
+
+        // Overflow keeps what was added before
+        let mut packet = flem::Packet::<10>::new();
+        assert_eq!(packet.add_iter(0..20u8), Err(flem::Status::PacketOverflow));
+        assert_eq!(packet.length(), flem::FLEM_HEADER_SIZE + 10);
+        assert_eq!(packet.get_data(), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+        let mut packet = flem::Packet::<10>::new();
+        packet.extend(0..20u8);
+        assert_eq!(packet.get_status(), flem::Status::PacketOverflow);
+        assert_eq!(packet.length(), flem::FLEM_HEADER_SIZE + 10);
+
+        // Peer limit applies
+        let mut packet = flem::Packet::<10>::new();
+        packet.set_peer_limit(3);
+        assert_eq!(packet.add_iter(0..5u8), Err(flem::Status::PacketOverflow));
+        assert_eq!(packet.length(), flem::FLEM_HEADER_SIZE + 3);
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0xF, &[1, 2, 3, 4]).unwrap();
+        assert_eq!(tx.checksum_mismatch(), None);
+
+        let mut corrupt = tx.bytes().to_vec();
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 0x10;
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(
+            rx.construct_slice(&corrupt).err(),
+            Some(flem::Status::ChecksumError)
+        );
+
+        let mut expected = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        expected.pack_data(0xF, &[1, 2, 3, 4 ^ 0x10]).unwrap();
+
+        let (computed, received) = rx.checksum_mismatch().unwrap();
+        assert_eq!(
+            received,
+            tx.get_checksum(),
+            "Received is the checksum on the wire"
+        );
+        assert_eq!(
+            computed,
+            expected.get_checksum(),
+            "Computed covers the corrupt byte"
+        );
+        assert_ne!(computed, received);
+    }
+
+    #[test]
+    fn copy_payload_to() {
+        let payload: Vec<u8> = (1..=12).collect();
+
+        let mut built = flem::Packet::<1024>::new();
+        built.add_data(&payload).unwrap();
+        built.pack();
+
+        let mut rx = flem::Packet::<1024>::new();
+        rx.construct_slice(built.bytes()).unwrap();
+
+        for packet in [&built, &rx] {
+            let mut out = [0u8; 16];
+            assert_eq!(packet.copy_payload_to(&mut out), Ok(12));
+            assert_eq!(&out[..12], &payload[..]);
+            assert_eq!(&out[12..], &[0; 4], "Only the payload is copied");
+            assert_eq!(packet.payload(), &payload[..]);
+
+            let mut exact = [0u8; 12];
+            assert_eq!(packet.copy_payload_to(&mut exact), Ok(12));
+
+            let mut short = [0xEEu8; 11];
+            assert_eq!(
+                packet.copy_payload_to(&mut short),
+                Err(flem::Status::BufferTooSmall)
+            );
+            assert_eq!(short, [0xEE; 11], "Untouched on error");
+        }
+
+        let empty = flem::Packet::<1024>::new();
+        assert_eq!(empty.copy_payload_to(&mut []), Ok(0));
+    }
+
+    #[test]
+    fn packet_header_parse() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.set_request(0x1234);
+        tx.set_response(flem::response::BUSY);
+        tx.add_data(&[9, 8, 7, 6]).unwrap();
+        tx.pack();
+
+        // Trailing bytes of the next frame are ignored
+        let mut wire = tx.bytes().to_vec();
+        wire.extend_from_slice(&[0xAB; 5]);
+
+        let header = flem::PacketHeader::parse(&wire).unwrap();
+        assert_eq!(header.header, flem::FLEM_HEADER);
+        assert_eq!(header.checksum, tx.get_checksum());
+        assert_eq!(header.request, 0x1234);
+        assert_eq!(header.response, flem::response::BUSY);
+        assert_eq!(header.length, 4);
+        assert_eq!(header.payload_offset, flem::FLEM_HEADER_SIZE);
+        assert_eq!(header.wire_len(), tx.wire_len());
+        assert_eq!(header.payload(&wire), &[9, 8, 7, 6]);
+
+        // Short slices
+        assert_eq!(
+            flem::PacketHeader::parse(&wire[..flem::FLEM_HEADER_SIZE - 1]),
+            Err(flem::Status::InvalidDataLengthDetected)
+        );
+        assert_eq!(
+            flem::PacketHeader::parse(&wire[..tx.wire_len() - 1]),
+            Err(flem::Status::InvalidDataLengthDetected)
+        );
+        assert_eq!(
+            flem::PacketHeader::parse(&[]),
+            Err(flem::Status::InvalidDataLengthDetected)
+        );
+
+        // Not a frame
+        assert_eq!(
+            flem::PacketHeader::parse(&wire[1..]),
+            Err(flem::Status::HeaderBytesNotFound)
+        );
+    }
+
+    #[test]
+    fn construct_recovers_without_reset() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0xF, &[1, 2, 3]).unwrap();
+        let mut corrupt = tx.bytes().to_vec();
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 0xFF;
+
+        // Checksum error, the next byte starts a new packet
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(
+            rx.construct_slice(&corrupt).err(),
+            Some(flem::Status::ChecksumError)
+        );
+        assert!(rx.checksum_mismatch().is_some(), "Failed packet is kept");
+        assert_eq!(rx.construct_slice(tx.bytes()), Ok(tx.wire_len()));
+        assert_eq!(rx, tx);
+
+        // Bytes after a complete packet that wasn't taken overflow once, then recover
+        assert_eq!(rx.construct(0x00), Err(flem::Status::PacketOverflow));
+        assert_eq!(rx.construct(0x00), Err(flem::Status::HeaderBytesNotFound));
+        assert_eq!(rx.construct_slice(tx.bytes()), Ok(tx.wire_len()));
+    }
+
+    #[test]
+    fn construct_soak_random_noise() {
+        // Fixed seed xorshift, no dependency needed
+        let mut state: u32 = 0x1234_5678;
+        let mut random = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        };
+
+        let noise_length = if cfg!(miri) { 20_000 } else { 4_000_000 };
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let mut previous = flem::Status::Ok;
+        for _ in 0..noise_length {
+            let status = match rx.construct(random()) {
+                Ok(_) => flem::Status::PacketReceived,
+                Err(status) => status,
+            };
+            assert!(
+                !(status == flem::Status::PacketOverflow
+                    && previous == flem::Status::PacketOverflow),
+                "Parser is stuck"
+            );
+            previous = status;
+        }
+        assert!(
+            rx.get_header() != 0,
+            "Noise should have started some packets"
+        );
+
+        // A garbage packet may still be in progress, or the payload of an oversized garbage
+        // packet being discarded, and swallow the first copies. Neither is longer than
+        // u16::MAX bytes.
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0xF, &[0x11; 20]).unwrap();
+        let copies = (flem::FLEM_HEADER_SIZE + u16::MAX as usize) / tx.wire_len() + 2;
+
+        let mut received = false;
+        for _ in 0..copies {
+            if rx.construct_slice(tx.bytes()) == Ok(tx.wire_len()) {
+                received = true;
+                break;
+            }
+        }
+        assert!(received, "Packet after the noise should have been received");
+        assert_eq!(rx, tx);
+    }
+
+    #[test]
+    fn raw_mode_without_checksum() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.set_request(0x10);
+        tx.add_data(&[1, 2, 3, 4]).unwrap();
+        tx.pack_raw();
+        assert_eq!(tx.get_header(), flem::FLEM_HEADER);
+        assert_eq!(tx.get_checksum(), 0);
+
+        let mut raw_rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        raw_rx.set_checksum_coverage(flem::ChecksumCoverage::Disabled);
+        assert_eq!(raw_rx.construct_slice(tx.bytes()), Ok(tx.wire_len()));
+        assert_eq!(raw_rx, tx);
+        assert_eq!(raw_rx.checksum_mismatch(), None);
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(
+            rx.construct_slice(tx.bytes()).err(),
+            Some(flem::Status::ChecksumError),
+            "Normal receivers reject raw packets"
+        );
+
+        // pack() with the checksum disabled is the same as pack_raw()
+        let mut raw_tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        raw_tx.set_checksum_coverage(flem::ChecksumCoverage::Disabled);
+        raw_tx.pack_data(0x10, &[1, 2, 3, 4]).unwrap();
+        assert_eq!(raw_tx.get_checksum(), 0);
+        tx.set_response(flem::response::SUCCESS);
+        tx.pack_raw();
+        assert_eq!(raw_tx, tx);
+
+        // Raw receivers don't check, a corrupt byte gets through
+        let mut corrupt = tx.bytes().to_vec();
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 0xFF;
+        raw_rx.reset_lazy();
+        assert_eq!(raw_rx.construct_slice(&corrupt), Ok(corrupt.len()));
+    }
+
+    #[test]
+    fn byte_ring_push_pop() {
+        let mut ring = flem::buffer::ByteRing::<4>::new();
+        assert!(ring.is_empty());
+        assert_eq!(ring.pop(), None);
+
+        // Wraps around the end several times
+        for round in 0..5u8 {
+            for i in 0..4 {
+                ring.push(round * 10 + i).unwrap();
+            }
+            assert!(ring.is_full());
+            assert_eq!(ring.len(), 4);
+            assert_eq!(ring.push(0xFF), Err(0xFF), "Full ring hands the byte back");
+
+            for i in 0..4 {
+                assert_eq!(ring.pop(), Some(round * 10 + i));
+            }
+            assert!(ring.is_empty());
+        }
+
+        // Any size, the ring stays in order while its counters wrap around
+        let mut odd = flem::buffer::ByteRing::<3>::new();
+        for i in 0..100u8 {
+            odd.push(i).unwrap();
+            odd.push(i.wrapping_add(100)).unwrap();
+            assert_eq!(odd.pop(), Some(i));
+            assert_eq!(odd.pop(), Some(i.wrapping_add(100)));
+        }
+        for i in 0..3 {
+            odd.push(i).unwrap();
+        }
+        assert!(odd.is_full());
+        assert_eq!(odd.push(3), Err(3));
+    }
+
+    #[test]
+    fn byte_ring_drain_into_packet() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0xF, &[1, 2, 3]).unwrap();
+        let mut next = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        next.pack_data(0x10, &[4]).unwrap();
+
+        let mut ring = flem::buffer::ByteRing::<64>::new();
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+
+        // Half a packet
+        for byte in &tx.bytes()[..5] {
+            ring.push(*byte).unwrap();
+        }
+        assert_eq!(
+            ring.drain_into_packet(&mut rx),
+            flem::Status::PacketBuilding
+        );
+        assert!(ring.is_empty());
+
+        // The rest and the next packet, the next packet stays in the ring
+        for byte in tx.bytes()[5..].iter().chain(next.bytes()) {
+            ring.push(*byte).unwrap();
+        }
+        assert_eq!(
+            ring.drain_into_packet(&mut rx),
+            flem::Status::PacketReceived
+        );
+        assert_eq!(rx.take().unwrap(), tx);
+        assert_eq!(ring.len(), next.wire_len());
+        assert_eq!(
+            ring.drain_into_packet(&mut rx),
+            flem::Status::PacketReceived
+        );
+        assert_eq!(rx, next);
+    }
+
+    #[test]
+    fn byte_ring_threads() {
+        // Not a power of two, the counters wrap many times
+        let mut ring = flem::buffer::ByteRing::<13>::new();
+        let count: u32 = if cfg!(miri) { 500 } else { 100_000 };
+        let (mut producer, mut consumer) = ring.split();
+
+        std::thread::scope(|scope| {
+            scope.spawn(move || {
+                for i in 0..count {
+                    while producer.push(i as u8).is_err() {
+                        std::thread::yield_now();
+                    }
+                }
+            });
+
+            let mut expected: u32 = 0;
+            while expected < count {
+                match consumer.pop() {
+                    Some(byte) => {
+                        assert_eq!(byte, expected as u8, "Bytes out of order");
+                        expected += 1;
+                    }
+                    None => std::thread::yield_now(),
+                }
+            }
+        });
+        assert!(ring.is_empty());
+    }
+
+    #[test]
+    fn validate_slice() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0xF, &[1, 2, 3, 4, 5]).unwrap();
+        let wire = tx.bytes().to_vec();
+        assert_eq!(flem::validate_slice(&wire), Ok(()));
+
+        let mut empty = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        empty.pack();
+        assert_eq!(flem::validate_slice(empty.bytes()), Ok(()));
+
+        // Flipped checksum
+        let mut flipped = wire.clone();
+        flipped[2] ^= 0x01;
+        assert_eq!(
+            flem::validate_slice(&flipped),
+            Err(flem::Status::ChecksumError)
+        );
+
+        // Flipped payload byte
+        let mut flipped = wire.clone();
+        flipped[flem::FLEM_HEADER_SIZE] ^= 0x80;
+        assert_eq!(
+            flem::validate_slice(&flipped),
+            Err(flem::Status::ChecksumError)
+        );
+
+        // Truncated
+        for length in 0..wire.len() {
+            assert_eq!(
+                flem::validate_slice(&wire[..length]),
+                Err(flem::Status::InvalidDataLengthDetected),
+                "Truncated to {} bytes",
+                length
+            );
+        }
+
+        // Trailing bytes
+        let mut long = wire.clone();
+        long.push(0);
+        assert_eq!(
+            flem::validate_slice(&long),
+            Err(flem::Status::PacketOverflow)
+        );
+
+        // Not a frame
+        assert_eq!(
+            flem::validate_slice(&wire[1..]),
+            Err(flem::Status::HeaderBytesNotFound)
+        );
+    }
+
+    #[cfg(feature = "sim")]
+    #[test]
+    fn loopback_link_faults() {
+        use flem::sim::LoopbackLink;
+        use flem::Status;
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0xF, &[1, 2, 3, 4, 5, 6]).unwrap();
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+
+        // Clean link
+        let statuses = LoopbackLink::new(&tx, &mut rx).run();
+        assert_eq!(statuses.len(), tx.wire_len());
+        assert!(statuses[..statuses.len() - 1]
+            .iter()
+            .all(|status| *status == Status::PacketBuilding));
+        assert_eq!(statuses.last(), Some(&Status::PacketReceived));
+        assert_eq!(rx.take().unwrap(), tx);
+
+        // Corruption, then a successful retry
+        let mut link = LoopbackLink::new(&tx, &mut rx).corrupt_byte_at(flem::FLEM_HEADER_SIZE + 2);
+        assert_eq!(link.run().last(), Some(&Status::ChecksumError));
+        link.clear_faults();
+        assert_eq!(link.run().last(), Some(&Status::PacketReceived));
+        assert_eq!(rx.take().unwrap(), tx);
+
+        // Garbage before the frame is skipped
+        let statuses = LoopbackLink::new(&tx, &mut rx)
+            .inject_garbage(&[0x00, 0x12, 0xFF])
+            .run();
+        assert_eq!(&statuses[..3], &[Status::HeaderBytesNotFound; 3]);
+        assert_eq!(statuses.last(), Some(&Status::PacketReceived));
+        assert_eq!(rx.take().unwrap(), tx);
+
+        // Dropped bytes, the packet never completes on its own
+        let mut link = LoopbackLink::new(&tx, &mut rx).drop_every_nth(7);
+        assert_eq!(link.wire_bytes().len(), tx.wire_len() - tx.wire_len() / 7);
+        let statuses = link.run();
+        assert_ne!(statuses.last(), Some(&Status::PacketReceived));
+        assert!(!link.rx().is_complete());
+        rx.reset_lazy();
+
+        // Garbage after a short frame ends up in the payload
+        let statuses = LoopbackLink::new(&tx, &mut rx)
+            .drop_every_nth(tx.wire_len())
+            .inject_garbage_after(&[0xEE])
+            .run();
+        assert_eq!(statuses.last(), Some(&Status::ChecksumError));
+    }
+
+    /// Same checks as the `construct` fuzz target in `fuzz/`, on streams built to hit the
+    /// edges of the data buffer.
+    fn construct_never_panics<const T: usize>(stream: &[u8]) {
+        let mut packet = flem::Packet::<T>::new();
+        for byte in stream {
+            if packet.construct(*byte).is_ok() {
+                assert!(packet.length() <= flem::FLEM_HEADER_SIZE + T);
+                assert_eq!(flem::validate_slice(packet.bytes()), Ok(()));
+                packet.take().unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn construct_adversarial_lengths() {
+        let mut state: u32 = 0xDEAD_BEEF;
+        let mut random = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+
+        let rounds = if cfg!(miri) { 20 } else { 2_000 };
+        for _ in 0..rounds {
+            // Valid header bytes so the length field is reached, then lengths around the
+            // buffer sizes under test, then noise
+            let mut stream = Vec::new();
+            for _ in 0..4 {
+                stream.extend_from_slice(&flem::FLEM_HEADER.to_le_bytes());
+                for _ in 2..8 {
+                    stream.push(random() as u8);
+                }
+                let length = match random() % 4 {
+                    0 => random() as u16,
+                    1 => (random() % 6) as u16,
+                    2 => u16::MAX,
+                    _ => 64 + (random() % 3) as u16 - 1,
+                };
+                stream.extend_from_slice(&length.to_le_bytes());
+                for _ in 0..(random() % 80) {
+                    stream.push(random() as u8);
+                }
+            }
+
+            construct_never_panics::<0>(&stream);
+            construct_never_panics::<1>(&stream);
+            construct_never_panics::<4>(&stream);
+            construct_never_panics::<64>(&stream);
+            let _ = flem::PacketHeader::parse(&stream);
+            let _ = flem::validate_slice(&stream);
+        }
+    }
+
+    #[test]
+    fn construct_edge_regressions() {
+        // Payload on a packet without a data buffer
+        let mut tx = flem::Packet::<4>::new();
+        tx.pack_data(0xF, &[1]).unwrap();
+        let mut rx = flem::Packet::<0>::new();
+        assert_eq!(
+            rx.construct_slice(tx.bytes()).err(),
+            Some(flem::Status::InvalidDataLengthDetected)
+        );
+
+        // Full buffer, then more bytes without taking the packet
+        let mut tx = flem::Packet::<4>::new();
+        tx.pack_data(0xF, &[1, 2, 3, 4]).unwrap();
+        let mut rx = flem::Packet::<4>::new();
+        assert_eq!(rx.construct_slice(tx.bytes()), Ok(tx.wire_len()));
+        for _ in 0..3 {
+            assert!(rx.construct(0xAB).is_err());
+        }
+        assert_eq!(rx.construct_slice(tx.bytes()), Ok(tx.wire_len()));
+
+        // Receiving into a packet that is half way through get_byte()
+        let mut packet = flem::Packet::<4>::new();
+        packet.pack_data(0xF, &[1, 2, 3, 4]).unwrap();
+        for _ in 0..flem::FLEM_HEADER_SIZE + 2 {
+            packet.get_byte().unwrap();
+        }
+        for byte in tx.bytes().iter().chain(tx.bytes()) {
+            let _ = packet.construct(*byte);
+        }
+        assert!(packet.length() <= flem::FLEM_HEADER_SIZE + 4);
+    }
+
+    #[test]
+    fn correlator_interleaved_requests() {
+        use flem::correlation::{Correlation, Correlator};
+
+        const READ: u16 = 0x10;
+        const WRITE: u16 = 0x11;
+
+        let mut correlator = Correlator::<2>::new();
+
+        // Host sends two reads before either response arrives
+        let mut first = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let first_id = correlator.begin(READ).unwrap();
+        first.pack_data_seq(READ, first_id, &[0x01]).unwrap();
+        let mut second = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let second_id = correlator.begin(READ).unwrap();
+        second.pack_data_seq(READ, second_id, &[0x02]).unwrap();
+        assert_ne!(first_id, second_id);
+        assert_eq!(
+            correlator.begin(WRITE).err(),
+            Some(flem::Status::PacketOverflow)
+        );
+
+        // Device echoes the transaction ID and answers with the address read, out of order
+        let respond = |request: &flem::Packet<FLEM_PACKET_SIZE>| {
+            let mut response = flem::Packet::<FLEM_PACKET_SIZE>::new();
+            let payload = &request.bytes()[flem::FLEM_HEADER_SIZE..];
+            response
+                .pack_data_seq(request.get_request(), payload[0], &[payload[1] * 10])
+                .unwrap();
+            response
+        };
+        let second_response = respond(&second);
+        let first_response = respond(&first);
+
+        assert_eq!(
+            correlator.complete(&second_response),
+            Correlation::Tagged {
+                transaction: second_id,
+                request: READ
+            }
+        );
+        assert_eq!(second_response.get_transaction(), Some(second_id));
+        assert_eq!(second_response.bytes()[flem::FLEM_HEADER_SIZE + 1], 20);
+
+        assert_eq!(
+            correlator.complete(&first_response),
+            Correlation::Tagged {
+                transaction: first_id,
+                request: READ
+            }
+        );
+        assert_eq!(first_response.bytes()[flem::FLEM_HEADER_SIZE + 1], 10);
+        assert_eq!(correlator.outstanding(), 0);
+
+        // Duplicate response is an orphan
+        assert_eq!(correlator.complete(&first_response), Correlation::Orphan);
+        assert_eq!(correlator.orphans(), 1);
+    }
+
+    #[test]
+    fn correlator_untagged_peer() {
+        use flem::correlation::{Correlation, Correlator};
+
+        let mut correlator = Correlator::<4>::new();
+        let read = correlator.begin(0x10).unwrap();
+        let write = correlator.begin(0x11).unwrap();
+
+        // Peer that doesn't know about transaction IDs, matched on the request code
+        let mut response = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        response.pack_data(0x11, &[0xFF, 0xFF]).unwrap();
+        assert_eq!(
+            correlator.complete(&response),
+            Correlation::Untagged {
+                transaction: write,
+                request: 0x11
+            }
+        );
+
+        let mut response = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        response.pack_data(0x10, &[]).unwrap();
+        assert_eq!(response.get_transaction(), None);
+        assert_eq!(
+            correlator.complete(&response),
+            Correlation::Untagged {
+                transaction: read,
+                request: 0x10
+            }
+        );
+
+        // Ambiguous, two of the same request in flight and no tag
+        correlator.begin(0x10).unwrap();
+        let cancelled = correlator.begin(0x10).unwrap();
+        assert_eq!(correlator.complete(&response), Correlation::Orphan);
+        assert!(correlator.cancel(cancelled));
+        assert!(!correlator.cancel(cancelled));
+        assert!(matches!(
+            correlator.complete(&response),
+            Correlation::Untagged { .. }
+        ));
+
+        // The transaction ID has to come first
+        let mut packet = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        packet.set_transaction(7).unwrap();
+        assert_eq!(
+            packet.set_transaction(8).err(),
+            Some(flem::Status::InvalidDataLengthDetected)
+        );
+        assert_eq!(packet.get_transaction(), Some(7));
+
+        // IDs in flight are skipped when the counter wraps
+        let mut correlator = Correlator::<2>::new();
+        let held = correlator.begin(0x1).unwrap();
+        for _ in 0..300 {
+            let id = correlator.begin(0x2).unwrap();
+            assert_ne!(id, held);
+            assert!(correlator.cancel(id));
+        }
+    }
+
+    #[test]
+    fn correlator_out_of_transaction_ids() {
+        use flem::correlation::Correlator;
+
+        // More slots than u8 transaction IDs, begin fails instead of searching forever
+        let mut correlator = Correlator::<300>::new();
+        for _ in 0..256 {
+            correlator.begin(0x10).unwrap();
+        }
+        assert_eq!(correlator.begin(0x10), Err(flem::Status::PacketOverflow));
+        assert_eq!(correlator.outstanding(), 256);
+
+        assert!(correlator.cancel(42));
+        assert_eq!(correlator.begin(0x10), Ok(42));
+    }
+
+    #[cfg(feature = "heapless")]
+    #[test]
+    fn heapless_packet_wire_compatible() {
+        use flem::heapless_packet::HeaplessPacket;
+
+        let payload: Vec<u8> = (0..30).collect();
+
+        let mut packet = flem::Packet::<32>::new();
+        packet.set_request(0x42);
+        packet.set_response(flem::response::BUSY);
+        packet.add_data(&payload).unwrap();
+        packet.pack();
+
+        let mut heapless_packet = HeaplessPacket::<32>::new();
+        heapless_packet.set_request(0x42);
+        heapless_packet.set_response(flem::response::BUSY);
+        heapless_packet.add_data(&payload[..10]).unwrap();
+        heapless_packet.add_data(&payload[10..]).unwrap();
+
+        let mut wire = [0u8; flem::FLEM_HEADER_SIZE + 32];
+        let length = heapless_packet.write_wire(&mut wire).unwrap();
+        assert_eq!(length, packet.wire_len());
+        assert_eq!(&wire[..length], packet.bytes(), "Same bytes on the wire");
+        assert_eq!(flem::validate_slice(&wire[..length]), Ok(()));
+
+        assert_eq!(heapless_packet.to_packet(), packet);
+        assert_eq!(HeaplessPacket::from(&packet), heapless_packet);
+
+        // Empty payload
+        let empty = HeaplessPacket::<32>::new();
+        let mut reference = flem::Packet::<32>::new();
+        reference.pack();
+        let length = empty.write_wire(&mut wire).unwrap();
+        assert_eq!(&wire[..length], reference.bytes());
+
+        // Output buffer too small
+        assert_eq!(
+            heapless_packet.write_wire(&mut wire[..flem::FLEM_HEADER_SIZE + 29]),
+            Err(flem::Status::BufferTooSmall)
+        );
+    }
+
+    #[cfg(feature = "heapless")]
+    #[test]
+    fn heapless_packet_overflow() {
+        use flem::heapless_packet::HeaplessPacket;
+
+        let mut packet = HeaplessPacket::<8>::new();
+        packet.add_data(&[1; 6]).unwrap();
+        assert_eq!(packet.add_data(&[2; 3]), Err(flem::Status::PacketOverflow));
+        assert_eq!(packet.payload(), &[1; 6], "Untouched on overflow");
+        packet.add_data(&[2; 2]).unwrap();
+        assert_eq!(packet.wire_len(), flem::FLEM_HEADER_SIZE + 8);
+        assert_eq!(packet.add_data(&[3]), Err(flem::Status::PacketOverflow));
+
+        packet.clear();
+        assert!(packet.payload().is_empty());
+    }
+
+    #[test]
+    fn well_known_codes_round_trip() {
+        use flem::{WellKnownRequest, WellKnownResponse};
+
+        assert_eq!(
+            WellKnownRequest::try_from(flem::request::ID),
+            Ok(WellKnownRequest::Id)
+        );
+        assert_eq!(u16::from(WellKnownRequest::Id), flem::request::ID);
+        assert_eq!(
+            WellKnownRequest::try_from(flem::request::PROTOCOL),
+            Ok(WellKnownRequest::Protocol)
+        );
+        assert_eq!(
+            u16::from(WellKnownRequest::Protocol),
+            flem::request::PROTOCOL
+        );
+        assert_eq!(
+            WellKnownRequest::try_from(flem::request::LOG),
+            Ok(WellKnownRequest::Log)
+        );
+        assert_eq!(u16::from(WellKnownRequest::Log), flem::request::LOG);
+        assert_eq!(
+            WellKnownRequest::try_from(flem::request::HEARTBEAT),
+            Ok(WellKnownRequest::Heartbeat)
+        );
+        for code in flem::request::DFU_ERASE..=flem::request::DFU_BOOT {
+            let request = WellKnownRequest::try_from(code).unwrap();
+            assert_eq!(u16::from(request), code);
+        }
+        assert_eq!(WellKnownRequest::try_from(0x10), Err(0x10));
+
+        for code in [
+            flem::response::ASYNC,
+            flem::response::SUCCESS,
+            flem::response::BUSY,
+            flem::response::NOT_READY,
+            flem::response::INVALID_ARGUMENT,
+            flem::response::OUT_OF_RANGE,
+            flem::response::UNKNOWN_REQUEST,
+            flem::response::CHECKSUM_ERROR,
+        ] {
+            let kind = WellKnownResponse::try_from(code).unwrap();
+            assert_eq!(u16::from(kind), code);
+        }
+        assert_eq!(WellKnownResponse::try_from(0x1234), Err(0x1234));
+
+        let mut packet = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        packet.pack_data(0x10, &[]).unwrap();
+        assert_eq!(packet.response_kind(), Ok(WellKnownResponse::Success));
+        packet.set_response(0x0042);
+        assert_eq!(packet.response_kind(), Err(0x0042));
+
+        assert!(flem::request::is_reserved(0x0000));
+        assert!(flem::request::is_reserved(0x000F));
+        assert!(!flem::request::is_reserved(0x0010));
+
+        // Well known requests in the reserved range are fine
+        packet.set_request(flem::request::ID);
+        packet.set_request(0x0010);
+
+        // Responses echo whatever the partner sent, the pack functions don't check
+        packet.pack_data(0x000C, &[1]).unwrap();
+        packet
+            .pack_error_code(0x000C, flem::response::UNKNOWN_REQUEST)
+            .unwrap();
+        assert_eq!(packet.get_request(), 0x000C);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "reserved")]
+    fn set_request_reserved_code_panics() {
+        let mut packet = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        packet.set_request(0x000C);
+    }
+
+    #[test]
+    fn getters_on_unaligned_packet() {
+        // The u8 in front puts the packet, and every u16 field in it, at an odd address.
+        // Run with `cargo +nightly miri test` to check the fields are never read through
+        // an unaligned reference.
+        #[repr(C)]
+        struct Offset {
+            pad: u8,
+            packet: flem::Packet<8>,
+        }
+
+        let mut offset = Offset {
+            pad: 0xA5,
+            packet: flem::Packet::new(),
+        };
+        offset.packet.pack_data(0x1234, &[1, 2, 3]).unwrap();
+
+        let packet = &offset.packet;
+        assert_eq!(offset.pad, 0xA5);
+        assert_eq!(packet.get_header(), flem::FLEM_HEADER);
+        assert_eq!(packet.get_request(), 0x1234);
+        assert_eq!(packet.get_response(), flem::response::SUCCESS);
+        assert_eq!(packet.get_length(), 3);
+        let bytes = packet.bytes();
+        assert_eq!(
+            packet.get_checksum(),
+            u16::from_le_bytes([bytes[2], bytes[3]])
+        );
+        assert!(format!("{:?}", packet).contains("request: 4660"));
+    }
+
+    #[test]
+    fn pack_error_without_detail() {
+        let mut packet = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        packet.pack_data(0x10, &[1, 2, 3]).unwrap();
+
+        packet.pack_error_code(0x10, flem::response::BUSY).unwrap();
+        assert_eq!(packet.get_request(), 0x10);
+        assert_eq!(packet.get_response(), flem::response::BUSY);
+        assert_eq!(packet.get_length(), 0);
+        assert!(packet.validate());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn pack_error_deprecated_data_argument() {
+        let mut packet = flem::Packet::<FLEM_PACKET_SIZE>::new();
+
+        packet.pack_error(0x10, flem::response::BUSY, &[]).unwrap();
+        assert_eq!(packet.get_response(), flem::response::BUSY);
+        assert_eq!(packet.get_length(), 0);
+
+        packet
+            .pack_error(0x10, flem::response::OUT_OF_RANGE, &[7])
+            .unwrap();
+        assert_eq!(packet.get_response(), flem::response::OUT_OF_RANGE);
+        assert_eq!(packet.payload(), &[7]);
+        assert!(packet.validate());
+    }
+
+    #[test]
+    fn pack_error_with_detail() {
+        let mut packet = flem::Packet::<4>::new();
+
+        packet
+            .pack_error_with_detail(0x10, flem::response::OUT_OF_RANGE, &[7, 8])
+            .unwrap();
+        assert_eq!(packet.get_response(), flem::response::OUT_OF_RANGE);
+        assert_eq!(packet.payload(), &[7, 8]);
+        assert!(packet.validate());
+
+        assert_eq!(
+            packet.pack_error_with_detail(0x11, flem::response::BUSY, &[0; 5]),
+            Err(flem::Status::PacketOverflow)
+        );
+        assert_eq!(packet.get_request(), 0);
+        assert_eq!(packet.get_response(), 0);
+        assert_eq!(packet.get_length(), 0);
+        assert_eq!(packet.get_data(), [0; 4]);
+    }
+
+    #[test]
+    fn as_full_bytes_covers_header_and_buffer() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(
+            tx.as_full_bytes().len(),
+            flem::FLEM_HEADER_SIZE + FLEM_PACKET_SIZE
+        );
+
+        tx.pack_data(0x10, &[0xAB; 5]).unwrap();
+        let region = tx.as_full_bytes();
+        assert_eq!(region.len(), flem::FLEM_HEADER_SIZE + FLEM_PACKET_SIZE);
+        assert_eq!(&region[..tx.length()], tx.bytes());
+    }
+
+    #[test]
+    fn new_uninit_data_exposes_only_initialized_bytes() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new_uninit_data();
+        assert_eq!(tx.initialized_len(), 0);
+        assert_eq!(tx.as_full_bytes().len(), flem::FLEM_HEADER_SIZE);
+        assert_eq!(tx.get_data(), [0; FLEM_PACKET_SIZE]);
+
+        tx.pack_data(0x10, &[0xAB; 5]).unwrap();
+        assert_eq!(tx.initialized_len(), 5);
+        assert_eq!(tx.as_full_bytes(), tx.bytes());
+        assert_eq!(&tx.get_data()[..6], &[0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0]);
+
+        tx.add_iter([1, 2]).unwrap();
+        tx.pack();
+        assert_eq!(tx.initialized_len(), 7);
+
+        // A received frame is zeroed up to its length before the payload arrives
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new_uninit_data();
+        let bytes = tx.bytes();
+        for byte in &bytes[..flem::FLEM_HEADER_SIZE] {
+            let _ = rx.construct(*byte);
+        }
+        assert_eq!(rx.initialized_len(), 7);
+        assert_eq!(rx.payload(), &[0; 7]);
+        assert!(rx.construct_slice(&bytes[flem::FLEM_HEADER_SIZE..]).is_ok());
+        assert_eq!(rx.payload(), &[0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 1, 2]);
+
+        rx[0] = 0xCD;
+        assert_eq!(rx.payload()[0], 0xCD);
+
+        rx.reset();
+        assert_eq!(rx.initialized_len(), FLEM_PACKET_SIZE);
+    }
+
+    #[test]
+    fn construct_detailed_reports_checksums() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0x10, &[0x11, 0x22, 0x33, 0x44]).unwrap();
+        let good = tx.get_checksum();
+        let bad = good ^ 0x5A5A;
+
+        let mut wire = tx.bytes().to_vec();
+        wire[2..4].copy_from_slice(&bad.to_le_bytes());
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let mut error = None;
+        for byte in wire.iter() {
+            if let Err(e) = rx.construct_detailed(*byte) {
+                if e.status != flem::Status::PacketBuilding {
+                    error = Some(e);
+                }
+            }
+        }
+
+        let error = error.expect("The corrupted checksum should be reported");
+        assert_eq!(error.status, flem::Status::ChecksumError);
+        assert_eq!(error.byte_index as usize, wire.len() - 1);
+        assert_eq!(error.expected_checksum, Some(good));
+        assert_eq!(error.received_checksum, Some(bad));
+        assert_eq!(flem::Status::from(error), flem::Status::ChecksumError);
+    }
+
+    #[test]
+    fn construct_detailed_reports_position() {
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let header = flem::FLEM_HEADER.to_le_bytes();
+
+        assert_eq!(rx.construct_detailed(header[0]).unwrap_err().byte_index, 0);
+        let error = rx.construct_detailed(!header[1]).unwrap_err();
+        assert_eq!(error.status, flem::Status::HeaderBytesNotFound);
+        assert_eq!(error.byte_index, 1);
+        assert_eq!(error.expected_checksum, None);
+        assert_eq!(error.received_checksum, None);
+
+        let error = rx.construct_slice_detailed(&header).unwrap_err();
+        assert_eq!(error.status, flem::Status::PacketBuilding);
+        assert_eq!(error.byte_index, 2);
+    }
+
+    #[cfg(feature = "cobs")]
+    #[test]
+    fn cobs_recovers_after_sync_word_noise() {
+        use flem::cobs;
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let header = flem::FLEM_HEADER.to_le_bytes();
+        let payload = [header[0], header[1], 0x55, 0x55, 0x00, header[0], header[1]];
+        tx.pack_data(0x10, &payload).unwrap();
+
+        let mut frame = [0_u8; cobs::max_encoded_len(flem::FLEM_HEADER_SIZE + FLEM_PACKET_SIZE)];
+        let frame_length = tx.cobs_encode(&mut frame).unwrap();
+        assert_eq!(frame_length, cobs::encode_into(&tx, &mut [0; 128]).unwrap());
+
+        // Half a frame and a burst of sync words, then the delimiter and a good frame
+        let mut stream = Vec::new();
+        stream.extend_from_slice(&frame[..frame_length / 2]);
+        stream.extend_from_slice(&[header[0], header[1], header[0], header[1]]);
+        stream.push(cobs::COBS_DELIMITER);
+        stream.extend_from_slice(&frame[..frame_length]);
+
+        let mut receiver = cobs::CobsReceiver::<FLEM_PACKET_SIZE>::new();
+        let mut received = 0;
+        let mut failed = 0;
+        for byte in stream {
+            match receiver.push(byte) {
+                Ok(_) => received += 1,
+                Err(flem::Status::PacketBuilding) => {}
+                Err(_) => failed += 1,
+            }
+        }
+
+        assert_eq!(failed, 1, "Only the broken frame should fail");
+        assert_eq!(received, 1, "The frame after the noise should be received");
+        assert_eq!(receiver.packet().payload(), &payload);
+    }
+
+    #[test]
+    fn checksum_known_answers() {
+        use flem::checksum::{Checksum, ChecksumAlgorithm, Crc16Ccitt, Crc16Ibm};
+
+        let check = b"123456789";
+
+        let mut ibm = Crc16Ibm::default();
+        ibm.update(&check[..4]);
+        ibm.update(&check[4..]);
+        assert_eq!(ibm.finalize(), 0xBB3D);
+        ibm.reset();
+        assert_eq!(ibm.finalize(), 0);
+
+        let mut ccitt = Crc16Ccitt::default();
+        ccitt.update(check);
+        assert_eq!(ccitt.finalize(), 0x29B1);
+        ccitt.reset();
+        ccitt.update(check);
+        assert_eq!(ccitt.finalize(), 0x29B1);
+
+        assert_eq!(ChecksumAlgorithm::Crc16Ibm.compute(&[check]), 0xBB3D);
+        assert_eq!(
+            ChecksumAlgorithm::Crc16Ccitt.compute(&[&check[..2], &check[2..]]),
+            0x29B1
+        );
+        assert_eq!(
+            ChecksumAlgorithm::custom::<Crc16Ccitt>().compute(&[check]),
+            0x29B1
+        );
+    }
+
+    #[test]
+    fn checksum_default_matches_crc16_ibm() {
+        // Bitwise CRC-16/IBM, independent of the table driven implementation
+        fn reference(bytes: &[u8]) -> u16 {
+            let mut crc = 0_u16;
+            for byte in bytes {
+                crc ^= *byte as u16;
+                for _ in 0..8 {
+                    crc = if crc & 1 != 0 {
+                        (crc >> 1) ^ 0xA001
+                    } else {
+                        crc >> 1
+                    };
+                }
+            }
+            crc
+        }
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let payload: Vec<u8> = (0..FLEM_PACKET_SIZE).map(|i| (i * 7) as u8).collect();
+        tx.pack_data(0x1234, &payload).unwrap();
+        assert_eq!(tx.get_checksum(), reference(&tx.bytes()[4..]));
+    }
+
+    #[test]
+    fn checksum_ccitt_round_trip() {
+        use flem::checksum::ChecksumAlgorithm;
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.set_checksum_algorithm(ChecksumAlgorithm::Crc16Ccitt);
+        tx.pack_data(0x10, &[0x55; 10]).unwrap();
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        rx.set_checksum_algorithm(ChecksumAlgorithm::Crc16Ccitt);
+        assert_eq!(rx.construct_slice(tx.bytes()), Ok(tx.length()));
+        assert_eq!(rx.payload(), &[0x55; 10]);
+
+        // A partner on the default algorithm rejects the packet
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(
+            rx.construct_slice(tx.bytes()),
+            Err(flem::Status::ChecksumError)
+        );
+    }
+
+    #[test]
+    fn header_complete_flips_after_header() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0x10, &[1, 2, 3, 4]).unwrap();
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert!(!rx.header_complete());
+        for (i, byte) in tx.bytes().iter().enumerate() {
+            let status = rx.construct(*byte);
+            assert_eq!(
+                rx.header_complete(),
+                i + 1 >= flem::FLEM_HEADER_SIZE,
+                "Wrong header_complete after byte {}",
+                i
+            );
+            if i + 1 == flem::FLEM_HEADER_SIZE {
+                assert_eq!(rx.expected_remaining(), Some(4));
+            }
+            if i + 1 == tx.length() {
+                assert_eq!(status, Ok(()));
+            }
+        }
+
+        rx.reset_lazy();
+        assert!(!rx.header_complete());
+    }
+
+    #[test]
+    fn packet32_round_trip() {
+        use flem::packet32::{crc32, Packet32, FLEM32_HEADER_SIZE};
+
+        assert_eq!(crc32(&[b"123456789"]), 0xCBF4_3926);
+        assert_eq!(crc32(&[b"1234", b"56789"]), 0xCBF4_3926);
+
+        const SIZE: usize = 4096;
+        let payload: Vec<u8> = (0..SIZE).map(|i| (i * 31) as u8).collect();
+        let mut tx = Packet32::<SIZE>::new();
+        tx.pack_data(0x10, &payload).unwrap();
+        assert_eq!(tx.length(), FLEM32_HEADER_SIZE + SIZE);
+        assert!(tx.validate());
+
+        let mut wire = vec![0_u8; tx.length()];
+        assert_eq!(
+            tx.write_wire(&mut wire[..10]),
+            Err(flem::Status::BufferTooSmall)
+        );
+        assert_eq!(tx.write_wire(&mut wire), Ok(tx.length()));
+
+        let mut rx = Packet32::<SIZE>::new();
+        assert_eq!(rx.construct_slice(&wire), Ok(wire.len()));
+        assert_eq!(rx.get_request(), 0x10);
+        assert_eq!(rx.get_response(), flem::response::SUCCESS);
+        assert_eq!(rx.get_checksum(), tx.get_checksum());
+        assert_eq!(rx.payload(), &payload[..]);
+
+        wire[FLEM32_HEADER_SIZE + 1000] ^= 0x01;
+        assert_eq!(rx.construct_slice(&wire), Err(flem::Status::ChecksumError));
+
+        // The next frame is received after the error
+        wire[FLEM32_HEADER_SIZE + 1000] ^= 0x01;
+        assert_eq!(rx.construct_slice(&wire), Ok(wire.len()));
+    }
+
+    #[test]
+    fn packet32_get_byte() {
+        use flem::packet32::Packet32;
+
+        let mut tx = Packet32::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+        let mut wire = [0_u8; 64];
+        let length = tx.write_wire(&mut wire).unwrap();
+
+        let sent: Vec<u8> = core::iter::from_fn(|| tx.get_byte().ok()).collect();
+        assert_eq!(sent, &wire[..length]);
+        assert_eq!(tx.get_status(), flem::Status::GetByteFinished);
+
+        // Packing again starts the transmit cursor over
+        tx.pack();
+        let mut rx = Packet32::<FLEM_PACKET_SIZE>::new();
+        let mut received = false;
+        while let Ok(byte) = tx.get_byte() {
+            received = rx.construct(byte).is_ok();
+        }
+        assert!(received);
+        assert_eq!(rx.payload(), &[1, 2, 3]);
+
+        #[cfg(feature = "protocol-v2")]
+        {
+            tx.set_flags(flem::flags::MORE_FRAGMENTS);
+            tx.pack();
+            while let Ok(byte) = tx.get_byte() {
+                let _ = rx.construct(byte);
+            }
+            assert_eq!(rx.get_flags(), flem::flags::MORE_FRAGMENTS);
+        }
+    }
+
+    #[test]
+    fn packet32_cross_mode_fails_fast() {
+        use flem::packet32::{Packet32, FLEM32_HEADER_SIZE};
+
+        let mut tx16 = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx16.pack_data(0x10, &[1, 2, 3]).unwrap();
+        let mut rx32 = Packet32::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(
+            rx32.construct(tx16.bytes()[0]),
+            Err(flem::Status::PacketBuilding)
+        );
+        assert_eq!(
+            rx32.construct(tx16.bytes()[1]),
+            Err(flem::Status::HeaderBytesNotFound)
+        );
+
+        let mut tx32 = Packet32::<FLEM_PACKET_SIZE>::new();
+        tx32.pack_data(0x10, &[1, 2, 3]).unwrap();
+        let mut wire = [0_u8; FLEM32_HEADER_SIZE + FLEM_PACKET_SIZE];
+        let length = tx32.write_wire(&mut wire).unwrap();
+        let mut rx16 = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(rx16.construct(wire[0]), Err(flem::Status::PacketBuilding));
+        assert_eq!(
+            rx16.construct(wire[1]),
+            Err(flem::Status::HeaderBytesNotFound)
+        );
+        assert!(rx16.construct_slice(&wire[..length]).is_err());
+
+        // A length field larger than the data buffer
+        let mut small = Packet32::<2>::new();
+        assert_eq!(
+            small.construct_slice(&wire[..length]),
+            Err(flem::Status::InvalidDataLengthDetected)
+        );
+    }
+
+    #[test]
+    fn zero_length_packet_get_byte_round_trip() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(flem::request::ID, &[]).unwrap();
+        assert_eq!(tx.length(), flem::FLEM_HEADER_SIZE);
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let mut sent = 0;
+        let mut received = None;
+        while let Ok(byte) = tx.get_byte() {
+            sent += 1;
+            received = Some(rx.construct(byte));
+        }
+
+        assert_eq!(sent, flem::FLEM_HEADER_SIZE);
+        assert_eq!(tx.get_status(), flem::Status::GetByteFinished);
+        assert_eq!(received, Some(Ok(())));
+        assert_eq!(rx.get_status(), flem::Status::PacketReceived);
+        assert!(
+            rx.validate(),
+            "Checksum should validate on the receiving side"
+        );
+        assert_eq!(rx.get_request(), flem::request::ID);
+        assert_eq!(rx.get_length(), 0);
+        assert!(rx.payload().is_empty());
+    }
+
+    #[test]
+    fn crc16_matches_bitwise_reference() {
+        use flem::checksum::{Checksum, Crc16Ibm};
+
+        // Runs with and without features = ["small-crc"], both CRC tables must match this
+        fn reference(bytes: &[u8]) -> u16 {
+            let mut crc = 0_u16;
+            for byte in bytes {
+                crc ^= *byte as u16;
+                for _ in 0..8 {
+                    crc = if crc & 1 != 0 {
+                        (crc >> 1) ^ 0xA001
+                    } else {
+                        crc >> 1
+                    };
+                }
+            }
+            crc
+        }
+
+        // xorshift, so the corpus is the same on every run
+        let mut state = 0x2545_F491_u32;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+
+        let mut corpus: Vec<Vec<u8>> = vec![vec![], vec![0x00], vec![0xFF], vec![0x55]];
+        for _ in 0..200 {
+            let length = (next() % 300) as usize;
+            corpus.push((0..length).map(|_| next() as u8).collect());
+        }
+
+        for bytes in corpus.iter() {
+            let mut crc = Crc16Ibm::default();
+            crc.update(bytes);
+            assert_eq!(
+                crc.finalize(),
+                reference(bytes),
+                "CRC mismatch for {:?}",
+                bytes
+            );
+        }
+    }
+
+    #[test]
+    fn pack_async_round_trip() {
+        const EVENT_BUTTON: u16 = 0x20;
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_async(EVENT_BUTTON, &[1, 0]).unwrap();
+        assert!(tx.is_async());
+        assert!(!tx.is_success());
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(rx.construct_slice(tx.bytes()), Ok(tx.length()));
+        assert!(rx.is_async());
+        assert_eq!(rx.response_kind(), Ok(flem::WellKnownResponse::Async));
+        assert_eq!(rx.get_request(), EVENT_BUTTON);
+        assert_eq!(rx.payload(), &[1, 0]);
+
+        tx.pack_data(EVENT_BUTTON, &[1, 0]).unwrap();
+        rx.reset_lazy();
+        assert_eq!(rx.construct_slice(tx.bytes()), Ok(tx.length()));
+        assert!(!rx.is_async());
+        assert!(rx.is_success());
+
+        let mut small = flem::Packet::<1>::new();
+        assert_eq!(
+            small.pack_async(EVENT_BUTTON, &[1, 0]),
+            Err(flem::Status::PacketOverflow)
+        );
+    }
+
+    #[test]
+    fn from_bytes_concatenated_frames() {
+        let mut first = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        first.pack_data(0x10, &[0x55, 0x55, 1]).unwrap();
+        let mut second = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        second.pack_error_code(0x11, flem::response::BUSY).unwrap();
+
+        let mut buffer = Vec::new();
+        buffer.extend_from_slice(first.bytes());
+        buffer.extend_from_slice(second.bytes());
+        buffer.extend_from_slice(&[0xDE, 0xAD]);
+
+        let (packet, used) = flem::Packet::<FLEM_PACKET_SIZE>::from_bytes(&buffer).unwrap();
+        assert_eq!(used, first.length());
+        assert_eq!(packet, first);
+
+        let rest = &buffer[used..];
+        let (packet, used) = flem::Packet::<FLEM_PACKET_SIZE>::from_bytes(rest).unwrap();
+        assert_eq!(used, second.length());
+        assert_eq!(packet.get_response(), flem::response::BUSY);
+
+        let rest = &rest[used..];
+        assert_eq!(rest, &[0xDE, 0xAD]);
+        assert_eq!(
+            flem::Packet::<FLEM_PACKET_SIZE>::from_bytes(rest).unwrap_err(),
+            flem::Status::HeaderBytesNotFound
+        );
+
+        // Truncated frame
+        let truncated = &first.bytes()[..first.length() - 1];
+        assert_eq!(
+            flem::Packet::<FLEM_PACKET_SIZE>::from_bytes(truncated).unwrap_err(),
+            flem::Status::PacketBuilding
+        );
+        assert_eq!(
+            flem::Packet::<FLEM_PACKET_SIZE>::from_bytes(&[]).unwrap_err(),
+            flem::Status::PacketBuilding
+        );
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "before pack()")]
+    fn get_byte_before_pack_panics() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.set_request(0x10);
+        tx.add_data(&[1, 2, 3]).unwrap();
+        let _ = tx.get_byte();
+    }
+
+    #[test]
+    fn packet_try_from_and_eq() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0x10, &[1, 2, 3, 4]).unwrap();
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        for byte in tx.bytes() {
+            let _ = rx.construct(*byte);
+        }
+        assert!(tx == rx, "Received packet should equal the transmitted one");
+
+        let parsed = flem::Packet::<FLEM_PACKET_SIZE>::try_from(tx.bytes()).unwrap();
+        assert!(parsed == tx);
+
+        let mut trailing = tx.bytes().to_vec();
+        trailing.push(0);
+        assert_eq!(
+            flem::Packet::<FLEM_PACKET_SIZE>::try_from(&trailing[..]).unwrap_err(),
+            flem::Status::PacketOverflow
+        );
+
+        let mut changed = tx.bytes().to_vec();
+        changed[flem::FLEM_HEADER_SIZE] ^= 0x01;
+        assert_eq!(
+            flem::Packet::<FLEM_PACKET_SIZE>::try_from(&changed[..]).unwrap_err(),
+            flem::Status::ChecksumError
+        );
+
+        rx.set_response(0x1234);
+        assert!(tx != rx, "Packets with different responses should differ");
+        let mut rx = parsed;
+        rx.add_data(&[5]).unwrap();
+        assert!(tx != rx, "Packets with different payloads should differ");
+    }
+
+    #[test]
+    fn mutation_after_pack_is_dirty() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert!(!tx.is_dirty());
+        tx.pack_data(0x10, &[1, 2]).unwrap();
+        assert!(!tx.is_dirty());
+
+        tx.add_data(&[3]).unwrap();
+        assert!(tx.is_dirty());
+        assert!(!tx.validate(), "The stored checksum is stale");
+        tx.pack();
+        assert!(!tx.is_dirty());
+
+        tx.set_request(0x11);
+        assert!(tx.is_dirty());
+        tx.pack();
+        tx.set_response(flem::response::BUSY);
+        assert!(tx.is_dirty());
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        rx.set_checksum_coverage(flem::ChecksumCoverage::PayloadOnly);
+        tx.pack();
+        assert_eq!(rx.construct_slice(tx.bytes()), Ok(tx.length()));
+        assert!(!rx.is_dirty(), "A received packet matches its checksum");
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "changed after pack()")]
+    fn get_byte_dirty_panics() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0x10, &[1, 2]).unwrap();
+        tx.add_data(&[3]).unwrap();
+        let _ = tx.get_byte();
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    #[test]
+    fn flags_round_trip_and_checksum() {
+        use flem::flags;
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.set_flag(flags::NO_RESPONSE | flags::COMPRESSED, true);
+        tx.set_flag(flags::FRAGMENT, true);
+        tx.set_flag(flags::FRAGMENT, false);
+        tx.set_request(0x10);
+        tx.add_data(&[1, 2, 3]).unwrap();
+        tx.pack();
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(rx.construct_slice(tx.bytes()), Ok(tx.length()));
+        assert!(rx.has_flag(flags::NO_RESPONSE));
+        assert!(rx.has_flag(flags::COMPRESSED));
+        assert!(rx.has_flag(flags::NO_RESPONSE | flags::COMPRESSED));
+        assert!(!rx.has_flag(flags::FRAGMENT));
+        assert!(!rx.has_flag(flags::MORE_FRAGMENTS));
+
+        // The flags are covered by the checksum
+        let mut wire = tx.bytes().to_vec();
+        wire[flem::FLEM_HEADER_SIZE - 1] ^= flags::FRAGMENT;
+        rx.reset_lazy();
+        assert_eq!(rx.construct_slice(&wire), Err(flem::Status::ChecksumError));
+    }
+
+    #[test]
+    fn other_protocol_version_is_rejected() {
+        // A frame of the version this build doesn't use, built by hand
+        #[cfg(not(feature = "protocol-v2"))]
+        let mut frame = vec![0x55, 0xAA, 0x00, 0x00, 0x10, 0x00, 0x01, 0x00, 0x02, 0x00];
+        #[cfg(not(feature = "protocol-v2"))]
+        frame.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // Sequence, flags and channel
+        #[cfg(feature = "protocol-v2")]
+        let mut frame = vec![0x55, 0x55, 0x00, 0x00, 0x10, 0x00, 0x01, 0x00, 0x02, 0x00];
+        frame.extend_from_slice(&[0x01, 0x02]);
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(rx.construct(frame[0]), Err(flem::Status::PacketBuilding));
+        assert_eq!(
+            rx.construct(frame[1]),
+            Err(flem::Status::HeaderBytesNotFound)
+        );
+        for byte in &frame[2..] {
+            assert_ne!(
+                rx.construct(*byte),
+                Ok(()),
+                "The frame must not be received"
+            );
+        }
+        assert!(!rx.is_complete());
+    }
+
+    #[test]
+    fn data_id_try_from() {
+        use flem::{DataId, DataIdError};
+
+        let id = DataId::new("Try from", 1, 2, 3, 64);
+        let mut packet = flem::Packet::<64>::new();
+        packet.pack_id(&id, true).unwrap();
+        let bytes = packet.payload();
+
+        let Ok(parsed) = DataId::try_from(bytes) else {
+            panic!("The ID should decode");
+        };
+        assert_eq!(parsed.get_name(), id.get_name());
+        assert_eq!(parsed.get_max_packet_size(), 64);
+        assert_eq!(parsed.get_major(), 1);
+
+        let short = &bytes[..DataId::<25>::SIZE - 1];
+        assert_eq!(
+            DataId::<25>::try_from(short).err(),
+            Some(DataIdError::TooShort)
+        );
+        assert!(DataId::from(short).is_none());
+
+        let mut zero_size = bytes.to_vec();
+        zero_size[3] = 0;
+        zero_size[4] = 0;
+        assert_eq!(
+            DataId::<25>::try_from(&zero_size[..]).err(),
+            Some(DataIdError::ZeroPacketSize)
+        );
+        assert_eq!(
+            flem::Status::from(DataIdError::ZeroPacketSize),
+            flem::Status::InvalidDataLengthDetected
+        );
+
+        fn decode(data: &[u8]) -> Result<u16, DataIdError> {
+            let id = DataId::<25>::try_from(data)?;
+            Ok(id.get_max_packet_size())
+        }
+        assert_eq!(decode(bytes), Ok(64));
+    }
+
+    #[test]
+    fn client_engine_scripted() {
+        use flem::client::{Engine, EngineEvent};
+        use flem::dispatch::Dispatcher;
+
+        const ECHO: u16 = 0x10;
+        fn echo(
+            rx: &flem::Packet<FLEM_PACKET_SIZE>,
+            tx: &mut flem::Packet<FLEM_PACKET_SIZE>,
+        ) -> Result<(), flem::Status> {
+            tx.pack_data(rx.get_request(), rx.payload())
+        }
+
+        let mut dispatcher = Dispatcher::<FLEM_PACKET_SIZE, 2>::new();
+        dispatcher.register(ECHO, echo).unwrap();
+        let mut engine = Engine::new(dispatcher);
+        engine.set_byte_budget(4);
+
+        let echo_request = flem::Packet::<FLEM_PACKET_SIZE>::with_payload(ECHO, &[9; 12]).unwrap();
+        let unknown_request = flem::Packet::<FLEM_PACKET_SIZE>::with_payload(0x11, &[]).unwrap();
+        let mut corrupt = flem::Packet::<FLEM_PACKET_SIZE>::with_payload(ECHO, &[1, 2]).unwrap();
+        corrupt.add_data(&[3]).unwrap(); // Stale checksum
+
+        let mut script = Vec::new();
+        script.extend_from_slice(&[0x00, 0x13]); // Noise
+        script.extend_from_slice(echo_request.bytes());
+        script.extend_from_slice(unknown_request.bytes());
+        script.extend_from_slice(corrupt.bytes());
+        let mut incoming = script.into_iter();
+
+        let mut outgoing = Vec::new();
+        let mut events = Vec::new();
+        let mut refuse = false;
+        for _ in 0..200 {
+            let event = engine.poll(
+                || incoming.next(),
+                |byte| {
+                    // A transmit FIFO that is full every other byte
+                    refuse = !refuse;
+                    if refuse {
+                        return false;
+                    }
+                    outgoing.push(byte);
+                    true
+                },
+            );
+            if event != EngineEvent::Idle {
+                events.push(event);
+            }
+        }
+
+        assert_eq!(
+            events,
+            [
+                EngineEvent::RequestHandled(ECHO),
+                EngineEvent::RequestHandled(0x11),
+                EngineEvent::Error(flem::Status::ChecksumError),
+            ]
+        );
+        assert!(!engine.is_sending());
+
+        let mut responses = Vec::new();
+        let mut rest = &outgoing[..];
+        while !rest.is_empty() {
+            let (packet, used) = flem::Packet::<FLEM_PACKET_SIZE>::from_bytes(rest).unwrap();
+            responses.push(packet);
+            rest = &rest[used..];
+        }
+        assert_eq!(responses.len(), 3);
+        assert!(responses[0] == echo_request);
+        assert_eq!(responses[1].get_request(), 0x11);
+        assert_eq!(responses[1].get_response(), flem::response::UNKNOWN_REQUEST);
+        assert_eq!(responses[2].get_request(), ECHO);
+        assert_eq!(responses[2].get_response(), flem::response::CHECKSUM_ERROR);
+    }
+
+    #[test]
+    fn packet_pool_exhaustion() {
+        use flem::buffer::PacketPool;
+        use flem::checksum::ChecksumAlgorithm;
+
+        let pool = PacketPool::<FLEM_PACKET_SIZE, 3>::new();
+        assert_eq!(pool.available(), 3);
+
+        let mut guards = Vec::new();
+        while let Some(mut packet) = pool.acquire() {
+            let request = 0x10 + guards.len() as u16;
+            packet.pack_data(request, &[guards.len() as u8]).unwrap();
+            guards.push(packet);
+        }
+        assert_eq!(guards.len(), 3);
+        assert_eq!(pool.available(), 0);
+        assert!(pool.acquire().is_none());
+
+        // A dropped guard makes its packet available again
+        let dropped = guards.remove(1);
+        assert_eq!(dropped.get_request(), 0x11);
+        drop(dropped);
+        assert_eq!(pool.available(), 1);
+
+        let mut packet = pool.acquire().unwrap();
+        assert_eq!(
+            packet.get_request(),
+            0,
+            "Reused packets don't keep the request"
+        );
+        assert_eq!(
+            packet.get_response(),
+            0,
+            "Reused packets don't keep the response"
+        );
+        assert_eq!(packet.get_length(), 0);
+        assert!(packet.payload().is_empty());
+        assert!(pool.acquire().is_none());
+
+        packet.pack_data(0x20, &[1]).unwrap();
+        assert_eq!(guards[1].get_request(), 0x12, "Other packets are untouched");
+
+        // Settings changed by one user are gone for the next
+        packet.set_sync_word(0xA55A);
+        packet.set_checksum_algorithm(ChecksumAlgorithm::Crc16Ccitt);
+        packet.set_checksum_coverage(flem::ChecksumCoverage::IncludeHeader);
+        packet.set_peer_limit(4);
+        #[cfg(feature = "protocol-v2")]
+        {
+            packet.set_sequence(7);
+            packet.set_channel(3);
+            packet.set_more_fragments(true);
+        }
+        packet.pack();
+        drop(packet);
+
+        let mut packet = pool.acquire().unwrap();
+        assert_eq!(packet.get_header(), 0);
+        assert_eq!(packet.get_checksum(), 0);
+        assert_eq!(packet.get_sync_word(), flem::FLEM_HEADER);
+        assert!(matches!(
+            packet.get_checksum_algorithm(),
+            ChecksumAlgorithm::Crc16Ibm
+        ));
+        assert_eq!(
+            packet.get_checksum_coverage(),
+            flem::ChecksumCoverage::PayloadOnly
+        );
+        assert_eq!(packet.get_peer_limit(), u16::MAX);
+        #[cfg(feature = "protocol-v2")]
+        {
+            assert_eq!(packet.get_sequence(), 0);
+            assert_eq!(packet.get_channel(), 0);
+            assert_eq!(packet.get_flags(), 0);
+        }
+        packet.pack_data(0x20, &[1]).unwrap();
+        assert_eq!(packet.get_header(), flem::FLEM_HEADER);
+
+        drop(packet);
+        drop(guards);
+        assert_eq!(pool.available(), 3);
+    }
+
+    #[test]
+    fn host_requests_against_client_engine() {
+        use flem::dispatch::Dispatcher;
+        use flem::host::Host;
+        use flem::{DataId, Packet, Status};
+
+        const ECHO: u16 = 0x10;
+
+        type Rx = Packet<FLEM_PACKET_SIZE>;
+
+        fn echo(rx: &Rx, tx: &mut Rx) -> Result<(), Status> {
+            tx.pack_data(rx.get_request(), rx.payload())
+        }
+
+        fn id(_rx: &Rx, tx: &mut Rx) -> Result<(), Status> {
+            tx.pack_id(&DataId::new("Loopback", 1, 2, 3, FLEM_PACKET_SIZE), true)
+        }
+
+        let mut dispatcher = Dispatcher::<FLEM_PACKET_SIZE, 4>::new();
+        dispatcher.register(ECHO, echo).unwrap();
+        dispatcher.register(flem::request::ID, id).unwrap();
+        let mut channel = Loopback::new(dispatcher);
+
+        let mut host = Host::<FLEM_PACKET_SIZE>::new();
+        let rx = host.request(&mut channel, ECHO, &[1, 2, 3]).unwrap();
+        assert_eq!(rx.get_request(), ECHO);
+        assert_eq!(rx.payload(), &[1, 2, 3]);
+
+        let id = host.request_id(&mut channel).unwrap();
+        assert_eq!(id.get_major(), 1);
+        assert_eq!(id.get_max_packet_size(), FLEM_PACKET_SIZE as u16);
+
+        assert_eq!(
+            host.request(&mut channel, 0x20, &[]).err(),
+            Some(Status::UnrecognizedRequest)
+        );
+
+        channel.corrupt = true;
+        assert_eq!(
+            host.request(&mut channel, ECHO, &[4, 5]).err(),
+            Some(Status::ChecksumError)
+        );
+
+        // The link still works after the corrupted response
+        let rx = host.request(&mut channel, ECHO, &[6]).unwrap();
+        assert_eq!(rx.payload(), &[6]);
+    }
+
+    #[test]
+    fn read_fields_bounds_checked() {
+        use flem::Status;
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.add_data(&[0xFF; 8]).unwrap();
+        tx.pack_data(0x10, &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06])
+            .unwrap();
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert!(rx.construct_slice(tx.bytes()).is_ok());
+
+        for packet in [&tx, &rx] {
+            // Exactly at the boundary
+            assert_eq!(packet.read_u16_le(4), Ok(0x0605));
+            assert_eq!(packet.read_u32_le(2), Ok(0x0605_0403));
+            assert_eq!(packet.read_bytes(6, 0), Ok(&[][..]));
+            assert_eq!(packet.read_bytes(0, 6).unwrap(), &[1, 2, 3, 4, 5, 6]);
+
+            // One past it, the stale bytes of the data buffer aren't readable
+            assert_eq!(packet.read_u16_le(5), Err(Status::PayloadUnderflow));
+            assert_eq!(packet.read_u32_le(3), Err(Status::PayloadUnderflow));
+            assert_eq!(packet.read_f32_le(3), Err(Status::PayloadUnderflow));
+            assert_eq!(packet.read_bytes(7, 0), Err(Status::PayloadUnderflow));
+            assert_eq!(
+                packet.read_bytes(1, usize::MAX),
+                Err(Status::PayloadUnderflow)
+            );
+        }
+
+        tx.pack_data(0x10, &1.5f32.to_le_bytes()).unwrap();
+        assert_eq!(tx.read_f32_le(0), Ok(1.5));
+
+        // Zero length payload
+        tx.pack_data(0x10, &[]).unwrap();
+        assert!(tx.read_bytes(0, 0).unwrap().is_empty());
+        assert_eq!(tx.read_u16_le(0), Err(Status::PayloadUnderflow));
+        assert_eq!(tx.read_bytes(0, 1), Err(Status::PayloadUnderflow));
+    }
+
+    #[test]
+    fn read_typed_fields() {
+        use flem::Status;
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.add_data(&[0xAB]).unwrap();
+        tx.add_data(&(-2i16).to_le_bytes()).unwrap();
+        tx.add_data(&(-300_000i32).to_le_bytes()).unwrap();
+        tx.add_data(&u64::MAX.to_le_bytes()).unwrap();
+        tx.add_data(&(-5i64).to_le_bytes()).unwrap();
+        tx.add_data(&0.25f64.to_le_bytes()).unwrap();
+        tx.pack();
+
+        assert_eq!(tx.read_u8(0), Ok(0xAB));
+        assert_eq!(tx.read_i8(0), Ok(-85));
+        assert_eq!(tx.read_i16_le(1), Ok(-2));
+        assert_eq!(tx.read_u16_le(1), Ok(0xFFFE));
+        assert_eq!(tx.read_i32_le(3), Ok(-300_000));
+        assert_eq!(tx.read_u64_le(7), Ok(u64::MAX));
+        assert_eq!(tx.read_i64_le(15), Ok(-5));
+        assert_eq!(tx.read_f64_le(23), Ok(0.25));
+
+        assert_eq!(tx.get_length(), 31);
+        assert_eq!(tx.read_u8(31), Err(Status::PayloadUnderflow));
+        assert_eq!(tx.read_i16_le(30), Err(Status::PayloadUnderflow));
+        assert_eq!(tx.read_i32_le(28), Err(Status::PayloadUnderflow));
+        assert_eq!(tx.read_u64_le(24), Err(Status::PayloadUnderflow));
+        assert_eq!(tx.read_f64_le(usize::MAX), Err(Status::PayloadUnderflow));
+    }
+
+    #[test]
+    fn write_typed_fields() {
+        use flem::Status;
+
+        let mut tx = flem::Packet::<24>::new();
+        tx.write_u8(0xAB).unwrap();
+        tx.write_i8(-1).unwrap();
+        tx.write_u16_le(0x1234).unwrap();
+        tx.write_i16_le(-2).unwrap();
+        tx.write_u32_le(0xDEAD_BEEF).unwrap();
+        tx.write_i32_le(-300_000).unwrap();
+        tx.write_f32_le(1.5).unwrap();
+        assert_eq!(tx.get_length(), 18);
+
+        // An 8 byte value doesn't fit the 6 bytes left and adds nothing
+        assert_eq!(tx.write_u64_le(1), Err(Status::PacketOverflow));
+        assert_eq!(tx.write_f64_le(1.0), Err(Status::PacketOverflow));
+        assert_eq!(tx.get_length(), 18);
+        tx.pack();
+
+        let mut rx = flem::Packet::<24>::new();
+        assert!(rx.construct_slice(tx.bytes()).is_ok());
+        assert_eq!(rx.read_u8(0), Ok(0xAB));
+        assert_eq!(rx.read_i8(1), Ok(-1));
+        assert_eq!(rx.read_u16_le(2), Ok(0x1234));
+        assert_eq!(rx.read_i16_le(4), Ok(-2));
+        assert_eq!(rx.read_u32_le(6), Ok(0xDEAD_BEEF));
+        assert_eq!(rx.read_i32_le(10), Ok(-300_000));
+        assert_eq!(rx.read_f32_le(14), Ok(1.5));
+        assert_eq!(&rx.payload()[2..4], &[0x34, 0x12]);
+
+        let mut wide = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        wide.write_u64_le(u64::MAX - 1).unwrap();
+        wide.write_i64_le(-5).unwrap();
+        wide.write_f64_le(-0.125).unwrap();
+        wide.pack();
+        assert_eq!(wide.read_u64_le(0), Ok(u64::MAX - 1));
+        assert_eq!(wide.read_i64_le(8), Ok(-5));
+        assert_eq!(wide.read_f64_le(16), Ok(-0.125));
+    }
+
+    #[test]
+    fn packets_and_pools_in_statics() {
+        use flem::buffer::PacketPool;
+
+        static PACKETS: [flem::Packet<FLEM_PACKET_SIZE>; 2] = [flem::Packet::new(); 2];
+
+        let mut tx = PACKETS[1];
+        assert!(tx.get_data().iter().all(|byte| *byte == 0));
+        assert_eq!(tx.get_length(), 0);
+        tx.pack_data(0x10, &[1, 2]).unwrap();
+
+        let mut rx = PACKETS[0];
+        assert!(rx.construct_slice(tx.bytes()).is_ok());
+        assert_eq!(rx.payload(), &[1, 2]);
+
+        let pool: PacketPool<FLEM_PACKET_SIZE, 2> = const { PacketPool::new() };
+        assert_eq!(pool.available(), 2);
+        assert!(pool.acquire().is_some());
+    }
+
+    #[test]
+    fn finalize_matches_get_byte() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.set_request(0x10);
+        tx.add_data(&[9; 100]).unwrap();
+        assert!(tx.is_dirty());
+
+        let frame = tx.finalize().to_vec();
+        assert!(!tx.is_dirty());
+        assert_eq!(tx.get_header(), flem::FLEM_HEADER);
+        assert_eq!(frame.len(), tx.length());
+
+        let mut sent = Vec::new();
+        while let Ok(byte) = tx.get_byte() {
+            sent.push(byte);
+        }
+        assert_eq!(frame, sent);
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(rx.construct_slice(&frame), Ok(frame.len()));
+        assert_eq!(rx.payload(), &[9; 100]);
+    }
+
+    #[cfg(feature = "critical-section")]
+    #[test]
+    fn packet_pool_shared_between_threads() {
+        use flem::buffer::PacketPool;
+
+        static POOL: PacketPool<FLEM_PACKET_SIZE, 4> = PacketPool::new();
+
+        let threads: Vec<_> = (0..4)
+            .map(|index| {
+                std::thread::spawn(move || {
+                    for _ in 0..100 {
+                        let mut packet = loop {
+                            if let Some(packet) = POOL.acquire() {
+                                break packet;
+                            }
+                        };
+                        assert_eq!(packet.get_length(), 0);
+                        packet.pack_data(0x10, &[index; 8]).unwrap();
+                        assert_eq!(packet.payload(), &[index; 8]);
+                    }
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(POOL.available(), 4);
+    }
+
+    #[test]
+    fn host_negotiates_with_new_and_old_clients() {
+        use flem::dispatch::Dispatcher;
+        use flem::host::Host;
+        use flem::{Packet, ProtocolInfo, Status};
+
+        type Rx = Packet<FLEM_PACKET_SIZE>;
+
+        fn echo(rx: &Rx, tx: &mut Rx) -> Result<(), Status> {
+            tx.pack_data(rx.get_request(), rx.payload())
+        }
+
+        fn protocol(_rx: &Rx, tx: &mut Rx) -> Result<(), Status> {
+            tx.pack_protocol_info(&ProtocolInfo::new(64))
+        }
+
+        let client = |with_protocol: bool| {
+            let mut dispatcher = Dispatcher::<FLEM_PACKET_SIZE, 4>::new();
+            dispatcher.register(0x10, echo).unwrap();
+            if with_protocol {
+                dispatcher
+                    .register(flem::request::PROTOCOL, protocol)
+                    .unwrap();
+            }
+            Loopback::new(dispatcher)
+        };
+
+        let mut host = Host::<FLEM_PACKET_SIZE>::new();
+
+        let mut new_client = client(true);
+        let info = host.negotiate(&mut new_client).unwrap().unwrap();
+        assert_eq!(info.wire_version, flem::FLEM_PROTOCOL_VERSION);
+        assert_eq!(info.max_packet_size, 64);
+        assert_eq!(host.peer(), Some(info));
+        assert_eq!(host.tx().get_peer_limit(), 64);
+        assert_eq!(
+            host.request(&mut new_client, 0x10, &[0; 65]).err(),
+            Some(Status::PacketOverflow),
+            "Requests are limited to the client's max packet size"
+        );
+        assert!(host.request(&mut new_client, 0x10, &[0; 64]).is_ok());
+
+        let mut old_client = client(false);
+        assert_eq!(host.negotiate(&mut old_client), Ok(None));
+        assert_eq!(host.peer(), None);
+        assert_eq!(host.tx().get_peer_limit(), u16::MAX);
+        let rx = host.request(&mut old_client, 0x10, &[1, 2]).unwrap();
+        assert_eq!(rx.payload(), &[1, 2]);
+
+        let info = ProtocolInfo::new(300);
+        let mut payload = info.to_bytes().to_vec();
+        assert_eq!(
+            ProtocolInfo::from_payload(&payload[..4]),
+            Err(Status::InvalidDataLengthDetected)
+        );
+        payload.push(0xAA);
+        assert_eq!(
+            ProtocolInfo::from_payload(&payload),
+            Ok(info),
+            "Appended fields are ignored"
+        );
+    }
+
+    #[test]
+    fn reset_rx_recovers_after_a_bad_frame() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0x10, &[1, 2, 3, 4]).unwrap();
+
+        let mut corrupted = tx.bytes().to_vec();
+        corrupted[flem::FLEM_HEADER_SIZE + 1] ^= 0xFF;
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(
+            rx.construct_slice(&corrupted),
+            Err(flem::Status::ChecksumError)
+        );
+
+        // Stop in the middle of the next frame, then give up on it
+        assert_eq!(
+            rx.construct_slice(&tx.bytes()[..6]),
+            Err(flem::Status::PacketBuilding)
+        );
+        let checksum = rx.get_checksum();
+        rx.reset_rx();
+        assert_eq!(rx.get_header(), 0);
+        assert_eq!(rx.get_length(), 0);
+        assert_eq!(rx.get_status(), flem::Status::Ok);
+        assert_eq!(rx.get_checksum(), checksum, "The checksum isn't touched");
+
+        assert_eq!(rx.construct_slice(tx.bytes()), Ok(tx.length()));
+        assert_eq!(rx.payload(), &[1, 2, 3, 4]);
+        assert_eq!(rx.get_request(), 0x10);
+    }
+
+    #[test]
+    fn reset_rx_stops_discarding_oversized() {
+        let mut oversized = flem::Packet::<64>::new();
+        oversized.pack_data(0x10, &[0x55; 64]).unwrap();
+
+        let mut tx = flem::Packet::<16>::new();
+        tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+
+        let mut rx = flem::Packet::<16>::new();
+        assert_eq!(
+            rx.construct_slice(oversized.bytes()),
+            Err(flem::Status::InvalidDataLengthDetected)
+        );
+        assert_eq!(rx.construct(0x55), Err(flem::Status::DiscardingOversized));
+
+        // Giving up on the oversized packet, the next frame is received right away
+        rx.reset_rx();
+        assert_eq!(rx.construct_slice(tx.bytes()), Ok(tx.length()));
+        assert_eq!(rx.payload(), &[1, 2, 3]);
+        assert_eq!(rx.get_oversized_length(), 64);
+    }
+
+    #[test]
+    fn payload_strings() {
+        use flem::Status;
+
+        // "é" is 2 bytes, the third one doesn't fit the 5 bytes left
+        let mut tx = flem::Packet::<8>::new();
+        tx.add_str("abc").unwrap();
+        tx.add_str("éé").unwrap();
+        assert_eq!(tx.get_length(), 7);
+        assert_eq!(tx.add_str("é"), Err(Status::PacketOverflow));
+        assert_eq!(tx.get_length(), 7, "A character is never split");
+        tx.add_str("!").unwrap();
+        tx.pack();
+        assert_eq!(tx.payload_as_str(), Ok("abcéé!"));
+
+        let mut rx = flem::Packet::<8>::new();
+        assert!(rx.construct_slice(tx.bytes()).is_ok());
+        assert_eq!(rx.payload_as_str(), Ok("abcéé!"));
+
+        // The NUL has to fit too
+        let mut tx = flem::Packet::<8>::new();
+        assert_eq!(tx.add_str_nul("12345678"), Err(Status::PacketOverflow));
+        assert_eq!(tx.add_str_nul("1234567é"), Err(Status::PacketOverflow));
+        assert_eq!(tx.get_length(), 0);
+        tx.add_str_nul("1234é").unwrap();
+        assert_eq!(tx.get_length(), 7);
+        tx.pack();
+        assert_eq!(tx.payload_as_str_nul(), Ok("1234é"));
+        assert_eq!(tx.payload(), "1234é\0".as_bytes());
+
+        // Invalid sequences: a lone continuation byte and a truncated character
+        tx.pack_data(0x10, &[b'a', 0x80, b'b']).unwrap();
+        assert_eq!(tx.payload_as_str(), Err(Status::InvalidUtf8));
+        assert_eq!(tx.payload_as_str_nul(), Err(Status::InvalidUtf8));
+        tx.pack_data(0x10, &[b'o', b'k', 0, 0xC3]).unwrap();
+        assert_eq!(tx.payload_as_str(), Err(Status::InvalidUtf8));
+        assert_eq!(
+            tx.payload_as_str_nul(),
+            Ok("ok"),
+            "Bytes after the NUL aren't checked"
+        );
+
+        tx.pack_data(0x10, &[]).unwrap();
+        assert_eq!(tx.payload_as_str(), Ok(""));
+        assert_eq!(tx.payload_as_str_nul(), Ok(""));
+    }
+
+    #[test]
+    fn generated_crc_table_matches_literal() {
+        // The table FLEM used to hardcode
+        const LITERAL: [u16; 256] = [
+            0x0000, 0xc0c1, 0xc181, 0x0140, 0xc301, 0x03c0, 0x0280, 0xc241, 0xc601, 0x06c0, 0x0780,
+            0xc741, 0x0500, 0xc5c1, 0xc481, 0x0440, 0xcc01, 0x0cc0, 0x0d80, 0xcd41, 0x0f00, 0xcfc1,
+            0xce81, 0x0e40, 0x0a00, 0xcac1, 0xcb81, 0x0b40, 0xc901, 0x09c0, 0x0880, 0xc841, 0xd801,
+            0x18c0, 0x1980, 0xd941, 0x1b00, 0xdbc1, 0xda81, 0x1a40, 0x1e00, 0xdec1, 0xdf81, 0x1f40,
+            0xdd01, 0x1dc0, 0x1c80, 0xdc41, 0x1400, 0xd4c1, 0xd581, 0x1540, 0xd701, 0x17c0, 0x1680,
+            0xd641, 0xd201, 0x12c0, 0x1380, 0xd341, 0x1100, 0xd1c1, 0xd081, 0x1040, 0xf001, 0x30c0,
+            0x3180, 0xf141, 0x3300, 0xf3c1, 0xf281, 0x3240, 0x3600, 0xf6c1, 0xf781, 0x3740, 0xf501,
+            0x35c0, 0x3480, 0xf441, 0x3c00, 0xfcc1, 0xfd81, 0x3d40, 0xff01, 0x3fc0, 0x3e80, 0xfe41,
+            0xfa01, 0x3ac0, 0x3b80, 0xfb41, 0x3900, 0xf9c1, 0xf881, 0x3840, 0x2800, 0xe8c1, 0xe981,
+            0x2940, 0xeb01, 0x2bc0, 0x2a80, 0xea41, 0xee01, 0x2ec0, 0x2f80, 0xef41, 0x2d00, 0xedc1,
+            0xec81, 0x2c40, 0xe401, 0x24c0, 0x2580, 0xe541, 0x2700, 0xe7c1, 0xe681, 0x2640, 0x2200,
+            0xe2c1, 0xe381, 0x2340, 0xe101, 0x21c0, 0x2080, 0xe041, 0xa001, 0x60c0, 0x6180, 0xa141,
+            0x6300, 0xa3c1, 0xa281, 0x6240, 0x6600, 0xa6c1, 0xa781, 0x6740, 0xa501, 0x65c0, 0x6480,
+            0xa441, 0x6c00, 0xacc1, 0xad81, 0x6d40, 0xaf01, 0x6fc0, 0x6e80, 0xae41, 0xaa01, 0x6ac0,
+            0x6b80, 0xab41, 0x6900, 0xa9c1, 0xa881, 0x6840, 0x7800, 0xb8c1, 0xb981, 0x7940, 0xbb01,
+            0x7bc0, 0x7a80, 0xba41, 0xbe01, 0x7ec0, 0x7f80, 0xbf41, 0x7d00, 0xbdc1, 0xbc81, 0x7c40,
+            0xb401, 0x74c0, 0x7580, 0xb541, 0x7700, 0xb7c1, 0xb681, 0x7640, 0x7200, 0xb2c1, 0xb381,
+            0x7340, 0xb101, 0x71c0, 0x7080, 0xb041, 0x5000, 0x90c1, 0x9181, 0x5140, 0x9301, 0x53c0,
+            0x5280, 0x9241, 0x9601, 0x56c0, 0x5780, 0x9741, 0x5500, 0x95c1, 0x9481, 0x5440, 0x9c01,
+            0x5cc0, 0x5d80, 0x9d41, 0x5f00, 0x9fc1, 0x9e81, 0x5e40, 0x5a00, 0x9ac1, 0x9b81, 0x5b40,
+            0x9901, 0x59c0, 0x5880, 0x9841, 0x8801, 0x48c0, 0x4980, 0x8941, 0x4b00, 0x8bc1, 0x8a81,
+            0x4a40, 0x4e00, 0x8ec1, 0x8f81, 0x4f40, 0x8d01, 0x4dc0, 0x4c80, 0x8c41, 0x4400, 0x84c1,
+            0x8581, 0x4540, 0x8701, 0x47c0, 0x4680, 0x8641, 0x8201, 0x42c0, 0x4380, 0x8341, 0x4100,
+            0x81c1, 0x8081, 0x4040,
+        ];
+
+        let generated = flem::checksum::build_crc16_table();
+        for (index, (generated, literal)) in generated.iter().zip(LITERAL.iter()).enumerate() {
+            assert_eq!(generated, literal, "Entry {} differs", index);
+        }
+        assert_eq!(generated.len(), LITERAL.len());
+    }
+
+    #[test]
+    fn router_forwards_by_request_range() {
+        use flem::host::ByteChannel;
+        use flem::router::Router;
+        use flem::{Packet, Status};
+
+        #[derive(Default)]
+        struct Bus(Vec<u8>);
+
+        impl ByteChannel for Bus {
+            fn write(&mut self, bytes: &[u8]) -> Result<(), Status> {
+                self.0.extend_from_slice(bytes);
+                Ok(())
+            }
+
+            fn read(&mut self) -> Option<u8> {
+                None
+            }
+        }
+
+        let mut router = Router::<FLEM_PACKET_SIZE, 4>::new();
+        router.add_rule(0x10..=0x1F, 0).unwrap();
+        router.add_rule(0x20..=0x2F, 1).unwrap();
+
+        let mut first = Bus::default();
+        let mut second = Bus::default();
+        let mut tx = Packet::<FLEM_PACKET_SIZE>::new();
+
+        let mut expected = [Vec::new(), Vec::new()];
+        for (request, output) in [(0x10, 0), (0x2F, 1), (0x1A, 0)] {
+            let mut sent = Packet::<FLEM_PACKET_SIZE>::new();
+            sent.pack_data(request, &[request as u8; 5]).unwrap();
+            sent.set_response(0x1234);
+            sent.pack();
+
+            let mut rx = Packet::<FLEM_PACKET_SIZE>::new();
+            assert!(rx.construct_slice(sent.bytes()).is_ok());
+
+            let mut outputs: [&mut dyn ByteChannel; 2] = [&mut first, &mut second];
+            assert_eq!(router.forward(&rx, &mut tx, &mut outputs), Ok(Some(output)));
+            expected[output].extend_from_slice(sent.bytes());
+        }
+        assert_eq!(first.0, expected[0], "Forwarded byte for byte");
+        assert_eq!(second.0, expected[1], "Forwarded byte for byte");
+
+        // Unmatched requests are answered by the bridge
+        let rx = Packet::<FLEM_PACKET_SIZE>::with_payload(0x30, &[1]).unwrap();
+        let mut outputs: [&mut dyn ByteChannel; 2] = [&mut first, &mut second];
+        assert_eq!(router.forward(&rx, &mut tx, &mut outputs), Ok(None));
+        assert_eq!(tx.get_request(), 0x30);
+        assert_eq!(tx.get_response(), flem::response::UNKNOWN_REQUEST);
+
+        // A rule naming a missing output
+        router.add_rule(0x30..=0x30, 2).unwrap();
+        assert_eq!(
+            router.forward(&rx, &mut tx, &mut outputs),
+            Err(Status::UnspecifiedError)
+        );
+        assert_eq!(first.0, expected[0], "Nothing more is forwarded");
+        assert_eq!(second.0, expected[1], "Nothing more is forwarded");
+        router.add_rule(0x40..=0x40, 0).unwrap();
+        assert_eq!(router.add_rule(0x50..=0x50, 0), Err(Status::PacketOverflow));
+    }
+
+    #[test]
+    fn sync_words_isolate_channels() {
+        const MOTOR: u16 = 0x5A5A;
+        const SENSOR: u16 = 0xA5A5;
+
+        let mut wire = Vec::new();
+        for index in 0..3u8 {
+            let mut motor = flem::Packet::<FLEM_PACKET_SIZE>::new();
+            motor.set_sync_word(MOTOR);
+            motor.pack_data(0x10, &[index]).unwrap();
+            assert_eq!(motor.get_header(), MOTOR);
+            wire.extend_from_slice(motor.bytes());
+
+            let mut sensor = flem::Packet::<FLEM_PACKET_SIZE>::new();
+            sensor.set_sync_word(SENSOR);
+            sensor.pack_data(0x20, &[index + 10]).unwrap();
+            wire.extend_from_slice(sensor.bytes());
+        }
+
+        let receive = |sync_word: u16| {
+            let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+            rx.set_sync_word(sync_word);
+            let mut received = Vec::new();
+            for byte in wire.iter() {
+                if rx.construct(*byte).is_ok() {
+                    received.push((rx.get_request(), rx.payload()[0]));
+                }
+            }
+            assert_eq!(rx.stats().checksum_errors, 0);
+            received
+        };
+
+        assert_eq!(receive(MOTOR), [(0x10, 0), (0x10, 1), (0x10, 2)]);
+        assert_eq!(receive(SENSOR), [(0x20, 10), (0x20, 11), (0x20, 12)]);
+        assert!(receive(flem::FLEM_HEADER).is_empty());
+
+        // The sync word survives resets
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.set_sync_word(MOTOR);
+        tx.reset();
+        tx.pack_data(0x10, &[]).unwrap();
+        assert_eq!(tx.get_header(), MOTOR);
+    }
+
+    #[test]
+    fn sync_word_stray_byte_before_frame() {
+        use flem::packet_be::PacketBe;
+        use flem::Status;
+
+        const SYNC_WORD: u16 = 0xA55A;
+
+        // The stray byte equals the first header byte, 0x5A little endian
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.set_sync_word(SYNC_WORD);
+        tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+        let mut wire = vec![0x5A];
+        wire.extend_from_slice(tx.bytes());
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        rx.set_sync_word(SYNC_WORD);
+        assert_eq!(rx.construct(wire[0]), Err(Status::PacketBuilding));
+        assert_eq!(rx.construct(wire[1]), Err(Status::HeaderBytesNotFound));
+        assert_eq!(rx.construct_slice(&wire[2..]), Ok(wire.len() - 2));
+        assert_eq!(rx.payload(), &[1, 2, 3]);
+        assert_eq!(rx.discarded_bytes(), 1, "Only the stray byte is dropped");
+
+        // Same for the byte header frames, 0xA5 first big endian
+        let mut tx = PacketBe::<FLEM_PACKET_SIZE>::new();
+        tx.set_sync_word(SYNC_WORD);
+        tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+        let mut wire = vec![0xA5; 1 + flem::FLEM_HEADER_SIZE + 3];
+        let length = tx.write_wire(&mut wire[1..]).unwrap();
+
+        let mut rx = PacketBe::<FLEM_PACKET_SIZE>::new();
+        rx.set_sync_word(SYNC_WORD);
+        assert_eq!(rx.construct_slice(&wire), Ok(1 + length));
+        assert_eq!(rx.payload(), &[1, 2, 3]);
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    #[test]
+    fn engine_answers_duplicates_from_cache() {
+        use flem::client::{Engine, EngineEvent};
+        use flem::dispatch::Dispatcher;
+        use flem::{Packet, Status};
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        const START_MOTOR: u16 = 0x10;
+        static STARTS: AtomicU32 = AtomicU32::new(0);
+
+        type Rx = Packet<FLEM_PACKET_SIZE>;
+
+        fn start_motor(rx: &Rx, tx: &mut Rx) -> Result<(), Status> {
+            let starts = STARTS.fetch_add(1, Ordering::SeqCst) + 1;
+            tx.pack_data_seq(
+                rx.get_request(),
+                rx.get_transaction().unwrap(),
+                &[starts as u8],
+            )
+        }
+
+        let mut dispatcher = Dispatcher::<FLEM_PACKET_SIZE, 2>::new();
+        dispatcher.register(START_MOTOR, start_motor).unwrap();
+        let mut engine = Engine::<FLEM_PACKET_SIZE, 2, 2>::with_duplicate_detection(dispatcher);
+
+        let mut deliver = |request: &Rx| {
+            let mut incoming = request.bytes().iter().copied();
+            let mut outgoing = Vec::new();
+            let mut events = Vec::new();
+            for _ in 0..10 {
+                let event = engine.poll(
+                    || incoming.next(),
+                    |byte| {
+                        outgoing.push(byte);
+                        true
+                    },
+                );
+                if event != EngineEvent::Idle {
+                    events.push(event);
+                }
+            }
+            (events, outgoing)
+        };
+
+        let mut first = Rx::new();
+        first.pack_data_seq(START_MOTOR, 7, &[100]).unwrap();
+
+        let (events, response) = deliver(&first);
+        assert_eq!(events, [EngineEvent::RequestHandled(START_MOTOR)]);
+
+        // The response was lost, the host sends the same frame again
+        let (events, resent) = deliver(&first);
+        assert_eq!(events, [EngineEvent::Duplicate(START_MOTOR)]);
+        assert_eq!(resent, response, "The cached response is sent again");
+        assert_eq!(STARTS.load(Ordering::SeqCst), 1, "The handler ran once");
+
+        // A new transaction ID runs the handler, the older request is still remembered
+        let mut second = Rx::new();
+        second.pack_data_seq(START_MOTOR, 8, &[100]).unwrap();
+        assert_eq!(
+            deliver(&second).0,
+            [EngineEvent::RequestHandled(START_MOTOR)]
+        );
+        assert_eq!(
+            deliver(&first),
+            (vec![EngineEvent::Duplicate(START_MOTOR)], response)
+        );
+        assert_eq!(STARTS.load(Ordering::SeqCst), 2);
+
+        // Only the last 2 requests are remembered
+        let mut third = Rx::new();
+        third.pack_data_seq(START_MOTOR, 9, &[100]).unwrap();
+        assert_eq!(
+            deliver(&third).0,
+            [EngineEvent::RequestHandled(START_MOTOR)]
+        );
+        assert_eq!(
+            deliver(&first).0,
+            [EngineEvent::RequestHandled(START_MOTOR)]
+        );
+        assert_eq!(STARTS.load(Ordering::SeqCst), 4);
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    #[test]
+    fn engine_checks_only_tagged_requests() {
+        use flem::client::{Engine, EngineEvent};
+        use flem::dispatch::Dispatcher;
+        use flem::host::{ByteChannel, Host};
+        use flem::{Packet, Status};
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        const WRITE: u16 = 0x10;
+        static WRITES: AtomicU32 = AtomicU32::new(0);
+
+        type Rx = Packet<FLEM_PACKET_SIZE>;
+
+        fn write(rx: &Rx, tx: &mut Rx) -> Result<(), Status> {
+            WRITES.fetch_add(1, Ordering::SeqCst);
+            tx.pack_data(rx.get_request(), &[])
+        }
+
+        let mut dispatcher = Dispatcher::<FLEM_PACKET_SIZE, 2>::new();
+        dispatcher.register(WRITE, write).unwrap();
+        let mut engine = Engine::<FLEM_PACKET_SIZE, 2, 4>::with_duplicate_detection(dispatcher);
+
+        let mut deliver = |bytes: &[u8]| {
+            let mut incoming = bytes.iter().copied();
+            let mut events = Vec::new();
+            for _ in 0..10 {
+                match engine.poll(|| incoming.next(), |_| true) {
+                    EngineEvent::Idle => {}
+                    event => events.push(event),
+                }
+            }
+            events
+        };
+
+        // Writing the same value twice isn't a duplicate, the first byte is data
+        let mut untagged = Rx::new();
+        untagged.pack_data(WRITE, &[7, 0xFF]).unwrap();
+        assert_eq!(untagged.get_tagged_transaction(), None);
+        assert_eq!(
+            deliver(untagged.bytes()),
+            [EngineEvent::RequestHandled(WRITE)]
+        );
+        assert_eq!(
+            deliver(untagged.bytes()),
+            [EngineEvent::RequestHandled(WRITE)]
+        );
+        assert_eq!(WRITES.load(Ordering::SeqCst), 2);
+
+        // The host tags its requests with a counter
+        struct Sent(Vec<u8>);
+
+        impl ByteChannel for Sent {
+            fn write(&mut self, bytes: &[u8]) -> Result<(), Status> {
+                self.0 = bytes.to_vec();
+                Ok(())
+            }
+
+            fn read(&mut self) -> Option<u8> {
+                None
+            }
+        }
+
+        let mut channel = Sent(Vec::new());
+        let mut host = Host::<FLEM_PACKET_SIZE>::new();
+        host.set_read_budget(1);
+        host.set_transactions(true);
+        for transaction in 0..2 {
+            assert!(host.request(&mut channel, WRITE, &[7, 0xFF]).is_err());
+            assert_eq!(host.tx().get_tagged_transaction(), Some(transaction));
+            assert_eq!(host.tx().payload(), &[transaction, 7, 0xFF]);
+            assert_eq!(deliver(&channel.0), [EngineEvent::RequestHandled(WRITE)]);
+        }
+
+        // Sent again after a lost response, answered from the cache
+        assert_eq!(deliver(&channel.0), [EngineEvent::Duplicate(WRITE)]);
+        assert_eq!(WRITES.load(Ordering::SeqCst), 4);
+
+        // A packet reused for an untagged request loses the flag with its payload
+        let mut tx = *host.tx();
+        tx.pack_data(WRITE, &[1]).unwrap();
+        assert_eq!(tx.get_tagged_transaction(), None);
+
+        host.set_transactions(false);
+        assert!(host.request(&mut channel, WRITE, &[1]).is_err());
+        assert_eq!(host.tx().payload(), &[1]);
+        assert!(!host.tx().has_flag(flem::flags::TRANSACTION));
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    #[test]
+    fn host_retry_answered_from_cache() {
+        use flem::client::Engine;
+        use flem::dispatch::Dispatcher;
+        use flem::host::{ByteChannel, Host};
+        use flem::{Packet, Status};
+        use std::collections::VecDeque;
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        const START_MOTOR: u16 = 0x10;
+        static STARTS: AtomicU32 = AtomicU32::new(0);
+
+        type Rx = Packet<FLEM_PACKET_SIZE>;
+
+        fn start_motor(rx: &Rx, tx: &mut Rx) -> Result<(), Status> {
+            let starts = STARTS.fetch_add(1, Ordering::SeqCst) + 1;
+            tx.pack_data_seq(
+                rx.get_request(),
+                rx.get_transaction().unwrap(),
+                &[starts as u8],
+            )
+        }
+
+        // A link that can hold back a response until the host writes again
+        struct Late {
+            engine: Engine<FLEM_PACKET_SIZE, 2, 2>,
+            to_client: VecDeque<u8>,
+            to_host: VecDeque<u8>,
+            held: Vec<u8>,
+            hold_next: bool,
+        }
+
+        impl ByteChannel for Late {
+            fn write(&mut self, bytes: &[u8]) -> Result<(), Status> {
+                self.to_client.extend(bytes);
+                self.to_host.extend(self.held.drain(..));
+                Ok(())
+            }
+
+            fn read(&mut self) -> Option<u8> {
+                if self.to_host.is_empty() {
+                    let to_client = &mut self.to_client;
+                    let to_host = &mut self.to_host;
+                    self.engine.poll(
+                        || to_client.pop_front(),
+                        |byte| {
+                            to_host.push_back(byte);
+                            true
+                        },
+                    );
+                    if self.hold_next && !to_host.is_empty() {
+                        self.held.extend(to_host.drain(..));
+                        self.hold_next = false;
+                    }
+                }
+                self.to_host.pop_front()
+            }
+        }
+
+        let mut dispatcher = Dispatcher::<FLEM_PACKET_SIZE, 2>::new();
+        dispatcher.register(START_MOTOR, start_motor).unwrap();
+        let mut channel = Late {
+            engine: Engine::with_duplicate_detection(dispatcher),
+            to_client: VecDeque::new(),
+            to_host: VecDeque::new(),
+            held: Vec::new(),
+            hold_next: true,
+        };
+
+        let mut host = Host::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(
+            host.retry(&mut channel).err(),
+            Some(Status::UnspecifiedError)
+        );
+
+        host.set_transactions(true);
+        assert_eq!(
+            host.request(&mut channel, START_MOTOR, &[]).err(),
+            Some(Status::NoResponse)
+        );
+        assert_eq!(STARTS.load(Ordering::SeqCst), 1);
+
+        // The retry keeps transaction ID 0, the late response answers it
+        let rx = host.retry(&mut channel).unwrap();
+        assert_eq!(rx.payload(), &[0, 1]);
+        assert_eq!(host.tx().get_tagged_transaction(), Some(0));
+
+        // The client answers the resent request from its cache. That response is still
+        // queued when the next request is sent and is skipped, it has transaction ID 0.
+        let rx = host.request(&mut channel, START_MOTOR, &[]).unwrap();
+        assert_eq!(rx.payload(), &[1, 2]);
+        assert_eq!(
+            STARTS.load(Ordering::SeqCst),
+            2,
+            "The handler ran once per ID"
+        );
+    }
+
+    #[test]
+    fn explain_reports_checksum_mismatch() {
+        use flem::Status;
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0x10, &[1, 2, 3, 4]).unwrap();
+        let packed = tx.explain();
+        assert!(packed.checksum_matches());
+        assert_eq!(packed.header, flem::FLEM_HEADER);
+        assert_eq!(packed.length, 4);
+        assert_eq!(packed.payload_received, 4);
+
+        let mut corrupted = tx.bytes().to_vec();
+        corrupted[2] ^= 0x01;
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+
+        // Halfway through the frame
+        let half = flem::FLEM_HEADER_SIZE + 2;
+        assert_eq!(
+            rx.construct_slice(&corrupted[..half]),
+            Err(Status::PacketBuilding)
+        );
+        let building = rx.explain();
+        assert_eq!(building.status, Status::PacketBuilding);
+        assert_eq!(building.byte_index, half as u32);
+        assert_eq!(building.payload_received, 2);
+        assert_eq!(building.computed_checksum, None);
+        assert!(building.to_string().contains("crc="));
+
+        assert_eq!(
+            rx.construct_slice(&corrupted[half..]),
+            Err(Status::ChecksumError)
+        );
+        let failed = rx.explain();
+        assert_eq!(failed.status, Status::ChecksumError);
+        assert_eq!(failed.stored_checksum, tx.get_checksum() ^ 0x01);
+        assert_eq!(failed.computed_checksum, Some(tx.get_checksum()));
+        assert!(!failed.checksum_matches());
+        assert_eq!(failed.payload_received, 4);
+
+        let line = failed.to_string();
+        assert!(!line.contains('\n'));
+        assert!(line.contains(&format!(
+            "crc={:#06x}/{:#06x}",
+            failed.stored_checksum,
+            tx.get_checksum()
+        )));
+        assert!(line.ends_with("st=ChecksumError"));
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    #[test]
+    fn channels_filter_on_receive() {
+        const MOTOR: u8 = 3;
+        const SENSOR: u8 = 5;
+
+        // Interleave packets for two subsystems on one link
+        let mut wire = Vec::new();
+        for (index, channel) in [MOTOR, SENSOR, MOTOR, SENSOR].iter().enumerate() {
+            let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+            tx.set_channel(*channel);
+            tx.pack_data(0x10, &[index as u8]).unwrap();
+            assert_eq!(tx.get_channel(), *channel, "Channel should survive packing");
+            wire.extend_from_slice(tx.bytes());
+        }
+
+        // The motor subsystem only keeps its own packets
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let mut motor = Vec::new();
+        for byte in &wire {
+            if rx.construct(*byte).is_ok() {
+                if rx.get_channel() == MOTOR {
+                    motor.push(rx.payload()[0]);
+                }
+                rx.reset_lazy();
+            }
+        }
+        assert_eq!(motor, [0, 2], "Only the motor packets should be kept");
+
+        // The channel is covered by the checksum
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.set_channel(MOTOR);
+        tx.pack_data(0x10, &[1]).unwrap();
+        let mut frame = tx.bytes().to_vec();
+        frame[13] = SENSOR;
+        rx.reset_lazy();
+        assert_eq!(
+            rx.construct_slice(&frame),
+            Err(flem::Status::ChecksumError),
+            "A changed channel should fail the checksum"
+        );
+
+        // reset() returns to the default channel
+        tx.reset();
+        assert_eq!(tx.get_channel(), 0);
+    }
+
+    #[test]
+    fn respond_to_echoes_request() {
+        const CUSTOM_REQUEST: u16 = 0x1234;
+
+        let mut host_tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        host_tx.pack_data(CUSTOM_REQUEST, &[1, 2]).unwrap();
+        let mut client_rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        client_rx.construct_slice(host_tx.bytes()).unwrap();
+
+        // A stale response in the tx packet is replaced
+        let mut client_tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        client_tx
+            .pack_error_code(0x20, flem::response::UNKNOWN_REQUEST)
+            .unwrap();
+        client_tx.respond_to(&client_rx);
+        client_tx.add_data(&[3]).unwrap();
+        client_tx.pack();
+
+        let mut host_rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        host_rx.construct_slice(client_tx.bytes()).unwrap();
+        assert_eq!(
+            host_rx.get_request(),
+            CUSTOM_REQUEST,
+            "Request should be echoed"
+        );
+        assert_eq!(host_rx.get_response(), flem::response::SUCCESS);
+        assert_eq!(host_rx.payload(), [3]);
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn pod_payload_round_trip() {
+        #[repr(C)]
+        #[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+        struct Sensor {
+            id: u8,
+            state: u8,
+            raw: u16,
+            timestamp: u32,
+            volts: f32,
+        }
+
+        let sensor = Sensor {
+            id: 3,
+            state: 1,
+            raw: 0x0ABC,
+            timestamp: 123_456,
+            volts: 3.3,
+        };
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.set_request(0x10);
+        tx.write_u8(0xEE).unwrap();
+        tx.add_pod(&sensor).unwrap();
+        tx.pack();
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        rx.construct_slice(tx.bytes()).unwrap();
+        // Offset 1, the struct isn't aligned in the payload
+        assert_eq!(rx.read_pod::<Sensor>(1), Ok(sensor));
+        assert_eq!(
+            rx.read_pod::<Sensor>(2),
+            Err(flem::Status::PayloadUnderflow),
+            "Read past the payload should fail"
+        );
+        assert_eq!(
+            rx.read_pod::<u32>(usize::MAX),
+            Err(flem::Status::PayloadUnderflow)
+        );
+
+        let mut small = flem::Packet::<8>::new();
+        assert_eq!(small.add_pod(&sensor), Err(flem::Status::PacketOverflow));
+        assert!(small.payload().is_empty(), "Nothing should be added");
+    }
+
+    #[test]
+    fn write_hex_of_known_packet() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0x0F, &[0x01, 0x02, 0xAB]).unwrap();
+
+        let mut hex = heapless::String::<64>::new();
+        tx.write_hex(&mut hex).unwrap();
+        // Header, checksum, request, response, length, (sequence, flags, channel,) data
+        #[cfg(not(feature = "protocol-v2"))]
+        assert_eq!(hex.as_str(), "55 55 04 2F 0F 00 01 00 03 00 01 02 AB");
+        #[cfg(feature = "protocol-v2")]
+        assert_eq!(
+            hex.as_str(),
+            "55 AA 15 1C 0F 00 01 00 03 00 00 00 00 00 01 02 AB"
+        );
+
+        // A sink that runs out of room reports the error
+        let mut short = heapless::String::<8>::new();
+        assert!(tx.write_hex(&mut short).is_err());
+    }
+
+    #[cfg(feature = "dfu")]
+    #[test]
+    fn dfu_image_over_loopback() {
+        use flem::dfu::{DfuBackend, DfuError, DfuHost};
+        use flem::dispatch::Dispatcher;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Mutex;
+
+        const FLASH_SIZE: usize = 256;
+
+        static FLASH: Mutex<[u8; FLASH_SIZE]> = Mutex::new([0xFF; FLASH_SIZE]);
+        static BOOTED: AtomicBool = AtomicBool::new(false);
+
+        // RAM backed flash, writes past the end fail with OUT_OF_RANGE
+        struct RamFlash;
+
+        impl DfuBackend for RamFlash {
+            fn erase() -> Result<(), u16> {
+                *FLASH.lock().unwrap() = [0xFF; FLASH_SIZE];
+                Ok(())
+            }
+
+            fn write(offset: u32, data: &[u8]) -> Result<(), u16> {
+                let start = offset as usize;
+                let mut flash = FLASH.lock().unwrap();
+                let target = flash
+                    .get_mut(start..start + data.len())
+                    .ok_or(flem::response::OUT_OF_RANGE)?;
+                target.copy_from_slice(data);
+                Ok(())
+            }
+
+            fn read(offset: u32, out: &mut [u8]) -> Result<(), u16> {
+                let start = offset as usize;
+                let flash = FLASH.lock().unwrap();
+                let source = flash
+                    .get(start..start + out.len())
+                    .ok_or(flem::response::OUT_OF_RANGE)?;
+                out.copy_from_slice(source);
+                Ok(())
+            }
+
+            fn boot() -> Result<(), u16> {
+                BOOTED.store(true, Ordering::SeqCst);
+                Ok(())
+            }
+        }
+
+        let mut dispatcher = Dispatcher::<FLEM_PACKET_SIZE, 4>::new();
+        flem::dfu::register::<RamFlash, FLEM_PACKET_SIZE, 4>(&mut dispatcher).unwrap();
+        let mut channel = Loopback::new(dispatcher);
+
+        // 250 bytes in chunks of 70: writes are split at the packet size and the last
+        // chunk and the last write are partial
+        let image: Vec<u8> = (0..250).map(|i| (i * 7) as u8).collect();
+        let mut dfu = DfuHost::<FLEM_PACKET_SIZE>::new();
+        let verify = dfu.send_image(&mut channel, image.chunks(70)).unwrap();
+        assert_eq!(verify.length, 250);
+        assert_eq!(
+            &FLASH.lock().unwrap()[..250],
+            image.as_slice(),
+            "Image not written"
+        );
+        assert_eq!(
+            &FLASH.lock().unwrap()[250..],
+            &[0xFF; 6],
+            "Rest should stay erased"
+        );
+
+        assert_eq!(
+            verify.crc,
+            flem::checksum::ChecksumAlgorithm::Crc16Ibm.compute(&[&image]),
+            "Verify should carry the CRC-16/IBM of the image"
+        );
+
+        // A wrong CRC is answered with the CRC of the written image
+        let wrong = flem::dfu::Verify {
+            length: 250,
+            crc: !verify.crc,
+        };
+        let rx = dfu
+            .host_mut()
+            .request(&mut channel, flem::dfu::VERIFY, &wrong.to_bytes())
+            .unwrap();
+        assert_eq!(rx.get_response(), flem::response::INVALID_ARGUMENT);
+        assert_eq!(rx.read_u16_le(0), Ok(verify.crc));
+
+        assert!(!BOOTED.load(Ordering::SeqCst));
+        dfu.boot(&mut channel).unwrap();
+        assert!(BOOTED.load(Ordering::SeqCst), "Backend should have booted");
+
+        // An image larger than the flash fails at the first write past the end
+        let large = [0xA5_u8; FLASH_SIZE + 50];
+        let write_size = (FLEM_PACKET_SIZE - flem::dfu::WRITE_OFFSET_SIZE) as u32;
+        let failing = (FLASH_SIZE as u32 / write_size) * write_size;
+        assert_eq!(
+            dfu.send_image(&mut channel, [&large[..]]),
+            Err(DfuError::Rejected {
+                request: flem::dfu::WRITE,
+                response: flem::response::OUT_OF_RANGE,
+                offset: failing,
+            })
+        );
+    }
+
+    #[test]
+    fn construct_strict_rejects_trailing_bytes() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+
+        // One fixed-size transfer with a stray byte after the packet
+        let mut transfer = tx.bytes().to_vec();
+        transfer.push(0x55);
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        let results: Vec<_> = transfer
+            .iter()
+            .map(|byte| rx.construct_strict(*byte))
+            .collect();
+        assert_eq!(results[tx.length() - 1], Ok(()), "Packet should complete");
+        assert_eq!(
+            results[tx.length()],
+            Err(flem::Status::PacketOverflow),
+            "The trailing byte should be rejected"
+        );
+        assert_eq!(rx.get_status(), flem::Status::PacketReceived);
+        assert_eq!(
+            rx.payload(),
+            [1, 2, 3],
+            "The received packet should be kept"
+        );
+
+        // The next transfer after a reset
+        rx.reset_lazy();
+        for byte in tx.bytes() {
+            let _ = rx.construct_strict(*byte);
+        }
+        assert_eq!(rx.get_status(), flem::Status::PacketReceived);
+    }
+
+    #[test]
+    fn log_packets_interleaved_with_response() {
+        use core::fmt::Write;
+        use flem::console::{LogCollector, LogSink};
+        use flem::correlation::{Correlation, Correlator};
+        use flem::host::{ByteChannel, Host};
+        use flem::{Packet, Status};
+        use std::collections::VecDeque;
+
+        const READ: u16 = 0x10;
+
+        // The client flushes its log after the request arrived, before the response
+        struct Client {
+            sink: LogSink<FLEM_PACKET_SIZE>,
+            to_host: VecDeque<u8>,
+        }
+
+        impl ByteChannel for Client {
+            fn write(&mut self, bytes: &[u8]) -> Result<(), Status> {
+                let (request, _) = Packet::<FLEM_PACKET_SIZE>::from_bytes(bytes)?;
+                let mut tx = Packet::<FLEM_PACKET_SIZE>::new();
+
+                // One line split across two log packets
+                write!(self.sink, "Motor current ").unwrap();
+                self.sink.flush(&mut tx).unwrap();
+                self.to_host.extend(tx.bytes());
+                writeln!(self.sink, "{} mA\r", 420).unwrap();
+                writeln!(self.sink, "Limit reached").unwrap();
+                self.sink.flush(&mut tx).unwrap();
+                self.to_host.extend(tx.bytes());
+
+                tx.respond_to(&request);
+                tx.add_data(&[0xAB]).unwrap();
+                tx.pack();
+                self.to_host.extend(tx.bytes());
+                Ok(())
+            }
+
+            fn read(&mut self) -> Option<u8> {
+                self.to_host.pop_front()
+            }
+        }
+
+        let mut channel = Client {
+            sink: LogSink::new(),
+            to_host: VecDeque::new(),
+        };
+        let mut host = Host::<FLEM_PACKET_SIZE>::new();
+        let mut collector = LogCollector::<64>::new();
+        let mut lines = Vec::new();
+
+        let response = host
+            .request_with_events(&mut channel, READ, &[], |event| {
+                assert!(collector.collect(event, |line| lines.push(line.to_string())));
+            })
+            .unwrap();
+        assert_eq!(response.get_request(), READ);
+        assert_eq!(response.payload(), [0xAB]);
+        assert_eq!(lines, ["Motor current 420 mA", "Limit reached"]);
+        assert!(collector.pending().is_empty());
+        assert!(
+            !collector.collect(&response, |_| {}),
+            "A response isn't a log packet"
+        );
+
+        // Without an event handler the log packets are skipped
+        assert_eq!(
+            host.request(&mut channel, READ, &[]).unwrap().payload(),
+            [0xAB]
+        );
+
+        // The correlator doesn't take log packets for responses
+        let mut correlator = Correlator::<2>::new();
+        correlator.begin(READ).unwrap();
+        let mut log = Packet::<FLEM_PACKET_SIZE>::new();
+        log.pack_async(flem::request::LOG, b"boot\n").unwrap();
+        assert_eq!(correlator.complete(&log), Correlation::Unsolicited);
+        assert_eq!(correlator.orphans(), 0);
+        assert_eq!(correlator.outstanding(), 1);
+        assert!(matches!(
+            correlator.complete(&response),
+            Correlation::Untagged { .. }
+        ));
+    }
+
+    #[test]
+    fn cursors_advance() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0x10, &[1, 2, 3, 4]).unwrap();
+        assert_eq!(tx.tx_cursor(), 0);
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(rx.rx_cursor(), 0);
+        for sent in 1..=tx.length() {
+            let byte = tx.get_byte().unwrap();
+            assert_eq!(tx.tx_cursor(), sent);
+
+            let _ = rx.construct(byte);
+            assert_eq!(rx.rx_cursor(), sent);
+        }
+        assert_eq!(rx.get_status(), flem::Status::PacketReceived);
+        assert_eq!(tx.get_byte(), Err(flem::Status::GetByteFinished));
+        assert_eq!(tx.tx_cursor(), tx.length(), "Cursor should stop at the end");
+
+        // Bulk transmit advances by the bytes copied
+        let mut tx = tx.clone_wire();
+        let mut fifo = [0u8; 5];
+        assert_eq!(tx.get_bytes_into(&mut fifo), 5);
+        assert_eq!(tx.tx_cursor(), 5);
+
+        // A failed frame starts over
+        rx.reset_lazy();
+        let _ = rx.construct(0x00);
+        assert_eq!(rx.rx_cursor(), 0);
+    }
+
+    #[test]
+    fn heartbeat_detects_dropped_beats() {
+        use flem::heartbeat::{Heartbeat, HeartbeatMonitor};
+
+        const INTERVAL: u32 = 100;
+        const TIMEOUT: u32 = 250;
+
+        let mut heartbeat = Heartbeat::<FLEM_PACKET_SIZE>::new(INTERVAL);
+        let mut monitor = HeartbeatMonitor::new();
+        assert!(!monitor.is_alive(0, TIMEOUT), "No heartbeat received yet");
+
+        // Ticks every 10, the beats at 100 and 200 are lost on the link
+        let mut sent = Vec::new();
+        for now in (0..=300).step_by(10) {
+            if let Some(beat) = heartbeat.tick(now) {
+                sent.push(now);
+                assert_eq!(beat.get_request(), flem::request::HEARTBEAT);
+                assert!(beat.is_async());
+                if now != 100 && now != 200 {
+                    assert!(monitor.observe(&beat, now));
+                }
+            }
+            if now == 90 {
+                assert!(monitor.is_alive(now, TIMEOUT));
+            }
+            if now == 190 {
+                assert!(monitor.is_alive(now, TIMEOUT), "Within the timeout");
+            }
+            if now == 260 {
+                assert!(
+                    !monitor.is_alive(now, TIMEOUT),
+                    "Should time out after the drops"
+                );
+            }
+        }
+        assert_eq!(sent, [0, 100, 200, 300]);
+        assert!(
+            monitor.is_alive(300, TIMEOUT),
+            "Alive again after the next beat"
+        );
+        assert_eq!(monitor.missed(), 2);
+        assert_eq!(monitor.last_seen(), Some(300));
+
+        // Other packets are left alone
+        let response = flem::Packet::<FLEM_PACKET_SIZE>::with_payload(0x10, &[0, 0]).unwrap();
+        assert!(!monitor.observe(&response, 310));
+
+        // A repeated or reordered beat isn't counted as missed beats
+        let beat = |count: u16| {
+            let mut packet = flem::Packet::<FLEM_PACKET_SIZE>::new();
+            packet
+                .pack_async(flem::request::HEARTBEAT, &count.to_le_bytes())
+                .unwrap();
+            packet
+        };
+        assert!(monitor.observe(&beat(3), 310));
+        assert!(monitor.observe(&beat(3), 320));
+        assert!(monitor.observe(&beat(2), 330));
+        assert_eq!(monitor.missed(), 2);
+        assert_eq!(monitor.last_seen(), Some(330));
+        assert!(monitor.observe(&beat(5), 340));
+        assert_eq!(monitor.missed(), 3, "Only count 4 is missing");
+
+        // Ticks wrap around u32
+        let mut heartbeat = Heartbeat::<FLEM_PACKET_SIZE>::new(INTERVAL);
+        assert!(heartbeat.tick(u32::MAX - 10).is_some());
+        assert!(heartbeat.tick(50).is_none());
+        assert!(heartbeat.tick(89).is_some());
+    }
+
+    #[test]
+    fn from_parts_builds_packed_packets() {
+        let data = [0x5A; FLEM_PACKET_SIZE];
+
+        let packet = flem::Packet::<FLEM_PACKET_SIZE>::from_parts(
+            0x10,
+            flem::response::OUT_OF_RANGE,
+            &data[..10],
+        )
+        .unwrap();
+        assert_eq!(packet.get_request(), 0x10);
+        assert_eq!(packet.get_response(), flem::response::OUT_OF_RANGE);
+        assert_eq!(packet.get_length(), 10);
+        assert_eq!(packet.payload(), &data[..10]);
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(rx.construct_slice(packet.bytes()), Ok(packet.length()));
+        assert_eq!(rx, packet, "The packet should be packed");
+
+        // Exactly full, then one byte too many
+        let full = flem::Packet::<FLEM_PACKET_SIZE>::from_parts(0x10, 1, &data).unwrap();
+        assert_eq!(full.payload().len(), FLEM_PACKET_SIZE);
+        let mut too_long = data.to_vec();
+        too_long.push(0);
+        assert_eq!(
+            flem::Packet::<FLEM_PACKET_SIZE>::from_parts(0x10, 1, &too_long).err(),
+            Some(flem::Status::PacketOverflow)
+        );
+    }
+
+    #[test]
+    fn payload_traits() {
+        fn sum(data: impl AsRef<[u8]>) -> u32 {
+            data.as_ref().iter().map(|byte| *byte as u32).sum()
+        }
+
+        let mut packet = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        packet.pack_data(0x10, &[1, 2, 3]).unwrap();
+        assert_eq!(sum(packet), 6);
+        assert_eq!(packet[0], 1);
+        assert_eq!(&packet[1..3], &[2, 3]);
+        assert_eq!(&packet[..], &[1, 2, 3]);
+
+        packet[2] = 30;
+        packet[..2].copy_from_slice(&[10, 20]);
+        assert_eq!(packet.payload(), [10, 20, 30]);
+        assert_eq!(
+            packet.get_length(),
+            3,
+            "Writing in place doesn't change the length"
+        );
+        packet.pack();
+        assert!(flem::validate_slice(packet.bytes()).is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn index_past_payload_panics() {
+        let packet = flem::Packet::<FLEM_PACKET_SIZE>::with_payload(0x10, &[1, 2, 3]).unwrap();
+        // Within T, but past the length field
+        let _ = packet[3];
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range for slice of length 3")]
+    fn index_mut_past_payload_panics() {
+        let mut packet = flem::Packet::<FLEM_PACKET_SIZE>::with_payload(0x10, &[1, 2, 3]).unwrap();
+        packet[2..5].fill(0);
+    }
+
+    /// Pack / construct round trip over random requests, responses and payloads, for a
+    /// packet size `$size`. The payload length is drawn from 0..=$size and shrinks towards
+    /// an empty payload, so a failure is reported with the shortest payload that fails.
+    macro_rules! roundtrip_proptest {
+        ($name:ident, $size:expr) => {
+            proptest::proptest! {
+                #[test]
+                fn $name(
+                    request: u16,
+                    response: u16,
+                    payload in proptest::collection::vec(proptest::num::u8::ANY, 0..=$size),
+                ) {
+                    let tx = flem::Packet::<$size>::from_parts(request, response, &payload)
+                        .unwrap();
+
+                    let mut rx = flem::Packet::<$size>::new();
+                    let (last, body) = tx.bytes().split_last().unwrap();
+                    for byte in body {
+                        proptest::prop_assert_eq!(
+                            rx.construct(*byte),
+                            Err(flem::Status::PacketBuilding)
+                        );
+                    }
+                    proptest::prop_assert_eq!(rx.construct(*last), Ok(()));
+                    proptest::prop_assert_eq!(rx.get_status(), flem::Status::PacketReceived);
+
+                    proptest::prop_assert_eq!(rx, tx);
+                    proptest::prop_assert_eq!(rx.get_request(), request);
+                    proptest::prop_assert_eq!(rx.get_response(), response);
+                    proptest::prop_assert_eq!(rx.payload(), payload.as_slice());
+                }
+            }
+        };
+    }
+
+    roundtrip_proptest!(roundtrip_prop_1, 1);
+    roundtrip_proptest!(roundtrip_prop_16, 16);
+    roundtrip_proptest!(roundtrip_prop_108, FLEM_PACKET_SIZE);
+    roundtrip_proptest!(roundtrip_prop_1024, 1024);
+
+    #[test]
+    fn dispatch_static_matches_dispatcher() {
+        use flem::dispatch::{dispatch_static, is_sorted_table, Dispatcher, Handler};
+        use flem::{DataId, Packet, Status};
+
+        type Rx = Packet<FLEM_PACKET_SIZE>;
+
+        const GET_DATA: u16 = 0x10;
+        const SET_DATA: u16 = 0x20;
+
+        fn id(_rx: &Rx, tx: &mut Rx) -> Result<(), Status> {
+            tx.pack_id(&DataId::new("Static", 1, 0, 0, FLEM_PACKET_SIZE), true)
+        }
+
+        fn get_data(rx: &Rx, tx: &mut Rx) -> Result<(), Status> {
+            tx.pack_data(rx.get_request(), &[0xAA])
+        }
+
+        fn set_data(rx: &Rx, tx: &mut Rx) -> Result<(), Status> {
+            tx.pack_data(rx.get_request(), rx.payload())
+        }
+
+        static HANDLERS: &[(u16, Handler<FLEM_PACKET_SIZE>)] = &[
+            (flem::request::ID, id),
+            (GET_DATA, get_data),
+            (SET_DATA, set_data),
+        ];
+        static EMPTY: &[(u16, Handler<FLEM_PACKET_SIZE>)] = &[];
+        const _: () = assert!(is_sorted_table(HANDLERS));
+        const _: () = assert!(is_sorted_table(EMPTY));
+
+        let mut dispatcher = Dispatcher::<FLEM_PACKET_SIZE, 4>::new();
+        for (request, handler) in HANDLERS {
+            dispatcher.register(*request, *handler).unwrap();
+        }
+
+        // Both paths give the same responses, unknown requests included
+        for request in [flem::request::ID, GET_DATA, SET_DATA, 0x30] {
+            let rx = Rx::with_payload(request, &[1, 2]).unwrap();
+            let mut from_static = Rx::new();
+            let mut from_dispatcher = Rx::new();
+            dispatch_static(HANDLERS, &rx, &mut from_static).unwrap();
+            dispatcher.dispatch(&rx, &mut from_dispatcher).unwrap();
+            assert_eq!(from_static, from_dispatcher, "Request {:#x}", request);
+        }
+
+        // Everything is unknown to an empty table
+        let rx = Rx::with_payload(GET_DATA, &[]).unwrap();
+        let mut tx = Rx::new();
+        dispatch_static(EMPTY, &rx, &mut tx).unwrap();
+        assert_eq!(tx.get_response(), flem::response::UNKNOWN_REQUEST);
+        assert_eq!(tx.get_request(), GET_DATA);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "sorted by request code without duplicates")]
+    fn dispatch_static_rejects_overlapping_codes() {
+        use flem::dispatch::{dispatch_static, is_sorted_table, Handler};
+        use flem::{Packet, Status};
+
+        type Rx = Packet<FLEM_PACKET_SIZE>;
+
+        fn first(rx: &Rx, tx: &mut Rx) -> Result<(), Status> {
+            tx.pack_data(rx.get_request(), &[1])
+        }
+
+        fn second(rx: &Rx, tx: &mut Rx) -> Result<(), Status> {
+            tx.pack_data(rx.get_request(), &[2])
+        }
+
+        static OVERLAPPING: &[(u16, Handler<FLEM_PACKET_SIZE>)] = &[(0x10, first), (0x10, second)];
+        assert!(!is_sorted_table(OVERLAPPING));
+
+        let rx = Rx::with_payload(0x10, &[]).unwrap();
+        let mut tx = Rx::new();
+        let _ = dispatch_static(OVERLAPPING, &rx, &mut tx);
+    }
+
+    #[test]
+    fn data_id_packet_size_ok() {
+        let id = flem::DataId::new("Client", 0, 1, 0, FLEM_PACKET_SIZE);
+        assert!(
+            id.packet_size_ok::<FLEM_PACKET_SIZE>(),
+            "Same size should fit"
+        );
+        assert!(id.packet_size_ok::<{ FLEM_PACKET_SIZE + 1 }>());
+        assert!(
+            !id.packet_size_ok::<{ FLEM_PACKET_SIZE - 1 }>(),
+            "One byte too small"
+        );
+
+        // The size learned from an ID response
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_id(&flem::DataId::new("Large", 0, 1, 0, 512), true)
+            .unwrap();
+        let large = tx.parse_id().unwrap();
+        assert_eq!(large.get_max_packet_size(), 512);
+        assert!(!large.packet_size_ok::<FLEM_PACKET_SIZE>());
+        assert!(large.packet_size_ok::<512>());
+    }
+
+    #[test]
+    fn packet_be_round_trip() {
+        use flem::packet_be::PacketBe;
+
+        let mut tx = PacketBe::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0x0110, &[1, 2, 3]).unwrap();
+        assert!(tx.validate());
+
+        let mut wire = [0_u8; flem::FLEM_HEADER_SIZE + FLEM_PACKET_SIZE];
+        let length = tx.write_wire(&mut wire).unwrap();
+        assert_eq!(wire[..2], flem::FLEM_HEADER.to_be_bytes());
+        assert_eq!(wire[2..4], tx.get_checksum().to_be_bytes());
+        // Request, response and length, most significant byte first
+        assert_eq!(wire[4..10], [0x01, 0x10, 0x00, 0x01, 0x00, 0x03]);
+        assert_eq!(wire[flem::FLEM_HEADER_SIZE..length], [1, 2, 3]);
+
+        // The same bytes as `get_byte()` sends them
+        let mut sent = Vec::new();
+        while let Ok(byte) = tx.get_byte() {
+            sent.push(byte);
+        }
+        assert_eq!(sent, wire[..length]);
+
+        let mut rx = PacketBe::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(rx.construct_slice(&wire[..length]), Ok(length));
+        assert_eq!(rx.get_status(), flem::Status::PacketReceived);
+        assert_eq!(rx.get_request(), 0x0110);
+        assert_eq!(rx.get_response(), flem::response::SUCCESS);
+        assert_eq!(rx.payload(), &[1, 2, 3]);
+
+        wire[flem::FLEM_HEADER_SIZE] ^= 0x01;
+        assert_eq!(
+            rx.construct_slice(&wire[..length]),
+            Err(flem::Status::ChecksumError)
+        );
+    }
+
+    #[test]
+    fn packet_be_mixed_endianness_fails() {
+        use flem::packet_be::PacketBe;
+        use flem::Status;
+
+        // 0x5555 frames, the protocol v1 header, don't differ in the header bytes
+        let mut tx_le = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx_le.set_sync_word(0x5555);
+        let mut rx_le = tx_le;
+        let mut tx_be = PacketBe::<FLEM_PACKET_SIZE>::new();
+        tx_be.set_sync_word(0x5555);
+        let mut rx_be = tx_be;
+        let mut wire = [0_u8; flem::FLEM_HEADER_SIZE + FLEM_PACKET_SIZE];
+
+        // The length reads 0x0300 big endian
+        tx_le.pack_data(0x10, &[1, 2, 3]).unwrap();
+        assert_eq!(
+            rx_be.construct_slice(tx_le.bytes()),
+            Err(Status::InvalidDataLengthDetected)
+        );
+
+        // An empty payload gets past the length, the swapped checksum is caught
+        tx_be.pack_data(0x10, &[]).unwrap();
+        let checksum = tx_be.get_checksum();
+        assert_ne!(checksum >> 8, checksum & 0xFF);
+        let length = tx_be.write_wire(&mut wire).unwrap();
+        assert_eq!(
+            rx_le.construct_slice(&wire[..length]),
+            Err(Status::ChecksumError)
+        );
+
+        // Unless the two checksum bytes are equal, then the fields are read swapped
+        let request = (0x10..=u16::MAX)
+            .find(|request| {
+                tx_be.pack_data(*request, &[]).unwrap();
+                let checksum = tx_be.get_checksum();
+                checksum >> 8 == checksum & 0xFF
+            })
+            .unwrap();
+        let length = tx_be.write_wire(&mut wire).unwrap();
+        assert_eq!(rx_le.construct_slice(&wire[..length]), Ok(length));
+        assert_eq!(rx_le.get_request(), request.swap_bytes());
+
+        // The protocol-v2 header bytes differ between the byte orders
+        #[cfg(feature = "protocol-v2")]
+        {
+            let mut tx_be = PacketBe::<FLEM_PACKET_SIZE>::new();
+            tx_be.pack_data(0x10, &[1, 2, 3]).unwrap();
+            let length = tx_be.write_wire(&mut wire).unwrap();
+            let mut rx_le = flem::Packet::<FLEM_PACKET_SIZE>::new();
+            assert_eq!(rx_le.construct(wire[0]), Err(Status::HeaderBytesNotFound));
+            assert!(rx_le.construct_slice(&wire[..length]).is_err());
+        }
+    }
+
+    #[cfg(feature = "protocol-v2")]
+    #[test]
+    fn frame_encoder_splits_payload() {
+        use flem::{flags, fragment::FrameEncoder};
+
+        let payload: Vec<u8> = (0..500_u32).map(|i| (i * 7) as u8).collect();
+        let encoder = FrameEncoder::<64>::new(0x10, &payload).with_sequence(u16::MAX - 2);
+        assert_eq!(encoder.len(), 8);
+        let mut packets: Vec<flem::Packet<64>> = encoder.collect();
+
+        assert_eq!(packets.len(), 8);
+        for (index, packet) in packets.iter_mut().enumerate() {
+            assert!(packet.validate());
+            assert_eq!(packet.get_request(), 0x10);
+            assert_eq!(
+                packet.get_sequence(),
+                (u16::MAX - 2).wrapping_add(index as u16)
+            );
+            assert!(packet.has_flag(flags::FRAGMENT));
+            assert_eq!(packet.has_more_fragments(), index < 7);
+        }
+        assert_eq!(packets[7].payload().len(), 500 - 7 * 64);
+        let joined: Vec<u8> = packets.iter().flat_map(|p| p.payload().to_vec()).collect();
+        assert_eq!(joined, payload);
+
+        #[cfg(feature = "std")]
+        {
+            let mut reassembler = flem::reassembly::Reassembler::<64>::new();
+            let received: Vec<_> = packets.iter().map(|p| reassembler.push(p)).collect();
+            assert_eq!(received[7], Ok(Some(payload.clone())));
+        }
+
+        // A payload that fits one packet isn't a fragment
+        let single: Vec<_> = FrameEncoder::<64>::new(0x10, &payload[..64]).collect();
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].get_flags(), 0);
+
+        let empty: Vec<_> = FrameEncoder::<64>::new(0x10, &[]).collect();
+        assert_eq!(empty.len(), 1);
+        assert!(empty[0].payload().is_empty());
+    }
+
+    #[test]
+    fn discarded_bytes_counts_junk() {
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0x10, &[1, 2, 3]).unwrap();
+
+        // 17 junk bytes, including a first header byte that isn't followed by the second
+        let mut stream = vec![0x00, 0x13, 0xFF, 0x55, 0x00, 0xAA, 0x01];
+        stream.extend_from_slice(&[0x42; 10]);
+        assert_eq!(stream.len(), 17);
+        stream.extend_from_slice(tx.bytes());
+
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        assert_eq!(rx.construct_slice(&stream), Ok(stream.len()));
+        assert_eq!(rx.discarded_bytes(), 17);
+        assert_eq!(rx.stats().resync_bytes, 17);
+
+        rx.reset_lazy();
+        assert_eq!(
+            rx.discarded_bytes(),
+            17,
+            "reset_lazy() should keep the count"
+        );
+        rx.clear_discarded();
+        assert_eq!(rx.discarded_bytes(), 0);
+
+        // The payload of an oversized packet is counted as well
+        let mut large = flem::Packet::<64>::new();
+        large.pack_data(0x10, &[0x5A; 40]).unwrap();
+        let mut small = flem::Packet::<16>::new();
+        assert_eq!(
+            small.construct_slice(large.bytes()),
+            Err(flem::Status::InvalidDataLengthDetected)
+        );
+        for byte in &large.bytes()[flem::FLEM_HEADER_SIZE..] {
+            assert_eq!(
+                small.construct(*byte),
+                Err(flem::Status::DiscardingOversized)
+            );
+        }
+        assert_eq!(small.discarded_bytes(), 40);
+
+        small.reset();
+        assert_eq!(small.discarded_bytes(), 0, "reset() should clear the count");
+    }
+
+    /// Self-generated regression vectors: v1 header, little endian fields and CRC-16/IBM
+    /// over everything after the checksum, worked out from the documented wire format. They
+    /// catch changes to this crate's byte layout, they don't show compatibility with the
+    /// C/C++ implementation. Captures from the C/C++ implementation are still outstanding.
+    #[cfg(not(feature = "protocol-v2"))]
+    mod regression_vectors {
+        /// `pack_id(&DataId::new("FLEM C", 1, 2, 3, 108), true)`
+        pub const ID_ASCII: [u8; 40] = [
+            0x55, 0x55, 0xB9, 0x12, 0x01, 0x00, 0x01, 0x00, 0x1E, 0x00, 0x01, 0x02, 0x03, 0x6C,
+            0x00, 0x46, 0x4C, 0x45, 0x4D, 0x20, 0x43, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        /// `pack_data(0x10, &[0x01, 0x02, 0x03, 0xAB])`
+        pub const SUCCESS_WITH_DATA: [u8; 14] = [
+            0x55, 0x55, 0x20, 0xAE, 0x10, 0x00, 0x01, 0x00, 0x04, 0x00, 0x01, 0x02, 0x03, 0xAB,
+        ];
+        /// `pack_error_code(0x10, response::UNKNOWN_REQUEST)`
+        pub const UNKNOWN_REQUEST: [u8; 10] =
+            [0x55, 0x55, 0x03, 0x48, 0x10, 0x00, 0xFE, 0xFF, 0x00, 0x00];
+    }
+
+    #[cfg(not(feature = "protocol-v2"))]
+    #[test]
+    fn regression_vectors_match() {
+        use flem::checksum::Checksum;
+        use regression_vectors::*;
+
+        // The CRC-16/IBM check value
+        let mut crc = flem::checksum::Crc16Ibm::default();
+        crc.update(b"123456789");
+        assert_eq!(crc.finalize(), 0xBB3D);
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_id(&flem::DataId::new("FLEM C", 1, 2, 3, 108), true)
+            .unwrap();
+        assert_eq!(tx.bytes(), ID_ASCII);
+
+        tx.pack_data(0x10, &[0x01, 0x02, 0x03, 0xAB]).unwrap();
+        assert_eq!(tx.bytes(), SUCCESS_WITH_DATA);
+
+        tx.pack_error_code(0x10, flem::response::UNKNOWN_REQUEST)
+            .unwrap();
+        assert_eq!(tx.bytes(), UNKNOWN_REQUEST);
+
+        // The frames are received as they are
+        let mut rx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        for vector in [&ID_ASCII[..], &SUCCESS_WITH_DATA, &UNKNOWN_REQUEST] {
+            assert_eq!(rx.construct_slice(vector), Ok(vector.len()));
+            assert_eq!(
+                rx.get_checksum(),
+                u16::from_le_bytes([vector[2], vector[3]])
+            );
+            rx.reset_lazy();
+        }
+
+        rx.construct_slice(&ID_ASCII).unwrap();
+        let id = rx.parse_id().ok().unwrap();
+        let name: String = id.get_name().iter().take_while(|c| **c != '\0').collect();
+        assert_eq!(name, "FLEM C");
+        assert_eq!(id.get_max_packet_size(), 108);
+    }
+
+    #[test]
+    fn try_convert_between_sizes() {
+        let payload: Vec<u8> = (0..100_u32).map(|i| i as u8).collect();
+        let mut large = flem::Packet::<512>::new();
+        large.add_data(&payload).unwrap();
+        large.set_request(0x10);
+        large.set_response(0x22);
+        #[cfg(feature = "protocol-v2")]
+        {
+            large.set_sequence(7);
+            large.set_channel(3);
+        }
+        large.pack();
+
+        // Down, the payload fits
+        let mut small = large.try_convert::<128>().unwrap();
+        assert!(small.validate());
+        assert_eq!(small.get_request(), 0x10);
+        assert_eq!(small.get_response(), 0x22);
+        assert_eq!(small.payload(), &payload[..]);
+        assert_eq!(small.bytes(), large.bytes());
+        #[cfg(feature = "protocol-v2")]
+        {
+            assert_eq!(small.get_sequence(), 7);
+            assert_eq!(small.get_channel(), 3);
+        }
+
+        // Down, the payload doesn't fit
+        assert_eq!(
+            large.try_convert::<64>().err(),
+            Some(flem::Status::PacketOverflow)
+        );
+
+        // Up
+        let mut back = small.try_convert::<1024>().unwrap();
+        assert!(back.validate());
+        assert_eq!(back.bytes(), large.bytes());
+
+        let mut rx = flem::Packet::<1024>::new();
+        assert_eq!(rx.construct_slice(small.bytes()), Ok(small.length()));
+        assert_eq!(rx.payload(), &payload[..]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_postcard_round_trip() {
+        let mut buffer = [0_u8; 256];
+
+        let mut tx = flem::Packet::<FLEM_PACKET_SIZE>::new();
+        tx.pack_data(0x10, &[1, 2, 3, 4, 5]).unwrap();
+        let encoded = postcard::to_slice(&tx, &mut buffer).unwrap();
+        let mut rx: flem::Packet<FLEM_PACKET_SIZE> = postcard::from_bytes(encoded).unwrap();
+        assert!(
+            rx.validate(),
+            "Checksum should still validate after a round trip"
+        );
+        assert_eq!(rx.bytes(), tx.bytes());
+
+        let truncated = &encoded[..encoded.len() - 1];
+        assert!(postcard::from_bytes::<flem::Packet<FLEM_PACKET_SIZE>>(truncated).is_err());
+        assert!(
+            postcard::from_bytes::<flem::Packet<4>>(encoded).is_err(),
+            "Data longer than the packet should not deserialize"
+        );
+
+        let id = flem::DataId::new("Postcard", 1, 2, 3, FLEM_PACKET_SIZE);
+        let encoded = postcard::to_slice(&id, &mut buffer).unwrap();
+        let decoded: flem::DataId = postcard::from_bytes(encoded).unwrap();
+        assert_eq!(decoded.get_name(), id.get_name());
+        assert_eq!(
+            (
+                decoded.get_major(),
+                decoded.get_minor(),
+                decoded.get_patch()
+            ),
+            (1, 2, 3)
+        );
+        assert_eq!(decoded.get_max_packet_size() as usize, FLEM_PACKET_SIZE);
+    }
 }